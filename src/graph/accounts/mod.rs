@@ -12,7 +12,13 @@
 use crate::prelude::errors::ClientErr;
 use crate::prelude::search::Location;
 use crate::prelude::structs::Engagement;
-use crate::prelude::{HttpConnection, InstagramAccount};
+use crate::prelude::{
+    paginate, DefaultHttpClient, GenericClientConnection, InstagramAccount, PageOptions, Paging,
+};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::secret::Secret;
+use crate::universal::HttpClient;
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
 
 /// This struct represent the data that will be return from facebook page
@@ -22,7 +28,7 @@ use serde::{Deserialize, Serialize};
 pub struct PageAccount {
     /// The access token of this given page, which used to make operation that
     /// requires permission on this page example post and get request.
-    access_token: String,
+    access_token: Secret,
     /// The category shows the name of the major category the pages belog to
     category: String,
     /// this is this list of categories  with their names and id  { name:"
@@ -71,7 +77,7 @@ pub struct Account {
     /// The access token of this given page, which used to make operation that
     /// requires permission on this page example post and get request.
     #[serde(default)]
-    access_token: String,
+    access_token: Secret,
     /// The category shows the name of the major category the pages belog to
     category: String,
     /// this is this list of categories  with their names and id  { name:"
@@ -99,12 +105,15 @@ pub struct ListDetails {
 #[derive(Deserialize, Debug, Clone, Default)]
 pub struct Accounts {
     pub data: Vec<Account>,
+    /// The cursors and the urls of the pages around this one.
+    #[serde(default)]
+    pub paging: Option<Paging>,
 }
 
 impl Account {
     /// This will return the page access token
-    pub fn access_token(&self) -> &String {
-        &self.access_token
+    pub fn access_token(&self) -> &str {
+        self.access_token.expose_secret()
     }
 
     pub fn category(&self) -> &String {
@@ -135,8 +144,8 @@ impl Account {
 
 impl PageAccount {
     /// This will return the page access token
-    pub fn access_token(&self) -> &String {
-        &self.access_token
+    pub fn access_token(&self) -> &str {
+        self.access_token.expose_secret()
     }
 
     pub fn category(&self) -> &String {
@@ -231,14 +240,19 @@ impl PageAccount {
 ///    let page_access_token_type = TokenLiveType::LONGLIVE;
 ///  let page :Result<Accounts, ClientErr>  = Client::new(token, "".to_owned()).accounts(page_access_token_type).pages_by_me().await;
 /// ```
-pub struct AccountsAPI {
+pub struct AccountsAPI<HttpC: HttpClient = DefaultHttpClient> {
     url: String,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl AccountsAPI {
-    pub fn new(base_url: String) -> AccountsAPI {
+impl<HttpC: HttpClient> AccountsAPI<HttpC> {
+    pub fn new(
+        base_url: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> AccountsAPI<HttpC> {
         AccountsAPI {
-            url: base_url.replace("EDGE", "accounts"),
+            url: RequestUrl::new(base_url).edge("accounts").build(),
+            http_connection,
         }
     }
 
@@ -259,9 +273,18 @@ impl AccountsAPI {
     ///  let page :Result<Accounts, ClientErr> = Client::new(token, "".to_owned()).accounts(page_access_token_type).pages_by_me().await;
     /// ```
     pub async fn get(&self) -> Result<Accounts, ClientErr> {
-        let resp = HttpConnection::get::<Accounts>(self.url.to_string(), "".to_string()).await?;
+        let resp = self
+            .http_connection
+            .get::<Accounts>(self.url.to_string(), "".to_string())
+            .await?;
         Ok(resp)
     }
+
+    /// The Facebook Pages of every page of the list, following the `next`
+    /// page while the stream is consumed.
+    pub fn stream(self, options: PageOptions) -> impl Stream<Item = Result<Account, ClientErr>> {
+        paginate(self.http_connection, self.url, options)
+    }
 }
 
 #[derive(Deserialize, Debug, Clone, Default, Serialize)]
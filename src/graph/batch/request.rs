@@ -1,103 +1,459 @@
 use crate::prelude::errors::{ClientErr, FacebookAPiError};
-use crate::prelude::HttpConnection;
+use crate::prelude::fields::FieldSelection;
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
+#[cfg(feature = "reqwest")]
+use crate::universal::form_data::{MultipartData, MultipartFile};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
+#[cfg(feature = "reqwest")]
+use bytes::Bytes;
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use http::Method;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
 use std::fmt::Debug;
+use url::form_urlencoded;
 
-pub struct BatchApi {
+/// Files are only attached with the reqwest client, which sends multipart
+/// requests.
+#[cfg(not(feature = "reqwest"))]
+type MultipartFile = std::convert::Infallible;
+
+/// The most operations Facebook accepts in one batch request.
+pub const MAX_BATCH_SIZE: usize = 50;
+
+/// The batch requests sent at the same time by default, see
+/// [max_concurrency](BatchApi::max_concurrency).
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
+/// Batch requests, sending several operations in one http request.
+///
+/// Any number of operations can be given: they are split in batch requests
+/// of at most [MAX_BATCH_SIZE](MAX_BATCH_SIZE) operations, sent
+/// [max_concurrency](BatchApi::max_concurrency) at a time, and the responses
+/// are returned in the order of the operations. The operations referring to
+/// each other by [name](BatchOperation::name) are kept in the same batch
+/// request.
+pub struct BatchApi<HttpC: HttpClient = DefaultHttpClient> {
     pub base_url: String,
     pub page_access_token: String,
     pub page_id: String,
+    max_concurrency: usize,
+    retry_failed: bool,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl BatchApi {
-    pub fn new(base_url: String, page_access_token: String, page_id: String) -> Self {
+impl<HttpC: HttpClient> BatchApi<HttpC> {
+    pub fn new(
+        base_url: String,
+        page_access_token: String,
+        page_id: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> Self {
         BatchApi {
             base_url,
             page_access_token,
             page_id,
+            max_concurrency: DEFAULT_BATCH_CONCURRENCY,
+            retry_failed: false,
+            http_connection,
         }
     }
 
+    /// The most batch requests sent at the same time, at least 1.
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Send again the operations which failed with a transient error, see
+    /// [is_transient](crate::prelude::errors::ApiError::is_transient),
+    /// following the [RetryPolicy](crate::prelude::retry::RetryPolicy) of the
+    /// client. Only the failed operations are sent again, and the operations
+    /// referring to other ones by name are never sent twice.
+    ///
+    /// A `429` or `5xx` response without a transient error of Facebook is
+    /// only sent again for the GET and DELETE operations: a POST, e.g. of a
+    /// post or a photo, may have been applied by Facebook and is not
+    /// published twice.
+    pub fn retry_failed(mut self, retry_failed: bool) -> Self {
+        self.retry_failed = retry_failed;
+        self
+    }
+
+    /// Send the items, and return the response of every item in their
+    /// order. A response is `None` when Facebook did not return it, e.g. for
+    /// an item which timed out or was not run.
     pub async fn request(
         self,
         batch_items: BatchItems,
-    ) -> Result<Vec<BatchResponseBody>, ClientErr> {
-        let batch = serde_json::to_string(&batch_items.data).unwrap();
-
-        let url = self.base_url
-            + "?batch="
-            + &batch
-            + "&access_token="
-            + &self.page_access_token
-            + "&include_headers=false";
-
-        let resp =
-            HttpConnection::batch_post::<Vec<BatchResponseBody>, String>(url, "".to_string())
-                .await?;
-        Ok(resp)
+    ) -> Result<Vec<Option<BatchResponseBody>>, ClientErr> {
+        self.send_operations(batch_items.data, &batch_items.attachments)
+            .await
     }
 
+    /// Same as [request](BatchApi::request), reading the response of every
+    /// item as `T`.
     pub async fn request_with_type<T>(
         self,
         batch_items: BatchItems,
-    ) -> Result<Vec<BatchResponseBodyWithType<T>>, ClientErr>
+    ) -> Result<Vec<Option<BatchResponseBodyWithType<T>>>, ClientErr>
+    where
+        T: DeserializeOwned,
+    {
+        let resp = self.request(batch_items).await?;
+        resp.into_iter()
+            .map(|body| body.map(BatchResponseBody::with_type).transpose())
+            .collect()
+    }
+
+    /// Send the operations of a [BatchRequest](BatchRequest), and read the
+    /// response of every operation as `T`.
+    ///
+    /// The responses are in the order of the operations. A response is
+    /// `None` when Facebook did not return it: the operation succeeded with
+    /// [omit_response_on_success](BatchOperation::omit_response_on_success),
+    /// or it was not run since an operation it depends on failed.
+    pub async fn send<T>(
+        &self,
+        request: &BatchRequest,
+    ) -> Result<Vec<Option<BatchResponseBodyWithType<T>>>, ClientErr>
     where
         T: DeserializeOwned,
     {
-        let batch = serde_json::to_string(&batch_items.data).unwrap();
-
-        let url = self.base_url
-            + "?batch="
-            + &batch
-            + "&access_token="
-            + &self.page_access_token
-            + "&include_headers=false";
-
-        let resp: Vec<BatchResponseBody> =
-            HttpConnection::batch_post::<Vec<BatchResponseBody>, String>(url, "".to_string())
-                .await?;
-
-        let mut resp_body: Vec<BatchResponseBodyWithType<T>> = Vec::new();
-
-        for body in resp {
-            if let Some(data) = body.body {
-                if body.code == 200 {
-                    let data: T = serde_json::from_str(&data.as_str())?;
-
-                    resp_body.push(BatchResponseBodyWithType {
-                        code: body.code,
-                        body: Some(data),
-                        error: None,
-                    })
-                } else {
-                    let data: FacebookAPiError = serde_json::from_str(&data.as_str())?;
-
-                    resp_body.push(BatchResponseBodyWithType {
-                        code: body.code,
-                        body: None,
-                        error: Some(data),
-                    })
+        let resp = self.send_operations(request.to_json(), &[]).await?;
+        resp.into_iter()
+            .map(|body| body.map(BatchResponseBody::with_type).transpose())
+            .collect()
+    }
+
+    /// Send the operations in batch requests of at most
+    /// [MAX_BATCH_SIZE](MAX_BATCH_SIZE) operations, and send again the
+    /// failed ones if asked.
+    async fn send_operations(
+        &self,
+        operations: Vec<Value>,
+        attachments: &[MultipartFile],
+    ) -> Result<Vec<Option<BatchResponseBody>>, ClientErr> {
+        let groups = operation_groups(&operations);
+        let mut responses = vec![None; operations.len()];
+        for (index, response) in self
+            .send_chunks(&operations, attachments, batch_chunks(&groups)?)
+            .await?
+        {
+            responses[index] = response;
+        }
+        if !self.retry_failed {
+            return Ok(responses);
+        }
+
+        let mut group_sizes: HashMap<usize, usize> = HashMap::new();
+        for &group in &groups {
+            *group_sizes.entry(group).or_default() += 1;
+        }
+        let retry_policy = self.http_connection.retry_policy().clone();
+        for attempt in 0..retry_policy.max_retries() {
+            let failed: Vec<usize> = (0..operations.len())
+                .filter(|&index| group_sizes[&groups[index]] == 1)
+                .filter(|&index| {
+                    let method = operation_method(&operations[index]);
+                    responses[index]
+                        .as_ref()
+                        .is_some_and(|response| response.is_transient_error(&method))
+                })
+                .collect();
+            if failed.is_empty() {
+                break;
+            }
+            self.http_connection
+                .http_client()
+                .sleep(retry_policy.backoff(attempt))
+                .await;
+            let chunks = failed
+                .chunks(MAX_BATCH_SIZE)
+                .map(<[usize]>::to_vec)
+                .collect();
+            for (index, response) in self.send_chunks(&operations, attachments, chunks).await? {
+                responses[index] = response;
+            }
+        }
+        Ok(responses)
+    }
+
+    /// Send a batch request for every chunk of operation indexes, and return
+    /// the response of every operation with its index.
+    async fn send_chunks(
+        &self,
+        operations: &[Value],
+        attachments: &[MultipartFile],
+        chunks: Vec<Vec<usize>>,
+    ) -> Result<Vec<(usize, Option<BatchResponseBody>)>, ClientErr> {
+        let batches: Vec<Vec<(usize, Option<BatchResponseBody>)>> = stream::iter(chunks)
+            .map(|chunk| async move {
+                let batch: Vec<Value> = chunk
+                    .iter()
+                    .map(|&index| operations[index].clone())
+                    .collect();
+                let resp = self.send_batch(batch, attachments).await?;
+                if resp.len() != chunk.len() {
+                    return Err(ClientErr::CustomError(format!(
+                        "batch request of {} operations answered with {} responses",
+                        chunk.len(),
+                        resp.len()
+                    )));
                 }
-            } else {
-                resp_body.push(BatchResponseBodyWithType {
-                    code: body.code,
-                    body: None,
-                    error: None,
+                Ok(chunk.into_iter().zip(resp).collect())
+            })
+            .buffered(self.max_concurrency)
+            .try_collect()
+            .await?;
+        Ok(batches.into_iter().flatten().collect())
+    }
+
+    /// Send one batch request, as a multipart body when its operations have
+    /// attached files.
+    async fn send_batch(
+        &self,
+        batch: Vec<Value>,
+        attachments: &[MultipartFile],
+    ) -> Result<Vec<Option<BatchResponseBody>>, ClientErr> {
+        #[cfg(feature = "reqwest")]
+        if let Some(form_data) = multipart_batch(&batch, attachments) {
+            let url = RequestUrl::new(self.base_url.as_str())
+                .access_token(&self.page_access_token)
+                .build();
+            return self
+                .http_connection
+                .batch_multipart_post::<Vec<Option<BatchResponseBody>>>(url, form_data)
+                .await;
+        }
+        #[cfg(not(feature = "reqwest"))]
+        let _ = attachments;
+
+        self.http_connection
+            .batch_post::<Vec<Option<BatchResponseBody>>, String>(
+                self.batch_url(&Value::Array(batch)),
+                "".to_string(),
+            )
+            .await
+    }
+
+    /// The url of a batch request. Its parameters are sent as an url encoded
+    /// body by [batch_post](GenericClientConnection::batch_post).
+    fn batch_url(&self, batch: &Value) -> String {
+        RequestUrl::new(self.base_url.as_str())
+            .param("batch", batch)
+            .access_token(&self.page_access_token)
+            .param("include_headers", false)
+            .build()
+    }
+}
+
+/// The multipart body of a batch request whose operations have attached
+/// files, with the files they name.
+#[cfg(feature = "reqwest")]
+fn multipart_batch(batch: &[Value], attachments: &[MultipartFile]) -> Option<MultipartData> {
+    let attached: Vec<&MultipartFile> = attachments
+        .iter()
+        .filter(|file| {
+            batch
+                .iter()
+                .any(|operation| attached_files(operation).any(|name| name == file.name))
+        })
+        .collect();
+    if attached.is_empty() {
+        return None;
+    }
+
+    let mut form_data = MultipartData::new()
+        .text("batch", Value::from(batch.to_vec()).to_string())
+        .text("include_headers", "false");
+    for file in attached {
+        form_data = form_data.file(
+            file.name.clone(),
+            file.file_name.clone(),
+            file.bytes.clone(),
+        );
+    }
+    Some(form_data)
+}
+
+/// The names of the files attached to an operation.
+#[cfg(feature = "reqwest")]
+fn attached_files(operation: &Value) -> impl Iterator<Item = &str> {
+    operation["attached_files"]
+        .as_str()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// The method of an operation. An operation whose method can not be read is
+/// taken as a POST, which is not sent again after a bare error status.
+fn operation_method(operation: &Value) -> Method {
+    operation["method"]
+        .as_str()
+        .and_then(|method| Method::from_bytes(method.as_bytes()).ok())
+        .unwrap_or(Method::POST)
+}
+
+/// The group of every operation: the index of the first operation of the
+/// operations referring to each other by name, which must be sent in the same
+/// batch request.
+fn operation_groups(operations: &[Value]) -> Vec<usize> {
+    let mut groups: Vec<usize> = (0..operations.len()).collect();
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    for (index, operation) in operations.iter().enumerate() {
+        let refers_to = |name: &str| {
+            let reference = format!("{{result={}:", name);
+            operation["depends_on"].as_str() == Some(name)
+                || ["relative_url", "body"].iter().any(|key| {
+                    operation[*key]
+                        .as_str()
+                        .is_some_and(|text| text.contains(&reference))
                 })
+        };
+        let referred: Vec<usize> = names
+            .iter()
+            .filter(|(name, _)| refers_to(name))
+            .map(|(_, &referred)| groups[referred])
+            .collect();
+        for group in referred {
+            // Operations only refer to earlier ones, so the merged group keeps
+            // the smallest index.
+            let (kept, merged) = (group.min(groups[index]), group.max(groups[index]));
+            groups
+                .iter_mut()
+                .filter(|g| **g == merged)
+                .for_each(|g| *g = kept);
+        }
+        if let Some(name) = operation["name"].as_str() {
+            names.insert(name, index);
+        }
+    }
+    groups
+}
+
+/// Split the operations in chunks of at most [MAX_BATCH_SIZE](MAX_BATCH_SIZE)
+/// operations, without splitting a group, and keeping the order of the
+/// operations in every chunk.
+fn batch_chunks(groups: &[usize]) -> Result<Vec<Vec<usize>>, ClientErr> {
+    let mut members: Vec<Vec<usize>> = Vec::new();
+    let mut positions: HashMap<usize, usize> = HashMap::new();
+    for (index, &group) in groups.iter().enumerate() {
+        match positions.get(&group) {
+            Some(&position) => members[position].push(index),
+            None => {
+                positions.insert(group, members.len());
+                members.push(vec![index]);
             }
         }
-        Ok(resp_body)
     }
+
+    let mut chunks: Vec<Vec<usize>> = Vec::new();
+    let mut chunk: Vec<usize> = Vec::new();
+    for group in members {
+        if group.len() > MAX_BATCH_SIZE {
+            return Err(ClientErr::CustomError(format!(
+                "{} operations referring to each other can not be sent in one batch request of \
+                 at most {} operations",
+                group.len(),
+                MAX_BATCH_SIZE
+            )));
+        }
+        if chunk.len() + group.len() > MAX_BATCH_SIZE {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+        chunk.extend(group);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    for chunk in &mut chunks {
+        chunk.sort_unstable();
+    }
+    Ok(chunks)
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
 pub struct BatchItems {
     pub data: Vec<Value>,
+    /// The files the items refer to in their `attached_files`.
+    #[serde(skip)]
+    attachments: Vec<MultipartFile>,
 }
 
 impl BatchItems {
+    /// Attach a file to the batch request, e.g. a photo, under a name the
+    /// items give in their `attached_files`. The items with attached files
+    /// are sent as a multipart body.
+    ///
+    /// # Example
+    /// ```
+    /// use facebook_api_rs::prelude::BatchItems;
+    ///
+    /// let photo = vec![0xff, 0xd8, 0xff];
+    /// let items = BatchItems::default()
+    ///     .attach_file("photo1", "cat.jpg", photo.clone())
+    ///     .attach_file("photo2", "dog.jpg", photo)
+    ///     .add_batch_item_with_attached_files(
+    ///         "POST",
+    ///         "caption=My+cat".to_string(),
+    ///         "page_id/photos",
+    ///         &["photo1"],
+    ///     )
+    ///     .add_batch_item_with_attached_files(
+    ///         "POST",
+    ///         "caption=My+dog&access_token=other_page_token".to_string(),
+    ///         "other_page_id/photos",
+    ///         &["photo2"],
+    ///     );
+    /// assert_eq!(items.attachments().len(), 2);
+    /// ```
+    #[cfg(feature = "reqwest")]
+    pub fn attach_file(
+        mut self,
+        name: impl Into<String>,
+        file_name: impl Into<String>,
+        bytes: impl Into<Bytes>,
+    ) -> Self {
+        self.attachments.push(MultipartFile {
+            name: name.into(),
+            file_name: file_name.into(),
+            bytes: bytes.into(),
+        });
+        self
+    }
+
+    #[cfg(feature = "reqwest")]
+    pub fn attachments(&self) -> &[MultipartFile] {
+        &self.attachments
+    }
+
+    /// Add an item using the files attached with
+    /// [attach_file](BatchItems::attach_file) under the given names.
+    pub fn add_batch_item_with_attached_files(
+        mut self,
+        method: &str,
+        body: String,
+        relative_url: &str,
+        attached_files: &[&str],
+    ) -> Self {
+        let data = json!(
+            {
+                "method": method.to_uppercase(),
+               "relative_url":relative_url,
+                "body": body,
+                "attached_files": attached_files.join(","),
+            }
+        );
+        self.data.push(data);
+        self
+    }
+
     pub fn add_batch_item_with_body(
         mut self,
         method: &str,
@@ -168,9 +524,683 @@ pub struct BatchResponseBody {
     pub body: Option<String>,
 }
 
+impl BatchResponseBody {
+    /// Read the body as `T` when the operation succeeded, or as a
+    /// [FacebookAPiError](FacebookAPiError) when it failed.
+    pub fn with_type<T: DeserializeOwned>(self) -> Result<BatchResponseBodyWithType<T>, ClientErr> {
+        let (body, error) = match self.body {
+            Some(data) if self.code == 200 => (Some(serde_json::from_str(&data)?), None),
+            Some(data) => (None, Some(serde_json::from_str(&data)?)),
+            None => (None, None),
+        };
+        Ok(BatchResponseBodyWithType {
+            code: self.code,
+            body,
+            error,
+        })
+    }
+
+    /// Whether the operation, sent with `method`, failed with an error worth
+    /// sending it again. A `429` or `5xx` status without a transient error of
+    /// Facebook is only retried for the [idempotent](Method::is_idempotent)
+    /// methods, as by the [RetryPolicy](crate::prelude::retry::RetryPolicy).
+    fn is_transient_error(&self, method: &Method) -> bool {
+        let transient = self.code != 200
+            && self
+                .body
+                .as_deref()
+                .and_then(|body| serde_json::from_str::<FacebookAPiError>(body).ok())
+                .is_some_and(|error| error.error.is_transient());
+        transient || method.is_idempotent() && (self.code == 429 || self.code >= 500)
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct BatchResponseBodyWithType<T> {
     pub code: u64,
     pub body: Option<T>,
     pub error: Option<FacebookAPiError>,
 }
+
+/// The operations of a batch request, sent together by
+/// [BatchApi::send](BatchApi::send).
+///
+/// An operation can use the response of an earlier operation of the batch
+/// by giving it a [name](BatchOperation::name) and referring to it with
+/// [result_ref](BatchRequest::result_ref).
+///
+/// For more information check <https://developers.facebook.com/docs/graph-api/batch-requests>.
+///
+/// # Example
+/// ```
+/// use facebook_api_rs::prelude::{BatchOperation, BatchRequest};
+///
+/// let request = BatchRequest::new()
+///     .operation(
+///         BatchOperation::post("page_id/feed")
+///             .name("create")
+///             .param("message", "Hello & welcome")
+///             .omit_response_on_success(false),
+///     )
+///     .operation(
+///         BatchOperation::get(BatchRequest::result_ref("create", "$.id"))
+///             .depends_on("create")
+///             .param("fields", "id,message"),
+///     );
+/// assert_eq!(request.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchRequest {
+    operations: Vec<BatchOperation>,
+}
+
+impl BatchRequest {
+    pub fn new() -> Self {
+        BatchRequest::default()
+    }
+
+    pub fn operation(mut self, operation: BatchOperation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+
+    pub fn operations(&self) -> &[BatchOperation] {
+        &self.operations
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// A reference to the response of the operation named `name`, e.g.
+    /// `{result=create:$.id}` for the `id` of the response of the `create`
+    /// operation. `json_path` is a JSONPath expression, such as
+    /// `$.data.*.id`, and the values it finds are joined with commas.
+    ///
+    /// The reference can be used in the relative url and in the parameters
+    /// of a later operation, and is left as it is when they are encoded.
+    pub fn result_ref(name: &str, json_path: &str) -> String {
+        format!("{{result={}:{}}}", name, json_path)
+    }
+
+    /// The operations of the `batch` parameter of the request, as json.
+    pub(crate) fn to_json(&self) -> Vec<Value> {
+        self.operations
+            .iter()
+            .map(BatchOperation::to_json)
+            .collect()
+    }
+}
+
+impl FromIterator<BatchOperation> for BatchRequest {
+    fn from_iter<I: IntoIterator<Item = BatchOperation>>(operations: I) -> Self {
+        BatchRequest {
+            operations: operations.into_iter().collect(),
+        }
+    }
+}
+
+/// One operation of a [BatchRequest](BatchRequest).
+///
+/// The parameters of a POST are sent as the body of the operation, the
+/// parameters of the other methods are added to its relative url.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct BatchOperation {
+    method: String,
+    relative_url: String,
+    params: Vec<(String, String)>,
+    name: Option<String>,
+    depends_on: Option<String>,
+    omit_response_on_success: Option<bool>,
+    access_token: Option<String>,
+}
+
+/// The access token is not printed.
+impl Debug for BatchOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchOperation")
+            .field("method", &self.method)
+            .field("relative_url", &self.relative_url)
+            .field("params", &self.params)
+            .field("name", &self.name)
+            .field("depends_on", &self.depends_on)
+            .field("omit_response_on_success", &self.omit_response_on_success)
+            .finish()
+    }
+}
+
+impl BatchOperation {
+    /// An operation with the given http method, e.g. `"GET"`, and url
+    /// relative to the graph url, e.g. `"page_id/feed"`.
+    pub fn new(method: &str, relative_url: impl Into<String>) -> Self {
+        BatchOperation {
+            method: method.to_uppercase(),
+            relative_url: relative_url.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn get(relative_url: impl Into<String>) -> Self {
+        BatchOperation::new("GET", relative_url)
+    }
+
+    pub fn post(relative_url: impl Into<String>) -> Self {
+        BatchOperation::new("POST", relative_url)
+    }
+
+    pub fn delete(relative_url: impl Into<String>) -> Self {
+        BatchOperation::new("DELETE", relative_url)
+    }
+
+    /// Add a parameter. The values are url encoded, except the
+    /// [references](BatchRequest::result_ref) to other operations.
+    pub fn param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.params.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Add several parameters.
+    pub fn params<I, K, V>(mut self, params: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: ToString,
+    {
+        self.params.extend(
+            params
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.to_string())),
+        );
+        self
+    }
+
+    /// Set the `fields` parameter, see [FieldSelection](FieldSelection).
+    pub fn fields(self, fields: FieldSelection) -> Self {
+        self.param("fields", fields)
+    }
+
+    /// The name other operations use to refer to the response of this one.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Run the operation after the operation named `name`, and only when
+    /// it succeeds.
+    pub fn depends_on(mut self, name: impl Into<String>) -> Self {
+        self.depends_on = Some(name.into());
+        self
+    }
+
+    /// Whether the response of the operation is left out when it succeeds.
+    /// Facebook leaves out by default the responses other operations of the
+    /// batch refer to.
+    pub fn omit_response_on_success(mut self, omit: bool) -> Self {
+        self.omit_response_on_success = Some(omit);
+        self
+    }
+
+    /// Send the operation with another access token than the access token
+    /// of the batch request, e.g. the token of another page.
+    pub fn access_token(mut self, access_token: impl Into<String>) -> Self {
+        self.access_token = Some(access_token.into());
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        let mut params = self.params.clone();
+        if let Some(access_token) = &self.access_token {
+            params.push(("access_token".to_string(), access_token.clone()));
+        }
+        let query = encode_params(&params);
+
+        let mut operation = Map::new();
+        operation.insert("method".to_string(), json!(self.method));
+        if self.method == "POST" {
+            operation.insert("relative_url".to_string(), json!(self.relative_url));
+            if !query.is_empty() {
+                operation.insert("body".to_string(), json!(query));
+            }
+        } else if query.is_empty() {
+            operation.insert("relative_url".to_string(), json!(self.relative_url));
+        } else {
+            let separator = if self.relative_url.contains('?') {
+                "&"
+            } else {
+                "?"
+            };
+            let relative_url = self.relative_url.clone() + separator + &query;
+            operation.insert("relative_url".to_string(), json!(relative_url));
+        }
+        if let Some(name) = &self.name {
+            operation.insert("name".to_string(), json!(name));
+        }
+        if let Some(depends_on) = &self.depends_on {
+            operation.insert("depends_on".to_string(), json!(depends_on));
+        }
+        if let Some(omit) = self.omit_response_on_success {
+            operation.insert("omit_response_on_success".to_string(), json!(omit));
+        }
+        Value::Object(operation)
+    }
+}
+
+/// Url encode the parameters of an operation, leaving the `{result=...}`
+/// references as they are so Facebook can find them.
+fn encode_params(params: &[(String, String)]) -> String {
+    params
+        .iter()
+        .map(|(name, value)| format!("{}={}", encode_part(name), encode_value(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn encode_value(mut value: &str) -> String {
+    let mut encoded = String::new();
+    while let Some(start) = value.find("{result=") {
+        let Some(end) = value[start..].find('}') else {
+            break;
+        };
+        encoded.push_str(&encode_part(&value[..start]));
+        encoded.push_str(&value[start..start + end + 1]);
+        value = &value[start + end + 1..];
+    }
+    encoded.push_str(&encode_part(value));
+    encoded
+}
+
+fn encode_part(value: &str) -> String {
+    form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::mock_server::MockGraphServer;
+
+    #[test]
+    fn test_operations_are_encoded() {
+        let request = BatchRequest::new()
+            .operation(
+                BatchOperation::post("1234/feed")
+                    .name("create")
+                    .param("message", "Tom & Jerry")
+                    .access_token("other_page_token")
+                    .omit_response_on_success(false),
+            )
+            .operation(
+                BatchOperation::get(BatchRequest::result_ref("create", "$.id"))
+                    .depends_on("create")
+                    .fields(FieldSelection::from_iter(["id", "message"])),
+            )
+            .operation(
+                BatchOperation::get("")
+                    .param("ids", BatchRequest::result_ref("list", "$.data.*.id")),
+            );
+
+        assert_eq!(
+            Value::Array(request.to_json()),
+            json!([
+                {
+                    "method": "POST",
+                    "relative_url": "1234/feed",
+                    "body": "message=Tom+%26+Jerry&access_token=other_page_token",
+                    "name": "create",
+                    "omit_response_on_success": false,
+                },
+                {
+                    "method": "GET",
+                    "relative_url": "{result=create:$.id}?fields=id%2Cmessage",
+                    "depends_on": "create",
+                },
+                {
+                    "method": "GET",
+                    "relative_url": "?ids={result=list:$.data.*.id}",
+                },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_a_batch_request() {
+        let server = MockGraphServer::start().unwrap();
+        let request = BatchRequest::new()
+            .operation(
+                BatchOperation::post("1234/feed")
+                    .name("create")
+                    .param("message", "Tom & Jerry"),
+            )
+            .operation(
+                BatchOperation::post("1234/feed")
+                    .name("shown")
+                    .param("message", "Shown")
+                    .omit_response_on_success(false),
+            )
+            .operation(
+                BatchOperation::get(BatchRequest::result_ref("create", "$.id"))
+                    .depends_on("create")
+                    .access_token("other_page_token"),
+            )
+            .operation(BatchOperation::delete("1234/unknown").name("broken"))
+            .operation(BatchOperation::get("1234/feed").depends_on("broken"));
+
+        let responses: Vec<Option<BatchResponseBodyWithType<Value>>> = server
+            .client("page_token".to_string())
+            .batch_request("1234".to_string())
+            .send(&request)
+            .await
+            .unwrap();
+
+        assert_eq!(responses.len(), 5);
+        // The response other operations refer to is omitted by default.
+        assert!(responses[0].is_none());
+        assert_eq!(
+            responses[1].as_ref().unwrap().body.as_ref().unwrap()["id"],
+            "1234_1002"
+        );
+        assert_eq!(responses[2].as_ref().unwrap().code, 200);
+        let broken = responses[3].as_ref().unwrap();
+        assert_eq!(broken.code, 400);
+        assert_eq!(broken.error.as_ref().unwrap().error.code, 100);
+        // The operation depending on a failed one is not run.
+        assert!(responses[4].is_none());
+
+        let requests = server.requests();
+        // The batch is sent in the body, not in the url.
+        assert!(requests[0].query.is_empty());
+        assert_eq!(requests[0].param("include_headers"), Some("false"));
+        assert_eq!(requests[1].param("message"), Some("Tom & Jerry"));
+        // The reference was replaced by the id of the created post.
+        assert_eq!(requests[3].path, "/1234_1001");
+        assert_eq!(requests[3].access_token(), Some("other_page_token"));
+        assert_eq!(requests.len(), 5);
+    }
+
+    #[test]
+    fn test_named_operations_are_kept_in_one_chunk() {
+        let mut request: BatchRequest = (0..49)
+            .map(|index| BatchOperation::get(format!("1234_{}", index)))
+            .collect();
+        request = request
+            .operation(BatchOperation::post("1234/feed").name("create"))
+            .operation(BatchOperation::get("1234_0"))
+            .operation(BatchOperation::get(BatchRequest::result_ref(
+                "create", "$.id",
+            )));
+        let operations: Vec<Value> = request
+            .operations
+            .iter()
+            .map(BatchOperation::to_json)
+            .collect();
+
+        let chunks = batch_chunks(&operation_groups(&operations)).unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], (0..49).collect::<Vec<_>>());
+        assert_eq!(chunks[1], vec![49, 50, 51]);
+
+        let linked: Vec<Value> = (0..51)
+            .map(|index| {
+                let operation = BatchOperation::get("me").name(format!("op{}", index));
+                match index {
+                    0 => operation,
+                    _ => operation.depends_on(format!("op{}", index - 1)),
+                }
+                .to_json()
+            })
+            .collect();
+        assert!(batch_chunks(&operation_groups(&linked)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_large_batches_are_split() {
+        let server = MockGraphServer::start().unwrap();
+        let items = (0..120).fold(BatchItems::default(), |items, index| {
+            items.add_batch_item_without_body("GET", &format!("?ids=item_{}", index))
+        });
+
+        let responses: Vec<Option<BatchResponseBodyWithType<HashMap<String, Value>>>> = server
+            .client("page_token".to_string())
+            .batch_request("1234".to_string())
+            .max_concurrency(3)
+            .request_with_type(items)
+            .await
+            .unwrap();
+
+        assert_eq!(responses.len(), 120);
+        for (index, response) in responses.iter().enumerate() {
+            let id = format!("item_{}", index);
+            let body = response.as_ref().unwrap().body.as_ref().unwrap();
+            assert_eq!(body[&id]["id"], id);
+        }
+        let mut sizes: Vec<usize> = server
+            .requests()
+            .iter()
+            .filter_map(|request| request.param("batch"))
+            .map(|batch| serde_json::from_str::<Vec<Value>>(batch).unwrap().len())
+            .collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![20, 50, 50]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_operations_are_sent_again() {
+        use crate::prelude::mock_server::MockResponse;
+        use crate::prelude::retry::RetryPolicy;
+        use std::time::Duration;
+
+        let server = MockGraphServer::start().unwrap();
+        // The mock added last answers first.
+        server.mock_once(
+            "GET",
+            "/",
+            MockResponse::error(400, 100, "OAuthException", "(#100) Invalid parameter"),
+        );
+        server.mock_once(
+            "GET",
+            "/",
+            MockResponse::error(
+                500,
+                2,
+                "OAuthException",
+                "An unexpected error has occurred.",
+            ),
+        );
+        let items = BatchItems::default()
+            .add_batch_item_without_body("GET", "?ids=first")
+            .add_batch_item_without_body("GET", "?ids=second")
+            .add_batch_item_without_body("GET", "?ids=third");
+
+        let responses: Vec<BatchResponseBodyWithType<Value>> = server
+            .client("page_token".to_string())
+            .add_retry_policy(RetryPolicy::default().with_initial_delay(Duration::ZERO))
+            .batch_request("1234".to_string())
+            .retry_failed(true)
+            .request_with_type(items)
+            .await
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+
+        assert_eq!(responses[0].body.as_ref().unwrap()["first"]["id"], "first");
+        // Errors which are not transient are not sent again.
+        assert_eq!(responses[1].error.as_ref().unwrap().error.code, 100);
+        assert_eq!(responses[2].code, 200);
+
+        let batches: Vec<String> = server
+            .requests()
+            .iter()
+            .filter_map(|request| request.param("batch").map(str::to_string))
+            .collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(
+            serde_json::from_str::<Value>(&batches[1]).unwrap(),
+            json!([{ "method": "GET", "relative_url": "?ids=first" }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_responses_keep_their_place() {
+        use crate::prelude::mock_server::MockResponse;
+
+        let server = MockGraphServer::start().unwrap();
+        // An item which timed out is answered with `null`.
+        server.mock_once(
+            "POST",
+            "/",
+            MockResponse::ok(json!([
+                null,
+                { "code": 200, "body": r#"{"id":"second"}"# },
+            ])),
+        );
+        let items = BatchItems::default()
+            .add_batch_item_without_body("GET", "first")
+            .add_batch_item_without_body("GET", "second");
+
+        let responses: Vec<Option<BatchResponseBodyWithType<Value>>> = server
+            .client("page_token".to_string())
+            .batch_request("1234".to_string())
+            .request_with_type(items)
+            .await
+            .unwrap();
+
+        assert_eq!(responses.len(), 2);
+        assert!(responses[0].is_none());
+        assert_eq!(
+            responses[1].as_ref().unwrap().body.as_ref().unwrap()["id"],
+            "second"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_posts_are_not_sent_again() {
+        use crate::prelude::mock_server::MockResponse;
+        use crate::prelude::retry::RetryPolicy;
+        use std::time::Duration;
+
+        let server = MockGraphServer::start().unwrap();
+        server.mock(
+            "POST",
+            "/1234/feed",
+            MockResponse::json(500, json!("Internal Server Error")),
+        );
+        server.mock_once(
+            "GET",
+            "/",
+            MockResponse::json(503, json!("Service Unavailable")),
+        );
+        let items = BatchItems::default()
+            .add_batch_item_with_body("POST", "message=Hello".to_string(), "1234/feed")
+            .add_batch_item_without_body("GET", "?ids=first");
+
+        let responses = server
+            .client("page_token".to_string())
+            .add_retry_policy(RetryPolicy::default().with_initial_delay(Duration::ZERO))
+            .batch_request("1234".to_string())
+            .retry_failed(true)
+            .request(items)
+            .await
+            .unwrap();
+
+        // The POST may have been published, so it is not sent again.
+        assert_eq!(responses[0].as_ref().unwrap().code, 500);
+        assert_eq!(responses[1].as_ref().unwrap().code, 200);
+
+        let batches: Vec<Value> = server
+            .requests()
+            .iter()
+            .filter_map(|request| request.param("batch"))
+            .map(|batch| serde_json::from_str(batch).unwrap())
+            .collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(
+            batches[1],
+            json!([{ "method": "GET", "relative_url": "?ids=first" }])
+        );
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn test_attached_files_are_sent_with_their_items() {
+        let server = MockGraphServer::start().unwrap();
+        let items = BatchItems::default()
+            .attach_file("photo1", "cat.jpg", vec![1_u8; 16])
+            .attach_file("photo2", "dog.jpg", vec![2_u8; 32])
+            .add_batch_item_with_attached_files(
+                "POST",
+                "caption=My+cat".to_string(),
+                "1234/photos",
+                &["photo1"],
+            )
+            .add_batch_item_with_attached_files(
+                "POST",
+                "caption=My+dog&access_token=other_page_token".to_string(),
+                "5678/photos",
+                &["photo2"],
+            );
+
+        let responses: Vec<Option<BatchResponseBodyWithType<Value>>> = server
+            .client("page_token".to_string())
+            .batch_request("1234".to_string())
+            .request_with_type(items)
+            .await
+            .unwrap();
+
+        assert_eq!(responses.len(), 2);
+        assert!(responses
+            .iter()
+            .all(|response| response.as_ref().unwrap().code == 200));
+        let requests = server.requests();
+        assert!(requests[0]
+            .header("content-type")
+            .unwrap()
+            .starts_with("multipart/form-data"));
+        assert_eq!(requests[0].access_token(), Some("page_token"));
+        assert_eq!(requests[0].param("include_headers"), Some("false"));
+        assert_eq!(requests[0].files["photo2"], vec![2_u8; 32]);
+        assert_eq!(requests[1].files["photo1"], vec![1_u8; 16]);
+        assert!(!requests[1].files.contains_key("photo2"));
+        assert_eq!(requests[2].path, "/5678/photos");
+        assert_eq!(requests[2].param("caption"), Some("My dog"));
+        assert_eq!(requests[2].access_token(), Some("other_page_token"));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn test_attached_files_batch_is_signed() {
+        use crate::prelude::app_secret_proof::app_secret_proof;
+
+        let server = MockGraphServer::start().unwrap();
+        let items = BatchItems::default()
+            .attach_file("photo", "cat.jpg", vec![1_u8; 16])
+            .add_batch_item_with_attached_files(
+                "POST",
+                "caption=My+cat&access_token=other_page_token".to_string(),
+                "5678/photos",
+                &["photo"],
+            );
+
+        server
+            .client("page_token".to_string())
+            .add_app_secret("app_secret".to_string())
+            .batch_request("1234".to_string())
+            .request(items)
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].param("appsecret_proof"),
+            Some(app_secret_proof("page_token", "app_secret").as_str())
+        );
+        assert_eq!(requests[1].access_token(), Some("other_page_token"));
+        assert_eq!(
+            requests[1].param("appsecret_proof"),
+            Some(app_secret_proof("other_page_token", "app_secret").as_str())
+        );
+    }
+}
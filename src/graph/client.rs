@@ -1,15 +1,27 @@
 //!  This mod will serves as method binder that gives access different end
 //! poinst availiable on the facebook-api.rs.
 
+use crate::graph::ids::IdsApi;
 use crate::graph::me::MeApi;
+use crate::graph::node::NodeApi;
 use crate::graph::pages::feed::FeedApi;
 use crate::graph::pages::post::PostApi;
 use crate::login::token::{TokenLiveType, UserToken};
+use crate::prelude::photo::PhotoApi;
 use crate::prelude::search::PagesSearchAPI;
 use crate::prelude::video::VideoApi;
 use crate::prelude::{
     BatchApi, HashtagAPi, InstagramApi, InstagramContentPublishingApi, InstagramMediaApi,
 };
+use crate::universal::client::{DefaultHttpClient, GenericClientConnection};
+use crate::universal::errors::ClientErr;
+use crate::universal::request_url::{graph_path, node_url, RequestUrl};
+use crate::universal::retry::RetryPolicy;
+use crate::universal::secret::Secret;
+use crate::universal::usage::{RateLimitUsage, Throttle};
+use crate::universal::HttpClient;
+use std::future::Future;
+use std::sync::Arc;
 
 /// Default Facebook Graph API version
 ///
@@ -19,38 +31,58 @@ use crate::prelude::{
 /// See: <https://developers.facebook.com/docs/graph-api/guides/versioning>
 pub const DEFAULT_GRAPH_API_VERSION: &str = "v23.0";
 
+/// Default host of the Facebook Graph API.
+///
+/// Use [add_graph_host](Client::add_graph_host) to send the requests to another
+/// host, for example a local stand-in of the Graph API used in tests.
+pub const DEFAULT_GRAPH_HOST: &str = "https://graph.facebook.com";
+
+/// Build the graph url template used by the different APIs, where `NODE` and
+/// `EDGE` are replaced by the node id and the edge of a request.
+pub(crate) fn graph_url(graph_host: &str, api_version: &str) -> String {
+    format!(
+        "{}/{}/NODE/EDGE",
+        graph_host.trim_end_matches('/'),
+        api_version
+    )
+}
+
 /// Client Struct for making calls to Facebook Graph
+///
+/// The client owns one [HttpClient](HttpClient) which is shared by every API
+/// it hands out, so a configured transport is reused for the whole session.
+/// By default the [DefaultHttpClient](DefaultHttpClient) of the enabled
+/// feature is used, use [add_http_client](Client::add_http_client) to
+/// replace it.
 #[derive(Debug)]
-pub struct Client {
+pub struct Client<HttpC: HttpClient = DefaultHttpClient> {
     graph: String,
-    short_live_user_access_token: String,
-    long_live_user_access_token: String,
+    short_live_user_access_token: Secret,
+    long_live_user_access_token: Secret,
     //  page_access_token: Option<String>,
     /// The access token token type is used to indicate which type of token is
     /// currently passed to the method. It is required to provide either
     /// page_access_token  or user_access_token. corresponding to the token
     /// passed it
-    page_access_token: String,
+    page_access_token: Secret,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
 /// Empty Client
 impl Default for Client {
     fn default() -> Self {
-        let graph = "https://graph.facebook.com/v23.0/NODE/EDGE".to_string();
+        let graph = graph_url(DEFAULT_GRAPH_HOST, DEFAULT_GRAPH_API_VERSION);
         Self {
             graph,
-            short_live_user_access_token: "".to_string(),
-            long_live_user_access_token: "".to_string(),
-            page_access_token: "".to_string(),
+            short_live_user_access_token: Secret::default(),
+            long_live_user_access_token: Secret::default(),
+            page_access_token: Secret::default(),
+            http_connection: GenericClientConnection::default(),
         }
     }
 }
 
 impl Client {
-    pub fn add_access_token(mut self, access_token: String) -> Client {
-        self.page_access_token = access_token;
-        self
-    }
     /// This method add access token to the client, the method is expecting two
     /// input ( access token and access token type ). Since the access token
     /// type could be user token or page token , use access_token_type  to
@@ -59,6 +91,11 @@ impl Client {
     /// "user_token" while for page token set the "access_toke_type" to be
     /// "page_token" example   Client::new(Token,"access_toke_type".
     /// to_string())
+    ///
+    /// # Panics
+    /// Panics if the default http client can not be built. Use
+    /// [try_new_with_version](Client::try_new_with_version) to get an error
+    /// instead.
     pub fn new(user_access_token: UserToken, page_token: String) -> Client {
         Client::default().add_access_tokens(user_access_token, page_token)
     }
@@ -75,7 +112,9 @@ impl Client {
     /// Using older versions may result in deprecated endpoints or reduced functionality.
     ///
     /// # Panics
-    /// Panics if the API version format is invalid. The version must be in format "vXX.X".
+    /// Panics if the API version format is invalid. The version must be in format "vXX.X",
+    /// or if the default http client can not be built.
+    /// Use [try_new_with_version](Client::try_new_with_version) to get an error instead.
     ///
     /// # Example
     /// ```
@@ -92,27 +131,211 @@ impl Client {
         page_token: String,
         api_version: String,
     ) -> Client {
+        Client::try_new_with_version(user_access_token, page_token, api_version)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as [new_with_version](Client::new_with_version), but an invalid
+    /// API version is returned as a
+    /// [ClientErr::InvalidApiVersion](ClientErr::InvalidApiVersion), and an
+    /// http client which can not be built as its error, instead of
+    /// panicking.
+    ///
+    /// # Example
+    /// ```
+    /// use facebook_api_rs::prelude::{Client, UserToken};
+    ///
+    /// let client = Client::try_new_with_version(
+    ///     UserToken::default(),
+    ///     "page_token".to_string(),
+    ///     "23".to_string(),
+    /// );
+    /// assert!(client.is_err());
+    /// ```
+    pub fn try_new_with_version(
+        user_access_token: UserToken,
+        page_token: String,
+        api_version: String,
+    ) -> Result<Client, ClientErr> {
         // Import the validation function from config module
         use crate::prelude::is_valid_api_version;
 
         if !is_valid_api_version(&api_version) {
-            panic!(
-                "Invalid API version format: '{}'. Expected format: 'vXX.X' (e.g., 'v23.0')",
-                api_version
-            );
+            return Err(ClientErr::InvalidApiVersion(api_version));
         }
 
-        let graph = format!("https://graph.facebook.com/{}/NODE/EDGE", api_version);
+        let graph = graph_url(DEFAULT_GRAPH_HOST, &api_version);
         let mut client = Client {
             graph,
-            short_live_user_access_token: "".to_string(),
-            long_live_user_access_token: "".to_string(),
-            page_access_token: "".to_string(),
+            short_live_user_access_token: Secret::default(),
+            long_live_user_access_token: Secret::default(),
+            page_access_token: Secret::default(),
+            http_connection: GenericClientConnection::try_default()?,
+        };
+        client.long_live_user_access_token = user_access_token.long_lived_token.into();
+        client.short_live_user_access_token = user_access_token.access_token.into();
+        client.page_access_token = page_token.into();
+        Ok(client)
+    }
+}
+
+impl<HttpC: HttpClient> Client<HttpC> {
+    pub fn add_access_token(mut self, access_token: String) -> Self {
+        self.page_access_token = access_token.into();
+        self
+    }
+
+    /// Send every request of this client and the APIs it hands out to another
+    /// Graph API host. The API version of the client is kept.
+    ///
+    /// This is mostly useful to point the crate at a local stand-in of the
+    /// Graph API in tests.
+    ///
+    /// # Example
+    /// ```
+    /// use facebook_api_rs::prelude::{Client, UserToken};
+    ///
+    /// let client = Client::new(UserToken::default(), "page_token".to_string())
+    ///     .add_graph_host("http://127.0.0.1:8080".to_string());
+    ///
+    /// assert_eq!(client.graph_host(), Some("http://127.0.0.1:8080"));
+    /// assert_eq!(client.api_version(), Some("v23.0"));
+    /// ```
+    pub fn add_graph_host(mut self, graph_host: String) -> Self {
+        let api_version = self
+            .api_version()
+            .unwrap_or(DEFAULT_GRAPH_API_VERSION)
+            .to_string();
+        self.graph = graph_url(&graph_host, &api_version);
+        self
+    }
+
+    /// The Graph API host the requests are sent to, e.g.
+    /// "https://graph.facebook.com".
+    pub fn graph_host(&self) -> Option<&str> {
+        self.graph
+            .strip_suffix("/NODE/EDGE")
+            .and_then(|url| url.rsplit_once('/'))
+            .map(|(graph_host, _)| graph_host)
+    }
+
+    /// Replace the http client used by this client and every API it hands
+    /// out.
+    ///
+    /// # Example
+    /// ```
+    /// use facebook_api_rs::prelude::{Client, DefaultHttpClient, HttpClient, UserToken};
+    ///
+    /// let http_client = DefaultHttpClient::new(None).unwrap();
+    /// let client = Client::new(UserToken::default(), "page_token".to_string())
+    ///     .add_http_client(http_client);
+    /// ```
+    pub fn add_http_client<C: HttpClient>(self, http_client: C) -> Client<C> {
+        self.add_shared_http_client(Arc::new(http_client))
+    }
+
+    /// Same as [add_http_client](Client::add_http_client) but for an http
+    /// client which is already shared elsewhere in your application.
+    pub fn add_shared_http_client<C: HttpClient>(self, http_client: Arc<C>) -> Client<C> {
+        Client {
+            graph: self.graph,
+            short_live_user_access_token: self.short_live_user_access_token,
+            long_live_user_access_token: self.long_live_user_access_token,
+            page_access_token: self.page_access_token,
+            http_connection: self.http_connection.with_http_client(http_client),
+        }
+    }
+
+    /// Set how the requests failing with a transient error are retried by
+    /// this client and the APIs it hands out. By default a request is sent
+    /// up to 4 times, see [RetryPolicy](RetryPolicy).
+    ///
+    /// Use [RetryPolicy::none] to turn the retries off.
+    pub fn add_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.http_connection = self.http_connection.with_retry_policy(retry_policy);
+        self
+    }
+
+    /// Sign every request of this client and the APIs it hands out with an
+    /// `appsecret_proof`, as required when "Require App Secret" is turned on
+    /// in the app dashboard.
+    ///
+    /// # Example
+    /// ```
+    /// use facebook_api_rs::prelude::{Client, UserToken};
+    ///
+    /// let client = Client::new(UserToken::default(), "page_token".to_string())
+    ///     .add_app_secret("app_secret".to_string());
+    /// ```
+    pub fn add_app_secret(mut self, app_secret: String) -> Self {
+        self.http_connection = self.http_connection.with_app_secret(app_secret);
+        self
+    }
+
+    /// Delay the requests of this client and the APIs it hands out when the
+    /// usage of the rate limits gets near 100%, see [Throttle](Throttle).
+    pub fn add_throttle(mut self, throttle: Throttle) -> Self {
+        self.http_connection = self.http_connection.with_throttle(throttle);
+        self
+    }
+
+    /// The rate-limit usage reported by the last response received by this
+    /// client or the APIs it handed out.
+    ///
+    /// The APIs take the client by value, so keep a clone of the
+    /// [http_connection](Client::http_connection) to read the usage after a
+    /// call. With concurrent requests, use [with_usage](Client::with_usage)
+    /// to get the usage of a given call.
+    pub fn rate_limit_usage(&self) -> Option<RateLimitUsage> {
+        self.http_connection.rate_limit_usage()
+    }
+
+    /// Call any API of the client, and return its result with the rate-limit
+    /// usage reported by the last response of the call.
+    ///
+    /// `request` is given a clone of the client whose connection keeps the
+    /// usage of its own responses, so the usage does not come from another
+    /// request sent at the same time. The usage of an error is available
+    /// from its [response](ClientErr::response).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use facebook_api_rs::prelude::{Client, UserToken};
+    ///
+    /// # async fn run() -> Result<(), facebook_api_rs::prelude::errors::ClientErr> {
+    /// let client = Client::new(UserToken::default(), "page_token".to_string());
+    /// let (feed, usage) = client
+    ///     .with_usage(|client| client.feed("page_id".to_string()).get())
+    ///     .await?;
+    /// println!("{} posts, {}% of the page limits", feed.data().data.len(), usage.max_percent());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn with_usage<F, Fut, T>(&self, request: F) -> Result<(T, RateLimitUsage), ClientErr>
+    where
+        F: FnOnce(Client<HttpC>) -> Fut,
+        Fut: Future<Output = Result<T, ClientErr>>,
+    {
+        let (http_connection, response_usage) = self.http_connection.recording_usage();
+        let client = Client {
+            graph: self.graph.clone(),
+            short_live_user_access_token: self.short_live_user_access_token.clone(),
+            long_live_user_access_token: self.long_live_user_access_token.clone(),
+            page_access_token: self.page_access_token.clone(),
+            http_connection,
         };
-        client.long_live_user_access_token = user_access_token.long_lived_token;
-        client.short_live_user_access_token = user_access_token.access_token;
-        client.page_access_token = page_token;
-        client
+        let result = request(client).await?;
+        let usage = response_usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+            .unwrap_or_default();
+        Ok((result, usage))
+    }
+
+    /// The connection shared by this client and every API it hands out.
+    pub fn http_connection(&self) -> &GenericClientConnection<HttpC> {
+        &self.http_connection
     }
 
     /// This method add access token to the client when the user has
@@ -122,9 +345,9 @@ impl Client {
         user_access_token: UserToken,
         page_access_token: String,
     ) -> Self {
-        self.long_live_user_access_token = user_access_token.long_lived_token;
-        self.short_live_user_access_token = user_access_token.access_token;
-        self.page_access_token = page_access_token;
+        self.long_live_user_access_token = user_access_token.long_lived_token.into();
+        self.short_live_user_access_token = user_access_token.access_token.into();
+        self.page_access_token = page_access_token.into();
         self
     }
 
@@ -154,8 +377,9 @@ impl Client {
     /// ```
     pub fn api_version(&self) -> Option<&str> {
         self.graph
-            .split('/')
-            .find(|s| s.starts_with('v') && s.contains('.'))
+            .strip_suffix("/NODE/EDGE")
+            .and_then(|url| url.rsplit('/').next())
+            .filter(|version| version.starts_with('v') && version.contains('.'))
     }
 
     /// This method gives an entry point to User API and Facebook pages account
@@ -175,56 +399,69 @@ impl Client {
     /// For information on Tokens check [UserToken](UserToken)    
     ///
     /// Or check [Facebook token doc](https://developers.facebook.com/docs/facebook-login/access-tokens/)
-    pub fn accounts(self, token_live_type: TokenLiveType) -> MeApi {
-        let mut base_url = "".to_owned();
-        if self.short_live_user_access_token.is_empty()
+    pub fn accounts(self, token_live_type: TokenLiveType) -> MeApi<HttpC> {
+        let access_token = if self.short_live_user_access_token.is_empty()
             && self.long_live_user_access_token.is_empty()
         {
-            base_url = self.graph + &"?access_token=".to_string() + &self.page_access_token
+            &self.page_access_token
         } else {
             match token_live_type {
                 TokenLiveType::LONGLIVE => {
-                    base_url = if self.long_live_user_access_token.is_empty() {
-                        self.graph
-                            + &"?access_token=".to_string()
-                            + &self.short_live_user_access_token
+                    if self.long_live_user_access_token.is_empty() {
+                        &self.short_live_user_access_token
                     } else {
-                        self.graph
-                            + &"?access_token=".to_string()
-                            + &self.long_live_user_access_token
+                        &self.long_live_user_access_token
                     }
                 }
                 TokenLiveType::SHORTLIVE => {
-                    base_url = if self.short_live_user_access_token.is_empty() {
-                        self.graph
-                            + &"?access_token=".to_string()
-                            + &self.long_live_user_access_token
+                    if self.short_live_user_access_token.is_empty() {
+                        &self.long_live_user_access_token
                     } else {
-                        self.graph
-                            + &"?access_token=".to_string()
-                            + &self.short_live_user_access_token
+                        &self.short_live_user_access_token
                     }
                 }
             }
-        }
+        };
+        let base_url = RequestUrl::new(self.graph.as_str())
+            .access_token(access_token.expose_secret())
+            .build();
 
-        MeApi::new(base_url)
+        MeApi::new(base_url, self.http_connection)
     }
 
     ///  This method is used to pass user data/crediteniatls to the Post CONTENT
     /// method which will be used to post  to content to the  feed : Note this
     /// API can not be use for posting of vide and image
-    pub fn feed(self, page_id: String) -> FeedApi {
-        let base_url = self.graph.replace("NODE", &page_id);
-        FeedApi::new(base_url, self.page_access_token)
+    pub fn feed(self, page_id: String) -> FeedApi<HttpC> {
+        let base_url = node_url(&self.graph, &page_id);
+        FeedApi::new(
+            base_url,
+            self.page_access_token.into_inner(),
+            self.http_connection,
+        )
     }
 
     ///  This method is used to get the different data avaliable on the page
     /// feed, it takes the "page_post_id" ( combination of the page_ and the
     /// post_id)
-    pub fn post(self, page_post_id: String) -> PostApi {
-        let base_url = self.graph.replace("NODE", &page_post_id);
-        PostApi::new(base_url, self.page_access_token)
+    pub fn post(self, page_post_id: String) -> PostApi<HttpC> {
+        let base_url = node_url(&self.graph, &page_post_id);
+        PostApi::new(
+            base_url,
+            self.page_access_token.into_inner(),
+            self.http_connection,
+        )
+    }
+
+    /// Entry point to the photo API of a page, to upload photos and list
+    /// the photos of the page.
+    pub fn photos(self, page_id: String) -> PhotoApi<HttpC> {
+        let base_url = node_url(&self.graph, &page_id);
+        PhotoApi::new(
+            base_url,
+            self.page_access_token.into_inner(),
+            self.http_connection,
+        )
     }
 
     /// Facebook Video API allows you to publish Videos on Pages and Groups.
@@ -273,9 +510,13 @@ impl Client {
     ///   .video_upload("page_id".to_owned())
     ///  .post_by_link(video_url, post_description,title).await?;
     /// ```
-    pub fn video_upload(self, page_id: String) -> VideoApi {
-        let base_url = self.graph.replace("NODE", &page_id);
-        VideoApi::new(base_url, self.page_access_token) // initit videp Api
+    pub fn video_upload(self, page_id: String) -> VideoApi<HttpC> {
+        let base_url = node_url(&self.graph, &page_id);
+        VideoApi::new(
+            base_url,
+            self.page_access_token.into_inner(),
+            self.http_connection,
+        ) // initit videp Api
     }
 
     /// Entry point to instagram Account api.
@@ -315,8 +556,12 @@ impl Client {
     ///            .account_by_id("instagram_account_id".to_owned()).await?;
     /// ```
 
-    pub fn instagram_account(self) -> InstagramApi {
-        InstagramApi::new(self.page_access_token, self.graph)
+    pub fn instagram_account(self) -> InstagramApi<HttpC> {
+        InstagramApi::new(
+            self.page_access_token.into_inner(),
+            self.graph,
+            self.http_connection,
+        )
     }
 
     /// You can use the Instagram Graph API to publish single images or single
@@ -343,10 +588,14 @@ impl Client {
     pub fn instagram_content_publishing(
         self,
         instagram_id: String,
-    ) -> InstagramContentPublishingApi {
-        let base_url = self.graph.replace("NODE", &instagram_id);
-
-        InstagramContentPublishingApi::new(self.page_access_token, base_url)
+    ) -> InstagramContentPublishingApi<HttpC> {
+        let base_url = node_url(&self.graph, &instagram_id);
+
+        InstagramContentPublishingApi::new(
+            self.page_access_token.into_inner(),
+            base_url,
+            self.http_connection,
+        )
     }
 
     /// Represents an Instagram album, photo, story, or video (uploaded video,
@@ -363,15 +612,23 @@ impl Client {
     /// * Comment on a media.
     /// * Update a post.
     /// * Insights on a media
-    pub fn instagram_media(self, media_id: String) -> InstagramMediaApi {
-        let base_url = self.graph.replace("NODE", &media_id);
-
-        InstagramMediaApi::new(self.page_access_token, base_url)
+    pub fn instagram_media(self, media_id: String) -> InstagramMediaApi<HttpC> {
+        let base_url = node_url(&self.graph, &media_id);
+
+        InstagramMediaApi::new(
+            self.page_access_token.into_inner(),
+            base_url,
+            self.http_connection,
+        )
     }
 
-    pub fn search_pages(self) -> PagesSearchAPI {
-        let base_url = self.graph.replace("NODE/EDGE", "pages/search");
-        PagesSearchAPI::new(base_url, self.page_access_token)
+    pub fn search_pages(self) -> PagesSearchAPI<HttpC> {
+        let base_url = graph_path(&self.graph, &["pages", "search"]);
+        PagesSearchAPI::new(
+            base_url,
+            self.page_access_token.into_inner(),
+            self.http_connection,
+        )
     }
 
     /// Entry point to the instagram hashtag api
@@ -392,20 +649,65 @@ impl Client {
     /// hashtags that facebook have deemed sensitive or offensive.
     ///
     /// [facebook hashtag doc](https://developers.facebook.com/docs/instagram-api/guides/hashtag-search)
-    pub fn instagram_hashtag(self, instagram_id: String) -> HashtagAPi {
-        let mut base_url = self.graph.replace("NODE/", "");
-        base_url = base_url + "?user_id=" + &instagram_id;
-        HashtagAPi::new(self.page_access_token, base_url)
+    pub fn instagram_hashtag(self, instagram_id: String) -> HashtagAPi<HttpC> {
+        let base_url = RequestUrl::new(graph_path(&self.graph, &["EDGE"]))
+            .param("user_id", instagram_id)
+            .build();
+        HashtagAPi::new(
+            self.page_access_token.into_inner(),
+            base_url,
+            self.http_connection,
+        )
+    }
+
+    /// A [UserToken](UserToken) sending its requests, e.g. the exchange of a
+    /// code or the debugging of a token, through the connection of this
+    /// client.
+    pub fn user_token(self) -> UserToken
+    where
+        HttpC: 'static,
+    {
+        UserToken::default()
+            .set_url(self.graph)
+            .set_http_connection(self.http_connection)
     }
 
-    pub fn user_token(self) -> UserToken {
-        // AccessTokenInformation::default()
-        UserToken::default().set_url(self.graph)
+    /// Entry point to any node of the Graph API, and to its edges, for the
+    /// endpoints which are not wrapped by the crate. The requests are sent
+    /// with the page access token, see [NodeApi](NodeApi).
+    pub fn node(self, node_id: impl AsRef<str>) -> NodeApi<HttpC> {
+        let base_url = node_url(&self.graph, node_id.as_ref());
+        NodeApi::new(
+            base_url,
+            self.page_access_token.into_inner(),
+            self.http_connection,
+        )
     }
 
-    pub fn batch_request(self, page_id: String) -> BatchApi {
+    /// Entry point to fetch several nodes, e.g. posts or medias, in one
+    /// request with the `ids` parameter, see [IdsApi](IdsApi).
+    pub fn ids<I, S>(self, ids: I) -> IdsApi<HttpC>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
         let base_url = self.graph.replace("/NODE/EDGE", "");
-        BatchApi::new(base_url, self.page_access_token, page_id)
+        IdsApi::new(
+            base_url,
+            self.page_access_token.into_inner(),
+            ids.into_iter().map(Into::into).collect(),
+            self.http_connection,
+        )
+    }
+
+    pub fn batch_request(self, page_id: String) -> BatchApi<HttpC> {
+        let base_url = self.graph.replace("/NODE/EDGE", "");
+        BatchApi::new(
+            base_url,
+            self.page_access_token.into_inner(),
+            page_id,
+            self.http_connection,
+        )
     }
 }
 
@@ -413,6 +715,10 @@ impl Client {
 mod test {
     use crate::graph::client::Client;
     use crate::login::token::UserToken;
+    use crate::universal::client::DefaultHttpClient;
+    use crate::universal::errors::ClientErr;
+    use crate::universal::HttpClient;
+    use std::sync::Arc;
 
     #[test]
     fn test_default_api_version() {
@@ -441,6 +747,52 @@ mod test {
         assert!(version.unwrap().contains('.'));
     }
 
+    #[test]
+    fn test_graph_host_override() {
+        let client = Client::new_with_version(
+            UserToken::default(),
+            "page_token".to_string(),
+            "v22.0".to_string(),
+        )
+        .add_graph_host("http://v1.localhost:8080/".to_string());
+
+        assert_eq!(client.graph, "http://v1.localhost:8080/v22.0/NODE/EDGE");
+        assert_eq!(client.graph_host(), Some("http://v1.localhost:8080"));
+        assert_eq!(client.api_version(), Some("v22.0"));
+
+        let user_token = client.user_token();
+        assert_eq!(
+            user_token.graph_url(),
+            "http://v1.localhost:8080/v22.0/NODE/EDGE"
+        );
+    }
+
+    #[test]
+    fn test_node_ids_are_path_segments() {
+        let client = || Client::new(UserToken::default(), "page_token".to_string());
+
+        let url = client().node("PLEDGE").edge("feed").url();
+        assert!(url.starts_with("https://graph.facebook.com/v23.0/PLEDGE/feed?"));
+
+        let url = client().node("12/34?fields=name#x").url();
+        assert!(url.starts_with("https://graph.facebook.com/v23.0/12%2F34%3Ffields=name%23x?"));
+    }
+
+    #[test]
+    fn test_shared_http_client() {
+        let http_client = Arc::new(DefaultHttpClient::new(None).unwrap());
+        let client = Client::new(UserToken::default(), "page_token".to_string())
+            .add_shared_http_client(http_client.clone());
+
+        assert!(Arc::ptr_eq(
+            client.http_connection().http_client(),
+            &http_client
+        ));
+
+        let connection = client.http_connection().clone();
+        assert!(Arc::ptr_eq(connection.http_client(), &http_client));
+    }
+
     #[test]
     #[should_panic(expected = "Invalid API version format")]
     fn test_invalid_version_format_panic() {
@@ -451,6 +803,43 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    async fn test_invalid_input_is_an_error() {
+        let error = Client::try_new_with_version(
+            UserToken::default(),
+            "page_token".to_string(),
+            "23.0".to_string(),
+        )
+        .err();
+        assert!(matches!(error, Some(ClientErr::InvalidApiVersion(version)) if version == "23.0"));
+
+        let error = Client::new(UserToken::default(), "page_token".to_string())
+            .add_graph_host("not a host".to_string())
+            .node("1234")
+            .get::<serde_json::Value>()
+            .await
+            .unwrap_err();
+        match error {
+            ClientErr::InvalidUrl(message) => {
+                assert!(message.starts_with("not a host/v23.0/1234"));
+                assert!(!message.contains("page_token"));
+            }
+            error => panic!("unexpected error: {:?}", error),
+        }
+
+        #[cfg(feature = "reqwest")]
+        assert!(matches!(
+            crate::prelude::file_analyze::FileResultServer::file_analyze(
+                "no/such/video.mp4".to_string()
+            ),
+            Err(ClientErr::Io(_))
+        ));
+        assert!(matches!(
+            UserToken::try_extract_user_tokens("redirect_url/?#".to_string()),
+            Err(ClientErr::InvalidUrl(_))
+        ));
+    }
+
     //  #[test]
     // fn test_builder() {
     // let mut token = UserToken::default();
@@ -1,6 +1,341 @@
+//! The lists returned by the Graph API, and the streams following their
+//! pages.
+//!
+//! The edges returning a list, e.g. the feed or the photos of a page, answer
+//! one page at a time with a [Data](Data) and a [Paging](Paging) giving the
+//! url of the next page. The `*_stream` methods of the APIs return a
+//! [Stream](Stream) of the items of every page, which asks for the next page
+//! when the items of the current one are consumed.
+//!
+//! # Example
+//! ```no_run
+//! use facebook_api_rs::prelude::{Client, PageOptions, UserToken};
+//! use futures_util::{pin_mut, StreamExt};
+//!
+//! # async fn photos() {
+//! let photos = Client::new(UserToken::default(), "page_token".to_string())
+//!     .photos("page_id".to_string())
+//!     .all_photos_stream(None, PageOptions::new().limit(50).max_items(200));
+//! pin_mut!(photos);
+//! while let Some(photo) = photos.next().await {
+//!     println!("{}", photo.unwrap().id);
+//! }
+//! # }
+//! ```
+
+use crate::prelude::errors::ClientErr;
+use crate::prelude::GenericClientConnection;
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
+use futures_util::stream::{self, Stream};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use url::Url;
+
 /// Struct for which can hold data types.
 #[derive(Deserialize, Debug, Clone, Serialize)]
 pub struct Data<T> {
     pub data: Vec<T>,
+    /// The cursors and the urls of the pages around this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paging: Option<Paging>,
+}
+
+impl<T> Data<T> {
+    /// The url of the next page, if there is one.
+    pub fn next(&self) -> Option<&str> {
+        self.paging.as_ref()?.next.as_deref()
+    }
+}
+
+/// The `paging` object of a list.
+///
+/// For more information check <https://developers.facebook.com/docs/graph-api/results>.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Paging {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursors: Option<Cursors>,
+    /// The url of the next page, absent on the last page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    /// The url of the previous page, absent on the first page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+/// The cursors pointing to the first and the last item of a page, to pass
+/// as the `before` or `after` parameter of a request.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Cursors {
+    #[serde(default)]
+    pub before: String,
+    #[serde(default)]
+    pub after: String,
+}
+
+/// How the pages of a list are requested by a stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageOptions {
+    limit: Option<u32>,
+    max_items: Option<usize>,
+}
+
+impl PageOptions {
+    pub fn new() -> Self {
+        PageOptions::default()
+    }
+
+    /// The number of items asked for each page. Facebook may return fewer.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Stop the stream after this number of items, without asking for more
+    /// pages.
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+}
+
+struct PageState<T, HttpC: HttpClient> {
+    http_connection: GenericClientConnection<HttpC>,
+    next: Option<String>,
+    access_token: Option<String>,
+    items: VecDeque<T>,
+    remaining: Option<usize>,
+}
+
+/// The items of a list starting at `url`, following the `next` url of every
+/// page. The stream ends after the last page, after
+/// [max_items](PageOptions::max_items), or after the first error.
+///
+/// The access token of `url` is added to the `next` urls which do not have
+/// one, since it is sent in a header and so is not in the urls returned by
+/// Facebook.
+pub fn paginate<T, HttpC>(
+    http_connection: GenericClientConnection<HttpC>,
+    url: String,
+    options: PageOptions,
+) -> impl Stream<Item = Result<T, ClientErr>>
+where
+    T: DeserializeOwned,
+    HttpC: HttpClient,
+{
+    let url = RequestUrl::new(url)
+        .optional_param("limit", options.limit)
+        .build();
+    let state = PageState {
+        http_connection,
+        access_token: query_parameter(&url, "access_token"),
+        next: Some(url),
+        items: VecDeque::new(),
+        remaining: options.max_items,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if state.remaining == Some(0) {
+                return None;
+            }
+            if let Some(item) = state.items.pop_front() {
+                state.remaining = state.remaining.map(|remaining| remaining - 1);
+                return Some((Ok(item), state));
+            }
+
+            let url = state.next.take()?;
+            match state
+                .http_connection
+                .get::<Data<T>>(url, "".to_string())
+                .await
+            {
+                Ok(page) => {
+                    // An empty page ends the list, even with a `next` url.
+                    if !page.data.is_empty() {
+                        state.next = page
+                            .paging
+                            .and_then(|paging| paging.next)
+                            .map(|next| with_access_token(next, state.access_token.as_deref()));
+                    }
+                    state.items = page.data.into();
+                }
+                Err(error) => return Some((Err(error), state)),
+            }
+        }
+    })
+}
+
+fn query_parameter(url: &str, name: &str) -> Option<String> {
+    Url::parse(url)
+        .ok()?
+        .query_pairs()
+        .find(|(key, value)| key == name && !value.is_empty())
+        .map(|(_, value)| value.into_owned())
+}
+
+fn with_access_token(next: String, access_token: Option<&str>) -> String {
+    let Some(access_token) = access_token else {
+        return next;
+    };
+    match Url::parse(&next) {
+        Ok(mut url) if query_parameter(&next, "access_token").is_none() => {
+            url.query_pairs_mut()
+                .append_pair("access_token", access_token);
+            url.into()
+        }
+        _ => next,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paging_is_kept() {
+        let page: Data<serde_json::Value> = serde_json::from_str(
+            r#"{
+                "data": [{"id": "1"}],
+                "paging": {
+                    "cursors": {"before": "MQ", "after": "Mg"},
+                    "next": "https://graph.facebook.com/v23.0/1234/photos?after=Mg"
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            page.next(),
+            Some("https://graph.facebook.com/v23.0/1234/photos?after=Mg")
+        );
+        assert_eq!(page.paging.unwrap().cursors.unwrap().after, "Mg");
+
+        let last: Data<serde_json::Value> = serde_json::from_str(r#"{"data": []}"#).unwrap();
+        assert!(last.next().is_none());
+    }
+
+    #[test]
+    fn test_access_token_is_added_to_the_next_url() {
+        assert_eq!(
+            with_access_token("https://x/1234/feed?after=Mg".to_string(), Some("a+b")),
+            "https://x/1234/feed?after=Mg&access_token=a%2Bb"
+        );
+        assert_eq!(
+            with_access_token(
+                "https://x/1234/feed?access_token=other".to_string(),
+                Some("a")
+            ),
+            "https://x/1234/feed?access_token=other"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_follows_the_next_pages() {
+        use crate::prelude::mock_server::{MockGraphServer, MockResponse};
+        use futures_util::StreamExt;
+        use serde_json::json;
+
+        let server = MockGraphServer::start().unwrap();
+        let photo = |id: &str| {
+            json!({
+                "id": id,
+                "message": "",
+                "from": {"id": "1234", "name": "Page"},
+                "permalink_url": "",
+            })
+        };
+        let next = |after: &str| format!("{}/v23.0/1234/photos?after={}", server.url(), after);
+        // The mock added last answers first.
+        server.mock_once(
+            "GET",
+            "/1234/photos",
+            MockResponse::ok(json!({ "data": [photo("5")] })),
+        );
+        server.mock_once(
+            "GET",
+            "/1234/photos",
+            MockResponse::ok(json!({
+                "data": [photo("3"), photo("4")],
+                "paging": { "next": next("Mg") },
+            })),
+        );
+        server.mock_once(
+            "GET",
+            "/1234/photos",
+            MockResponse::ok(json!({
+                "data": [photo("1"), photo("2")],
+                "paging": { "cursors": { "before": "MA", "after": "MQ" }, "next": next("MQ") },
+            })),
+        );
+
+        let photos = server
+            .client("page_token".to_string())
+            .photos("1234".to_string());
+        let ids: Vec<String> = photos
+            .all_photos_stream(None, PageOptions::new().limit(2))
+            .map(|photo| photo.unwrap().id)
+            .collect()
+            .await;
+        assert_eq!(ids, vec!["1", "2", "3", "4", "5"]);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].param("limit"), Some("2"));
+        assert_eq!(requests[1].param("after"), Some("MQ"));
+        // The token is sent with the next pages too.
+        assert!(requests
+            .iter()
+            .all(|request| request.header("authorization") == Some("Bearer page_token")));
+    }
+
+    #[tokio::test]
+    async fn test_stream_stops_after_max_items_and_errors() {
+        use crate::prelude::mock_server::{MockGraphServer, MockResponse};
+        use futures_util::StreamExt;
+        use serde_json::json;
+
+        let server = MockGraphServer::start().unwrap();
+        let next = format!("{}/v23.0/me/accounts?after=MQ", server.url());
+        let page = |id: &str| {
+            json!({
+                "id": id,
+                "name": "Page",
+                "access_token": "page_access_token",
+                "category": "Software",
+                "category_list": [],
+                "tasks": [],
+            })
+        };
+        server.mock(
+            "GET",
+            "/me/accounts",
+            MockResponse::ok(json!({
+                "data": [page("1"), page("2")],
+                "paging": { "next": next },
+            })),
+        );
+        let pages: Vec<_> = server
+            .client("page_token".to_string())
+            .accounts(crate::prelude::TokenLiveType::LONGLIVE)
+            .pages_by_me_stream(PageOptions::new().max_items(3))
+            .collect()
+            .await;
+        assert_eq!(pages.len(), 3);
+        assert_eq!(server.requests().len(), 2);
+
+        server.mock(
+            "GET",
+            "/me/accounts",
+            MockResponse::error(400, 190, "OAuthException", "Invalid OAuth access token."),
+        );
+        let pages: Vec<_> = server
+            .client("page_token".to_string())
+            .accounts(crate::prelude::TokenLiveType::LONGLIVE)
+            .pages_by_me_stream(PageOptions::new())
+            .collect()
+            .await;
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].is_err());
+    }
 }
@@ -0,0 +1,138 @@
+//! Fetch several nodes in one request with the `ids` parameter.
+//!
+//! `GET /?ids=a,b,c&fields=...` returns a json object with one entry per
+//! id. An [IdsApi](IdsApi), given by [Client::ids](crate::prelude::Client::ids),
+//! reads it as a `HashMap` of the nodes by id, and splits the ids in requests
+//! of at most [MAX_IDS_PER_REQUEST](MAX_IDS_PER_REQUEST) ids.
+//!
+//! For more information check <https://developers.facebook.com/docs/graph-api/guides/multi-id-lookup>.
+//!
+//! # Example
+//! ```no_run
+//! use facebook_api_rs::prelude::fields::FieldSelection;
+//! use facebook_api_rs::prelude::utils::GetPostResponse;
+//! use facebook_api_rs::prelude::{Client, UserToken};
+//! use std::collections::HashMap;
+//!
+//! # async fn posts() {
+//! let posts: HashMap<String, GetPostResponse> =
+//!     Client::new(UserToken::default(), "page_token".to_string())
+//!         .ids(["page_id_post_1", "page_id_post_2"])
+//!         .fields(FieldSelection::from_iter(["id", "message", "from", "permalink_url"]))
+//!         .get()
+//!         .await
+//!         .unwrap();
+//! # }
+//! ```
+
+use crate::prelude::errors::ClientErr;
+use crate::prelude::fields::FieldSelection;
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+/// The most ids Facebook accepts in the `ids` parameter of one request.
+pub const MAX_IDS_PER_REQUEST: usize = 50;
+
+/// A request fetching several nodes by their ids.
+#[derive(Clone)]
+pub struct IdsApi<HttpC: HttpClient = DefaultHttpClient> {
+    base_url: String,
+    access_token: String,
+    ids: Vec<String>,
+    fields: Option<FieldSelection>,
+    http_connection: GenericClientConnection<HttpC>,
+}
+
+impl<HttpC: HttpClient> IdsApi<HttpC> {
+    /// Note: this method is called inside the Client.
+    pub fn new(
+        base_url: String,
+        access_token: String,
+        ids: Vec<String>,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> IdsApi<HttpC> {
+        IdsApi {
+            base_url,
+            access_token,
+            ids,
+            fields: None,
+            http_connection,
+        }
+    }
+
+    /// The fields of every node, see [FieldSelection](FieldSelection).
+    pub fn fields(mut self, fields: FieldSelection) -> Self {
+        self.fields = Some(fields);
+        self
+    }
+
+    /// Send the requests with another access token than the page access
+    /// token of the client.
+    pub fn access_token(mut self, access_token: impl Into<String>) -> Self {
+        self.access_token = access_token.into();
+        self
+    }
+
+    /// The nodes by id, read as `T`. The ids are sent by requests of at most
+    /// [MAX_IDS_PER_REQUEST](MAX_IDS_PER_REQUEST) ids, one after the other,
+    /// and the first failing request fails the whole fetch.
+    pub async fn get<T: DeserializeOwned>(&self) -> Result<HashMap<String, T>, ClientErr> {
+        let mut nodes = HashMap::with_capacity(self.ids.len());
+        for ids in self.ids.chunks(MAX_IDS_PER_REQUEST) {
+            let resp = self
+                .http_connection
+                .get::<HashMap<String, T>>(self.url(ids), "".to_string())
+                .await?;
+            nodes.extend(resp);
+        }
+        Ok(nodes)
+    }
+
+    fn url(&self, ids: &[String]) -> String {
+        RequestUrl::new(self.base_url.clone() + "/")
+            .param("ids", ids.join(","))
+            .optional_param("fields", self.fields.as_ref())
+            .access_token(&self.access_token)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::mock_server::MockGraphServer;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Node {
+        id: String,
+    }
+
+    #[tokio::test]
+    async fn test_ids_are_split_in_requests_of_50() {
+        let server = MockGraphServer::start().unwrap();
+        let ids: Vec<String> = (0..120).map(|id| format!("1234_{}", id)).collect();
+
+        let nodes: HashMap<String, Node> = server
+            .client("page_token".to_string())
+            .ids(ids.clone())
+            .fields(FieldSelection::from_iter(["id"]))
+            .get()
+            .await
+            .unwrap();
+
+        assert_eq!(nodes.len(), 120);
+        assert!(ids.iter().all(|id| nodes[id].id == *id));
+        let requests = server.requests();
+        let sizes: Vec<usize> = requests
+            .iter()
+            .map(|request| request.param("ids").unwrap().split(',').count())
+            .collect();
+        assert_eq!(sizes, vec![50, 50, 20]);
+        assert_eq!(requests[0].path, "/v23.0/");
+        assert_eq!(requests[0].param("fields"), Some("id"));
+    }
+}
@@ -3,9 +3,12 @@
 //! [facebook docs](https://developers.facebook.com/docs/instagram-api/reference/page)
 
 use crate::prelude::errors::ClientErr;
-use crate::prelude::HttpConnection;
+use crate::prelude::fields::FieldSelection;
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
+use crate::universal::request_url::{node_url, RequestUrl};
+use crate::universal::HttpClient;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use urlencoding::encode;
 
 /// Instagram business account
 /// ```
@@ -30,16 +33,22 @@ pub struct InstagramAccountId {
 }
 
 #[derive(Clone)]
-pub struct InstagramApi {
+pub struct InstagramApi<HttpC: HttpClient = DefaultHttpClient> {
     page_access_token: String,
     base_url: String,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl InstagramApi {
-    pub fn new(page_access_token: String, base_url: String) -> InstagramApi {
+impl<HttpC: HttpClient> InstagramApi<HttpC> {
+    pub fn new(
+        page_access_token: String,
+        base_url: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> InstagramApi<HttpC> {
         InstagramApi {
             page_access_token,
             base_url,
+            http_connection,
         }
     }
 
@@ -48,13 +57,15 @@ impl InstagramApi {
         self,
         facebook_page_id: String,
     ) -> Result<InstagramAccountIds, ClientErr> {
-        let graph_url = self.base_url.replace("NODE", &facebook_page_id);
-        let base_url = graph_url.replace("EDGE", "?");
-        let url = base_url
-            + "fields=instagram_business_account"
-            + "&access_token="
-            + &self.page_access_token;
-        let resp = HttpConnection::get::<InstagramAccountIds>(url, "".to_string()).await?;
+        let url = RequestUrl::new(node_url(&self.base_url, &facebook_page_id))
+            .node()
+            .fields(&FieldSelection::from_iter(["instagram_business_account"]))
+            .access_token(&self.page_access_token)
+            .build();
+        let resp = self
+            .http_connection
+            .get::<InstagramAccountIds>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
@@ -84,14 +95,28 @@ impl InstagramApi {
     /// ```
     /// [facebook account doc](https://developers.facebook.com/docs/instagram-api/reference/ig-user)
     pub async fn account_by_id(self, instagram_id: String) -> Result<InstagramAccount, ClientErr> {
-        let graph_url = self.base_url.replace("NODE", &instagram_id);
-        let url = graph_url.replace("EDGE", "?");
-
-        let url_fields = Fields::default().build_url_with_fields();
+        let fields = FieldSelection::from_iter(Fields::default().fields);
+        self.account_by_id_with_fields::<InstagramAccount>(instagram_id, fields)
+            .await
+    }
 
-        let request_url =
-            url + "fields=" + url_fields.as_str() + "&access_token=" + &self.page_access_token;
-        let resp = HttpConnection::get::<InstagramAccount>(request_url, "".to_string()).await?;
+    /// Same as [account_by_id](InstagramApi::account_by_id), with the fields
+    /// chosen by the caller, see [FieldSelection](FieldSelection). The
+    /// account is returned as `T`, which may be a type of the caller.
+    pub async fn account_by_id_with_fields<T: DeserializeOwned>(
+        self,
+        instagram_id: String,
+        fields: FieldSelection,
+    ) -> Result<T, ClientErr> {
+        let request_url = RequestUrl::new(node_url(&self.base_url, &instagram_id))
+            .node()
+            .fields(&fields)
+            .access_token(&self.page_access_token)
+            .build();
+        let resp = self
+            .http_connection
+            .get::<T>(request_url, "".to_string())
+            .await?;
         Ok(resp)
     }
 }
@@ -147,19 +172,3 @@ impl Default for Fields {
         Self { fields }
     }
 }
-
-impl Fields {
-    pub fn build_url_with_fields(self) -> String {
-        let mut url_fields = "".to_string();
-        let fields_count = Fields::default().fields.len();
-        for (count, field) in Fields::default().fields.into_iter().enumerate() {
-            if count < fields_count - 1 {
-                url_fields = url_fields + &field + ",";
-            } else {
-                url_fields = url_fields + &field;
-            }
-        }
-        url_fields = String::from(encode(url_fields.as_str())); // encode the url
-        url_fields
-    }
-}
@@ -4,19 +4,28 @@
 
 use crate::prelude::errors::ClientErr;
 use crate::prelude::utils::Id;
-use crate::prelude::HttpConnection;
+use crate::prelude::{paginate, DefaultHttpClient, GenericClientConnection, PageOptions, Paging};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
+use futures_util::Stream;
 use serde::Deserialize;
 
-pub struct HashtagAPi {
+pub struct HashtagAPi<HttpC: HttpClient = DefaultHttpClient> {
     access_token: String,
     base_url: String,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl HashtagAPi {
-    pub fn new(access_token: String, base_url: String) -> HashtagAPi {
+impl<HttpC: HttpClient> HashtagAPi<HttpC> {
+    pub fn new(
+        access_token: String,
+        base_url: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> HashtagAPi<HttpC> {
         HashtagAPi {
             access_token,
             base_url,
+            http_connection,
         }
     }
 
@@ -29,10 +38,16 @@ impl HashtagAPi {
     ///
     /// Note: if you search for a key w
     pub async fn hashtag_search(self, hashtag_key_word: String) -> Result<HashtagId, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "ig_hashtag_search");
-        let url = base_url + "&q=" + &hashtag_key_word + "&access_token=" + &self.access_token;
+        let url = RequestUrl::new(self.base_url.as_str())
+            .edge("ig_hashtag_search")
+            .param("q", hashtag_key_word)
+            .access_token(&self.access_token)
+            .build();
 
-        let resp = HttpConnection::get::<HashtagId>(url, "".to_string()).await?;
+        let resp = self
+            .http_connection
+            .get::<HashtagId>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
@@ -46,14 +61,35 @@ impl HashtagAPi {
         self,
         hashtag_id: String,
     ) -> Result<MediaIds, ClientErr> {
-        let edge = hashtag_id + "/recent_media";
-        let base_url = self.base_url.replace("EDGE", edge.as_str());
-        let url = base_url + "&access_token=" + &self.access_token;
+        let url = self.recent_medias_url(hashtag_id);
 
-        let resp = HttpConnection::get::<MediaIds>(url, "".to_string()).await?;
+        let resp = self
+            .http_connection
+            .get::<MediaIds>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
+    /// Same as
+    /// [recent_medias_by_hashtag_id](HashtagAPi::recent_medias_by_hashtag_id),
+    /// but returns the medias of every page, following the `next` page while
+    /// the stream is consumed.
+    pub fn recent_medias_by_hashtag_id_stream(
+        self,
+        hashtag_id: String,
+        options: PageOptions,
+    ) -> impl Stream<Item = Result<Id, ClientErr>> {
+        let url = self.recent_medias_url(hashtag_id);
+        paginate(self.http_connection, url, options)
+    }
+
+    fn recent_medias_url(&self, hashtag_id: String) -> String {
+        RequestUrl::new(self.base_url.as_str())
+            .segments(&[&hashtag_id, "recent_media"])
+            .access_token(&self.access_token)
+            .build()
+    }
+
     /// Get a collection of media objects from top media post that have a given
     /// hashtag. For example: `#Coke`
     ///
@@ -69,11 +105,15 @@ impl HashtagAPi {
     /// Client::new(UserToken::default(), "facebook_page_token".to_owned() )
     /// ```
     pub async fn top_medias_by_hashtag_id(self, hashtag_id: String) -> Result<MediaIds, ClientErr> {
-        let edge = hashtag_id + "/top_media";
-        let base_url = self.base_url.replace("EDGE", edge.as_str());
-        let url = base_url + "&access_token=" + &self.access_token;
+        let url = RequestUrl::new(self.base_url.as_str())
+            .segments(&[&hashtag_id, "top_media"])
+            .access_token(&self.access_token)
+            .build();
 
-        let resp = HttpConnection::get::<MediaIds>(url, "".to_string()).await?;
+        let resp = self
+            .http_connection
+            .get::<MediaIds>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 }
@@ -87,6 +127,9 @@ impl HashtagAPi {
 #[derive(Deserialize, Debug)]
 pub struct MediaIds {
     pub data: Vec<Id>,
+    /// The cursors and the urls of the pages around this one.
+    #[serde(default)]
+    pub paging: Option<Paging>,
 }
 
 #[derive(Deserialize, Debug)]
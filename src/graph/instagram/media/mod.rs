@@ -7,21 +7,30 @@
 //! For more information check [Facebook doc](https://developers.facebook.com/docs/instagram-api/reference/ig-media).
 
 use crate::prelude::errors::ClientErr;
-use crate::prelude::HttpConnection;
+use crate::prelude::fields::FieldSelection;
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use urlencoding::encode;
 
-#[derive(Deserialize, Clone)]
-pub struct InstagramMediaApi {
+#[derive(Clone)]
+pub struct InstagramMediaApi<HttpC: HttpClient = DefaultHttpClient> {
     access_token: String,
     base_url: String,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl InstagramMediaApi {
-    pub fn new(access_token: String, base_url: String) -> InstagramMediaApi {
+impl<HttpC: HttpClient> InstagramMediaApi<HttpC> {
+    pub fn new(
+        access_token: String,
+        base_url: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> InstagramMediaApi<HttpC> {
         InstagramMediaApi {
             access_token,
             base_url,
+            http_connection,
         }
     }
 
@@ -30,18 +39,16 @@ impl InstagramMediaApi {
         self,
         comment_message: String,
     ) -> Result<InstaMediaContainerId, ClientErr> {
-        let self_data = self.clone();
-        let base_url = self.base_url.replace("EDGE", "comments");
-        let comment = comment_message;
-        let message = encode(&comment);
-        let url = base_url.to_string()
-            + "?message="
-            + (&message).as_ref()
-            + "&access_token="
-            + &self_data.access_token;
+        let url = RequestUrl::new(self.base_url.as_str())
+            .edge("comments")
+            .param("message", comment_message)
+            .access_token(&self.access_token)
+            .build();
 
-        let resp =
-            HttpConnection::post::<InstaMediaContainerId, String>(url, "".to_string()).await?;
+        let resp = self
+            .http_connection
+            .post::<InstaMediaContainerId, String>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
@@ -50,19 +57,26 @@ impl InstagramMediaApi {
     /// It returns the information about the media with response of
     /// [MediaContainerData](MediaContainerData)
     pub async fn media_data(self) -> Result<MediaContainerData, ClientErr> {
-        let mut url = self.base_url.replace("EDGE", "?fields=");
+        let fields = FieldSelection::from_iter(Fields::default().fields);
+        self.media_data_with_fields::<MediaContainerData>(fields)
+            .await
+    }
 
-        let fields_count = Fields::default().fields.len();
-        for (count, field) in Fields::default().fields.into_iter().enumerate() {
-            if count < fields_count - 1 {
-                url = url + &field + ",";
-            } else {
-                url = url + &field; // remove the comma in the last filed
-            }
-        }
-        url = url + "&access_token=" + &self.access_token;
+    /// Same as [media_data](InstagramMediaApi::media_data), with the fields
+    /// chosen by the caller, e.g. `children{media_url}`, see
+    /// [FieldSelection](FieldSelection). The media is returned as `T`, which
+    /// may be a type of the caller.
+    pub async fn media_data_with_fields<T: DeserializeOwned>(
+        self,
+        fields: FieldSelection,
+    ) -> Result<T, ClientErr> {
+        let url = RequestUrl::new(self.base_url.as_str())
+            .node()
+            .fields(&fields)
+            .access_token(&self.access_token)
+            .build();
 
-        let resp = HttpConnection::get::<MediaContainerData>(url, "".to_string()).await?;
+        let resp = self.http_connection.get::<T>(url, "".to_string()).await?;
         Ok(resp)
     }
     // This method allows you to check the status for a given media.
@@ -159,7 +173,7 @@ impl Default for Fields {
             "owner",
             "permalink",
             "thumbnail_url",
-            "timestamp ",
+            "timestamp",
             "username",
             "video_title",
         ];
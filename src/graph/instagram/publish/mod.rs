@@ -23,11 +23,14 @@
 #![allow(dead_code, unused)]
 
 use crate::prelude::errors::ClientErr;
+use crate::prelude::fields::FieldSelection;
 use crate::prelude::structs::MediaType;
-use crate::prelude::HttpConnection;
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
 // use facebook_api_rs::prelude::search::PagesSearchAPI;
 use serde::{Deserialize, Serialize};
-use urlencoding::encode;
+use serde_json::json;
 
 //@Todo: remove this struct
 #[derive(Deserialize, Debug, Clone, Default, Serialize)]
@@ -100,17 +103,23 @@ pub struct InstagramPostParams {
 ///  Client::new(UserToken::default(), "facebook_page_token".to_owned())
 ///     .instagram_content_publishing("instagram_id".to_owned()).publish_container()
 /// ```
-#[derive(Deserialize, Clone, Serialize)]
-pub struct InstagramContentPublishingApi {
+#[derive(Clone)]
+pub struct InstagramContentPublishingApi<HttpC: HttpClient = DefaultHttpClient> {
     pub access_token: String,
     pub base_url: String,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl InstagramContentPublishingApi {
-    pub fn new(access_token: String, base_url: String) -> InstagramContentPublishingApi {
+impl<HttpC: HttpClient> InstagramContentPublishingApi<HttpC> {
+    pub fn new(
+        access_token: String,
+        base_url: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> InstagramContentPublishingApi<HttpC> {
         InstagramContentPublishingApi {
             access_token,
             base_url,
+            http_connection,
         }
     }
 
@@ -221,24 +230,18 @@ impl InstagramContentPublishingApi {
         post_caption: String,
         location_page_id: Option<String>,
     ) -> Result<InstagramContainerId, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "media");
-        let caption = encode(&post_caption);
-
-        let mut url = base_url
-            + "?media_type=VIDEO"
-            + "&video_url="
-            + &video_url
-            + "&caption="
-            + &*caption
-            + "&access_token="
-            + &self.access_token;
-
-        if let Some(_location_page_id) = location_page_id {
-            url = url + "location_id=" + &*_location_page_id
-        };
-
-        let resp =
-            HttpConnection::post::<InstagramContainerId, String>(url, "".to_string()).await?;
+        let url = self
+            .media_url()
+            .param("media_type", "VIDEO")
+            .param("video_url", video_url)
+            .non_empty_param("caption", post_caption)
+            .optional_param("location_id", location_page_id)
+            .build();
+
+        let resp = self
+            .http_connection
+            .post::<InstagramContainerId, String>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
@@ -324,27 +327,18 @@ impl InstagramContentPublishingApi {
         location_page_id: Option<String>,
         tag_users: Option<Vec<InstagramTagUser>>,
     ) -> Result<InstagramContainerId, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "media");
-        let caption = encode(&post_caption).to_string();
-        let mut url = base_url
-            + "?image_url="
-            + &image_url
-            + "&caption="
-            + &*caption
-            + "&access_token="
-            + &self.access_token;
-
-        if let Some(_location_page_id) = location_page_id {
-            url = url + "location_id=" + &*_location_page_id
-        };
-
-        if let Some(users) = tag_users {
-            let tags = format!("{:?}", users);
-            url = url + "&user_tags=" + tags.as_str();
-        };
-
-        let resp =
-            HttpConnection::post::<InstagramContainerId, String>(url, "".to_string()).await?;
+        let url = self
+            .media_url()
+            .param("image_url", image_url)
+            .non_empty_param("caption", post_caption)
+            .optional_param("location_id", location_page_id)
+            .optional_param("user_tags", tag_users.map(|users| json!(users)))
+            .build();
+
+        let resp = self
+            .http_connection
+            .post::<InstagramContainerId, String>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
@@ -425,26 +419,21 @@ impl InstagramContentPublishingApi {
         location_page_id: Option<String>,
         tag_users: Option<Vec<InstagramTagUser>>,
     ) -> Result<InstagramContainerId, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "media");
-
-        let mut url = base_url + "&is_carousel_item=true" + "&access_token=" + &self.access_token;
-
-        let media_type = match media_type {
-            MediaType::Video => url = url + "video_url=" + &media_url + "media_type=VIDEO",
-            MediaType::Image => url = url + "image_url=" + &media_url,
-        };
-
-        if let Some(_location_page_id) = location_page_id {
-            url = url + "location_id=" + &*_location_page_id
-        };
-
-        if let Some(users) = tag_users {
-            let tags = format!("{:?}", users);
-            url = url + "&user_tags=" + tags.as_str();
-        };
-
-        let resp =
-            HttpConnection::post::<InstagramContainerId, String>(url, "".to_string()).await?;
+        let url = self.media_url().param("is_carousel_item", true);
+        let url = match media_type {
+            MediaType::Video => url
+                .param("media_type", "VIDEO")
+                .param("video_url", media_url),
+            MediaType::Image => url.param("image_url", media_url),
+        }
+        .optional_param("location_id", location_page_id)
+        .optional_param("user_tags", tag_users.map(|users| json!(users)))
+        .build();
+
+        let resp = self
+            .http_connection
+            .post::<InstagramContainerId, String>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
@@ -514,20 +503,17 @@ impl InstagramContentPublishingApi {
         post_caption: String,
         container_ids: Vec<String>,
     ) -> Result<InstagramContainerId, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "media");
-        let caption = encode(&post_caption);
-        let children = format!("{:?}", container_ids);
-        let mut url = base_url
-            + "?media_type=CAROUSEL"
-            + "children="
-            + &children
-            + "&caption="
-            + &*caption
-            + "&access_token="
-            + &self.access_token;
-
-        let resp =
-            HttpConnection::post::<InstagramContainerId, String>(url, "".to_string()).await?;
+        let url = self
+            .media_url()
+            .param("media_type", "CAROUSEL")
+            .param("children", container_ids.join(","))
+            .non_empty_param("caption", post_caption)
+            .build();
+
+        let resp = self
+            .http_connection
+            .post::<InstagramContainerId, String>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
@@ -543,33 +529,39 @@ impl InstagramContentPublishingApi {
         self,
         container_id: String,
     ) -> Result<InstagramMediaId, ClientErr> {
-        let self_data = self.clone();
-
-        let base_url = self_data.base_url.replace("EDGE", "media_publish");
-        let url = base_url.to_string()
-            + "?creation_id="
-            + &container_id
-            + "&access_token="
-            + &self_data.access_token;
+        let url = RequestUrl::new(self.base_url.as_str())
+            .edge("media_publish")
+            .param("creation_id", container_id)
+            .access_token(&self.access_token)
+            .build();
 
-        let resp = HttpConnection::post(url, "".to_string()).await?;
+        let resp = self.http_connection.post(url, "".to_string()).await?;
         Ok(resp)
     }
 
     /// This method allows you to check the status for a given media.
     pub async fn status(self, container_id: String) -> Result<ContainerStatus, ClientErr> {
         // The user_id in the self.base_url is supposed to be replaced by the container
-        // id. The easiest way is to form a new  url.
-        let base_url = format!(
-            "https://graph.facebook.com/{}?fields=status_code,status,id",
-            container_id
-        );
-
-        let url = base_url + "&access_token=" + &self.access_token;
-
-        let resp = HttpConnection::get::<ContainerStatus>(url, "".to_string()).await?;
+        // id, so the url is formed again from the host and version of the base url.
+        let graph_root = self.base_url.rsplitn(3, '/').last().unwrap_or_default();
+        let url = RequestUrl::new(format!("{}/{}", graph_root, container_id))
+            .fields(&FieldSelection::from_iter(["status_code", "status", "id"]))
+            .access_token(&self.access_token)
+            .build();
+
+        let resp = self
+            .http_connection
+            .get::<ContainerStatus>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
+
+    /// The url of the `media` edge, with the access token.
+    fn media_url(&self) -> RequestUrl {
+        RequestUrl::new(self.base_url.as_str())
+            .edge("media")
+            .access_token(&self.access_token)
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -12,8 +12,15 @@
 
 use crate::graph::accounts::AccountsAPI;
 use crate::prelude::errors::ClientErr;
-use crate::prelude::{Accounts, HttpConnection};
+use crate::prelude::fields::FieldSelection;
+use crate::prelude::{Account, Accounts, DefaultHttpClient, GenericClientConnection, PageOptions};
+use crate::universal::request_url::{node_url, RequestUrl};
+use crate::universal::secret::redact;
+use crate::universal::HttpClient;
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// This struct contain different data gotten as a response  when a user sign in
 #[derive(Deserialize, Serialize, Clone, Debug, Default)]
@@ -68,15 +75,33 @@ impl FacebookPictureUserPicture {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct MeApi {
+#[derive(Clone)]
+pub struct MeApi<HttpC: HttpClient = DefaultHttpClient> {
+    graph_base: String,
     url: String,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl MeApi {
-    pub fn new(graph_base: String) -> MeApi {
+/// The access token in the url is not printed.
+impl<HttpC: HttpClient + fmt::Debug> fmt::Debug for MeApi<HttpC> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MeApi")
+            .field("graph_base", &redact(&self.graph_base, &[]))
+            .field("url", &redact(&self.url, &[]))
+            .field("http_connection", &self.http_connection)
+            .finish()
+    }
+}
+
+impl<HttpC: HttpClient> MeApi<HttpC> {
+    pub fn new(
+        graph_base: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> MeApi<HttpC> {
         MeApi {
-            url: graph_base.replace("NODE", "me"),
+            url: node_url(&graph_base, "me"),
+            graph_base,
+            http_connection,
         }
     }
 
@@ -88,25 +113,48 @@ impl MeApi {
     ///  
     /// [facebook accounts docs](https://developers.facebook.com/docs/graph-api/reference/user/accounts/)
     pub async fn pages_by_me(self) -> Result<Accounts, ClientErr> {
-        Ok(AccountsAPI::new(self.url).get().await?)
+        AccountsAPI::new(self.url, self.http_connection).get().await
+    }
+
+    /// Same as [pages_by_me](MeApi::pages_by_me), but returns the Facebook
+    /// Pages of every page of the list, following the `next` page while the
+    /// stream is consumed.
+    pub fn pages_by_me_stream(
+        self,
+        options: PageOptions,
+    ) -> impl Stream<Item = Result<Account, ClientErr>> {
+        AccountsAPI::new(self.url, self.http_connection).stream(options)
     }
 
     pub async fn pages_by_user_id(self, user_id: String) -> Result<Accounts, ClientErr> {
-        Ok(AccountsAPI::new(self.url.replace("me", &user_id))
-            .get()
-            .await?)
+        AccountsAPI::new(
+            self.url.replacen("/me/", &format!("/{}/", user_id), 1),
+            self.http_connection,
+        )
+        .get()
+        .await
     }
 
     /// The /me node is a special endpoint that translates to the object ID of
     /// the person or Page whose access token is currently being used
     /// to make the API calls. If you had a User access token, you could
     pub async fn user(&self) -> Result<Me, ClientErr> {
-        let fields =
-            "&fields=id,name,picture, email,first_name,last_name,about,birthday,gender,link";
-        let base_ur = self.url.replace("EDGE", "");
-        let url = base_ur + fields;
+        self.user_with_fields::<Me>(user_fields()).await
+    }
 
-        let resp = HttpConnection::get::<Me>(url, "".to_string()).await?;
+    /// Same as [user](MeApi::user), with the fields chosen by the caller,
+    /// e.g. `picture.type(large)`, see [FieldSelection](FieldSelection).
+    /// The user is returned as `T`, which may be a type of the caller.
+    pub async fn user_with_fields<T: DeserializeOwned>(
+        &self,
+        fields: FieldSelection,
+    ) -> Result<T, ClientErr> {
+        let url = RequestUrl::new(self.url.as_str())
+            .node()
+            .fields(&fields)
+            .build();
+
+        let resp = self.http_connection.get::<T>(url, "".to_string()).await?;
         Ok(resp)
     }
 
@@ -114,14 +162,28 @@ impl MeApi {
     /// the person or Page whose access token is currently being used
     /// to make the API calls. If you had a User access token, you could
     pub async fn user_by_id(&self, user_id: String) -> Result<Me, ClientErr> {
-        let base_url = self.url.replace("me", &user_id);
-
-        let fields =
-            "&fields=id,name,picture, email,first_name,last_name,about,birthday,gender,link";
-        let base_url = base_url.replace("EDGE", "");
-        let url = base_url + fields;
+        let url = RequestUrl::new(node_url(&self.graph_base, &user_id))
+            .node()
+            .fields(&user_fields())
+            .build();
 
-        let resp = HttpConnection::get::<Me>(url, "".to_string()).await?;
+        let resp = self.http_connection.get::<Me>(url, "".to_string()).await?;
         Ok(resp)
     }
 }
+
+/// The fields of the user returned as a [Me](Me).
+fn user_fields() -> FieldSelection {
+    FieldSelection::from_iter([
+        "id",
+        "name",
+        "picture",
+        "email",
+        "first_name",
+        "last_name",
+        "about",
+        "birthday",
+        "gender",
+        "link",
+    ])
+}
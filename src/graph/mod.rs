@@ -2,15 +2,17 @@ pub mod accounts;
 mod batch;
 pub mod client;
 pub mod data;
+pub mod ids;
 pub mod image;
 pub mod instagram;
 pub mod me;
+pub mod node;
 pub mod pages;
 pub mod utils;
 
 pub mod prelude {
     pub use crate::graph::{
-        accounts::*, batch::request::*, client::*, data::*, image::*, instagram::prelude::*, me::*,
-        pages::*, utils::*,
+        accounts::*, batch::request::*, client::*, data::*, ids::*, image::*,
+        instagram::prelude::*, me::*, node::*, pages::*, utils::*,
     };
 }
@@ -0,0 +1,252 @@
+//! Requests to any node or edge of the Graph API.
+//!
+//! The APIs of the crate wrap the common endpoints. A [NodeApi](NodeApi),
+//! given by [Client::node](crate::prelude::Client::node), reaches any other
+//! one, e.g. the insights of a page, through the same connection: the access
+//! token, the retries, the errors and the pagination are handled the same
+//! way.
+//!
+//! # Example
+//! ```no_run
+//! use facebook_api_rs::prelude::{Client, Data, UserToken};
+//! use serde_json::Value;
+//!
+//! # async fn insights() {
+//! let insights: Data<Value> = Client::new(UserToken::default(), "page_token".to_string())
+//!     .node("page_id")
+//!     .edge("insights")
+//!     .param("metric", "page_impressions_unique")
+//!     .param("period", "day")
+//!     .get()
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+
+use crate::prelude::errors::ClientErr;
+use crate::prelude::fields::FieldSelection;
+use crate::prelude::{paginate, DefaultHttpClient, GenericClientConnection, PageOptions};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::secret::redact;
+use crate::universal::usage::RateLimitUsage;
+use crate::universal::HttpClient;
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// A request to a node, or to an edge of a node, of the Graph API.
+#[derive(Clone)]
+pub struct NodeApi<HttpC: HttpClient = DefaultHttpClient> {
+    base_url: String,
+    access_token: String,
+    edge: String,
+    params: Vec<(String, String)>,
+    http_connection: GenericClientConnection<HttpC>,
+}
+
+/// The access token is not printed.
+impl<HttpC: HttpClient + fmt::Debug> fmt::Debug for NodeApi<HttpC> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeApi")
+            .field("base_url", &self.base_url)
+            .field("edge", &self.edge)
+            .field("params", &redact(&format!("{:?}", self.params), &[]))
+            .field("http_connection", &self.http_connection)
+            .finish()
+    }
+}
+
+impl<HttpC: HttpClient> NodeApi<HttpC> {
+    /// Note: this method is called inside the Client.
+    ///
+    /// # Arguments
+    /// * `base_url` - The graph url of the node, with the `EDGE` placeholder
+    /// * `access_token` - The access token sent with the requests
+    /// * `http_connection` - The connection the requests are sent through
+    pub fn new(
+        base_url: String,
+        access_token: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> NodeApi<HttpC> {
+        NodeApi {
+            base_url,
+            access_token,
+            edge: String::new(),
+            params: Vec::new(),
+            http_connection,
+        }
+    }
+
+    /// Send the request to an edge of the node, e.g. `"insights"`.
+    pub fn edge(mut self, edge: impl Into<String>) -> Self {
+        self.edge = edge.into();
+        self
+    }
+
+    /// Add a parameter. The values are url encoded.
+    pub fn param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.params.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Add several parameters.
+    pub fn params<I, K, V>(mut self, params: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: ToString,
+    {
+        self.params.extend(
+            params
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.to_string())),
+        );
+        self
+    }
+
+    /// Set the `fields` parameter, see [FieldSelection](FieldSelection).
+    pub fn fields(self, fields: FieldSelection) -> Self {
+        self.param("fields", fields)
+    }
+
+    /// Send the request with another access token than the page access
+    /// token of the client, e.g. a user access token.
+    pub fn access_token(mut self, access_token: impl Into<String>) -> Self {
+        self.access_token = access_token.into();
+        self
+    }
+
+    /// The url of the request, with its parameters and its access token.
+    pub fn url(&self) -> String {
+        let url = RequestUrl::new(self.base_url.as_str());
+        let url = if self.edge.is_empty() {
+            url.node()
+        } else {
+            url.edge(self.edge.trim_matches('/'))
+        };
+        url.params(self.params.iter().map(|(name, value)| (name, value)))
+            .access_token(&self.access_token)
+            .build()
+    }
+
+    /// Send a GET request, and read the response as `T`.
+    pub async fn get<T: DeserializeOwned>(&self) -> Result<T, ClientErr> {
+        self.http_connection
+            .get::<T>(self.url(), "".to_string())
+            .await
+    }
+
+    /// Same as [get](NodeApi::get), with the rate-limit usage reported by
+    /// the response.
+    pub async fn get_with_usage<T: DeserializeOwned>(
+        &self,
+    ) -> Result<(T, RateLimitUsage), ClientErr> {
+        self.http_connection
+            .get_with_usage::<T>(self.url(), "".to_string())
+            .await
+    }
+
+    /// Send a POST request, with the parameters as an url encoded body, and
+    /// read the response as `T`.
+    pub async fn post<T: DeserializeOwned>(&self) -> Result<T, ClientErr> {
+        self.http_connection
+            .post::<T, String>(self.url(), "".to_string())
+            .await
+    }
+
+    /// Same as [post](NodeApi::post), with the rate-limit usage reported by
+    /// the response.
+    pub async fn post_with_usage<T: DeserializeOwned>(
+        &self,
+    ) -> Result<(T, RateLimitUsage), ClientErr> {
+        self.http_connection
+            .post_with_usage::<T, String>(self.url(), "".to_string())
+            .await
+    }
+
+    /// Send a DELETE request, and read the response as `T`.
+    pub async fn delete<T: DeserializeOwned>(&self) -> Result<T, ClientErr> {
+        self.http_connection
+            .delete::<T>(self.url(), "".to_string())
+            .await
+    }
+
+    /// Same as [delete](NodeApi::delete), with the rate-limit usage reported
+    /// by the response.
+    pub async fn delete_with_usage<T: DeserializeOwned>(
+        &self,
+    ) -> Result<(T, RateLimitUsage), ClientErr> {
+        self.http_connection
+            .delete_with_usage::<T>(self.url(), "".to_string())
+            .await
+    }
+
+    /// The items of the list returned by the edge, following the pages of
+    /// the list while the stream is consumed, see
+    /// [paginate](crate::prelude::paginate).
+    pub fn stream<T: DeserializeOwned>(
+        &self,
+        options: PageOptions,
+    ) -> impl Stream<Item = Result<T, ClientErr>> {
+        paginate(self.http_connection.clone(), self.url(), options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::mock_server::{MockGraphServer, MockResponse};
+    use crate::prelude::Data;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_node_requests() {
+        let server = MockGraphServer::start().unwrap();
+        server.mock_once(
+            "GET",
+            "/1234/insights",
+            MockResponse::ok(json!({ "data": [{ "name": "page_impressions_unique" }] })),
+        );
+        server.mock_once(
+            "POST",
+            "/1234_5678",
+            MockResponse::ok(json!({ "success": true })),
+        );
+        server.mock_once(
+            "DELETE",
+            "/1234_5678",
+            MockResponse::ok(json!({ "success": true })),
+        );
+        let client = || server.client("page_token".to_string());
+
+        let insights: Data<Value> = client()
+            .node("1234")
+            .edge("insights")
+            .params([("metric", "page_impressions_unique"), ("period", "day")])
+            .get()
+            .await
+            .unwrap();
+        assert_eq!(insights.data[0]["name"], "page_impressions_unique");
+
+        let updated: Value = client()
+            .node("1234_5678")
+            .param("message", "Tom & Jerry")
+            .post()
+            .await
+            .unwrap();
+        assert_eq!(updated["success"], true);
+
+        let _: Value = client().node("1234_5678").delete().await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/v23.0/1234/insights");
+        assert_eq!(requests[0].param("period"), Some("day"));
+        assert_eq!(
+            requests[0].header("authorization"),
+            Some("Bearer page_token")
+        );
+        // The parameters of a POST are sent in its body.
+        assert!(requests[1].query.is_empty());
+        assert_eq!(requests[1].param("message"), Some("Tom & Jerry"));
+        assert_eq!(requests[2].method, "DELETE");
+    }
+}
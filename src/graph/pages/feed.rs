@@ -5,25 +5,37 @@
 
 use crate::graph::pages::utils::{Fields, GetPostResponse};
 use crate::prelude::errors::ClientErr;
-use crate::prelude::{Data, HttpConnection};
+use crate::prelude::fields::FieldSelection;
+use crate::prelude::{paginate, Data, DefaultHttpClient, GenericClientConnection, PageOptions};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 /// The feed API of Facebook Page let you publish and get data of the page,
 /// the API have different end points depending on the operation you want to
 /// perform  on the page. For more information on different endpoint for get and
 /// publish post check Facebook document <https://developers.facebook.com/docs/graph-api/reference/v23.0/page/feed#publish>.
-pub struct FeedApi {
+pub struct FeedApi<HttpC: HttpClient = DefaultHttpClient> {
     base_url: String,
     page_access_token: String,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl FeedApi {
+impl<HttpC: HttpClient> FeedApi<HttpC> {
     /// This is a static method used to create an instance of the feedApi
     /// Note: this method is called inside the Client.
-    pub fn new(base_url: String, access_token: String) -> FeedApi {
+    pub fn new(
+        base_url: String,
+        access_token: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> FeedApi<HttpC> {
         FeedApi {
             base_url,
             page_access_token: access_token,
+            http_connection,
         }
     }
 
@@ -36,53 +48,77 @@ impl FeedApi {
     /// * ` message` - This is body of the message you want to post.
     /// * ` url_link ` -  The link you intend to attach to the  post.
     pub async fn post(&self, post_params: FeedPostFields) -> Result<FeedPostSuccess, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "feed");
-        let mut url = base_url
-            + "?message="
-            + &post_params.message
-            + "&access_token="
-            + &self.page_access_token;
-
-        if post_params.call_to_action.is_some() {
-            // let type_ = "type:".to_owned() + &post_params.call_to_action.unwrap().name;
-            let type_ = "type:".to_owned()
-                + format!("{:?}", &post_params.call_to_action.as_ref().unwrap().type_).as_str();
-            let link = "Link:".to_owned() + &post_params.call_to_action.unwrap().link;
-            let value = "value:{".to_owned() + &link + "}";
-            let built_url = "{".to_owned() + &type_ + "," + &value + "}";
-            url = "&call_to_action=".to_owned() + &built_url;
-        };
+        let mut url = RequestUrl::new(self.base_url.as_str())
+            .edge("feed")
+            .non_empty_param("message", &post_params.message)
+            .non_empty_param("link", &post_params.link);
+        if let Some(call_to_action) = &post_params.call_to_action {
+            url = url.param(
+                "call_to_action",
+                json!({ "type": call_to_action.type_, "value": { "link": call_to_action.link } }),
+            );
+        }
+        // The tags can not be sent without a place.
         if !post_params.place.is_empty() {
-            if !post_params.tags.is_empty() {
-                let tag_list = build_tags(post_params.tags);
-                url = url + "&place=" + &post_params.place + "&tags=" + &tag_list;
-            } else {
-                url = url + "&place=" + &post_params.place
-            }
+            url = url
+                .param("place", &post_params.place)
+                .non_empty_param("tags", post_params.tags.join(","));
         }
+        let url = url.access_token(&self.page_access_token).build();
 
-        let resp = HttpConnection::get::<FeedPostSuccess>(url, "".to_string()).await?;
+        let resp = self
+            .http_connection
+            .post::<FeedPostSuccess, String>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
-    /// This method  return data  of the page feed
+    /// This method  return data  of the page feed. Only the first page of
+    /// the feed is returned, see [get_stream](FeedApi::get_stream) for the
+    /// whole feed.
     pub async fn get(self) -> Result<FeedData, ClientErr> {
-        let mut base_url = self.base_url.replace("EDGE", "feed");
-        base_url = "?fields=".to_string();
-        let field_count = Fields::default().fields.len();
-        for (count, field) in Fields::default().fields.into_iter().enumerate() {
-            if count < field_count - 1 {
-                base_url = base_url + &field + ",";
-            } else {
-                base_url = base_url + &field; // remove the comma in the last
-                                              // filed
-            }
-        }
-        let url = base_url + "&access_token=" + &self.page_access_token;
+        let url = self.feed_url(&FieldSelection::from_iter(Fields::default().fields));
+
+        let resp = self
+            .http_connection
+            .get::<FeedData>(url, "".to_string())
+            .await?;
+        Ok(resp)
+    }
+
+    /// The posts of the page feed, following the pages of the feed while the
+    /// stream is consumed.
+    pub fn get_stream(
+        self,
+        options: PageOptions,
+    ) -> impl Stream<Item = Result<GetPostResponse, ClientErr>> {
+        let url = self.feed_url(&FieldSelection::from_iter(Fields::default().fields));
+        paginate(self.http_connection, url, options)
+    }
 
-        let resp = HttpConnection::get::<FeedData>(url, "".to_string()).await?;
+    /// Same as [get](FeedApi::get), with the fields of the posts chosen by
+    /// the caller, see [FieldSelection](FieldSelection). The posts are
+    /// returned as `T`, which may be a type of the caller.
+    pub async fn get_with_fields<T: DeserializeOwned>(
+        self,
+        fields: FieldSelection,
+    ) -> Result<Data<T>, ClientErr> {
+        let url = self.feed_url(&fields);
+
+        let resp = self
+            .http_connection
+            .get::<Data<T>>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
+
+    fn feed_url(&self, fields: &FieldSelection) -> String {
+        RequestUrl::new(self.base_url.as_str())
+            .edge("feed")
+            .fields(fields)
+            .access_token(&self.page_access_token)
+            .build()
+    }
 }
 
 /// Return response for posting feeds ( message or link) to the page, the
@@ -103,9 +139,17 @@ pub struct MediaPostSuccess {
 /// The data depends of the feeds.
 #[derive(Deserialize, Debug, Serialize)]
 pub struct FeedData {
+    #[serde(flatten)]
     data: Data<GetPostResponse>,
 }
 
+impl FeedData {
+    /// The posts of the page, and the paging of the feed.
+    pub fn data(&self) -> &Data<GetPostResponse> {
+        &self.data
+    }
+}
+
 pub struct FeedPostFields {
     /// The URL of a link to attach to the post. At lest either link or message
     /// must be supplied.
@@ -222,10 +266,42 @@ pub struct Feeling {
     og_object_id: String,
 }
 
-fn build_tags(list: Vec<String>) -> String {
-    let mut url = "".to_string();
-    for value in list {
-        url = format!("{}{},", url, value)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::mock_server::MockGraphServer;
+
+    #[tokio::test]
+    async fn test_post_encodes_the_fields() {
+        let server = MockGraphServer::start().unwrap();
+        let post = server
+            .client("page_token".to_string())
+            .feed("1234".to_string())
+            .post(FeedPostFields {
+                link: "https://example.com/?a=1&b=2".to_string(),
+                message: "Tom & Jerry #1".to_string(),
+                tags: vec!["11".to_string(), "22".to_string()],
+                place: "5678".to_string(),
+                call_to_action: Some(CallToAction {
+                    link: "https://example.com/?a=1&b=2".to_string(),
+                    type_: CallToActionType::LEARN_MORE,
+                }),
+                feeling: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(post.id, "1234_1001");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.param("message"), Some("Tom & Jerry #1"));
+        assert_eq!(request.param("link"), Some("https://example.com/?a=1&b=2"));
+        assert_eq!(request.param("place"), Some("5678"));
+        assert_eq!(request.param("tags"), Some("11,22"));
+        assert_eq!(
+            request.param("call_to_action"),
+            Some(r#"{"type":"LEARN_MORE","value":{"link":"https://example.com/?a=1&b=2"}}"#)
+        );
+        assert_eq!(request.param("b"), None);
     }
-    url
 }
@@ -1,4 +1,5 @@
 use crate::prelude::video::{UploadPhase, VideoParams};
+use crate::universal::errors::ClientErr;
 use reqwest::multipart::{Form, Part};
 use std::borrow::BorrowMut;
 use std::fs::File;
@@ -12,12 +13,12 @@ pub fn resumable_form_data_reqwest(
     mut video_params: VideoParams,
     mut file: File,
     //  thumb_file:Option<File>
-) -> Form {
+) -> Result<Form, ClientErr> {
     use std::io::prelude::*;
     let mut current_upload_phase = "";
 
     let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer).unwrap();
+    file.read_to_end(&mut buffer)?;
     let params = video_params.borrow_mut();
     let part = Part::bytes(buffer).file_name("vdeoe ");
 
@@ -25,7 +26,7 @@ pub fn resumable_form_data_reqwest(
         UploadPhase::start => {
             current_upload_phase = "start";
             let form_data = Form::new()
-                .text("file_size", file.metadata().unwrap().len().to_string())
+                .text("file_size", file.metadata()?.len().to_string())
                 .text(" upload_phase", "start");
 
             form_data
@@ -84,5 +85,5 @@ pub fn resumable_form_data_reqwest(
             form_data
         }
     };
-    formdata
+    Ok(formdata)
 }
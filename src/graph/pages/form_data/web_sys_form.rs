@@ -1,22 +1,31 @@
 use crate::prelude::video::{UploadPhase, VideoParams};
+use crate::universal::errors::ClientErr;
+use crate::universal::web_sys_client::js_error;
 use serde::{Deserialize, Serialize};
 use web_sys::{Blob, File, FormData};
 
 #[cfg(any(feature = "web-sys"))]
-pub fn form_data_seed(file: File, video_params: VideoParams) -> FormData {
-    let mut form_data = FormData::new().unwrap();
+pub fn form_data_seed(file: File, video_params: VideoParams) -> Result<FormData, ClientErr> {
+    let form_data = FormData::new().map_err(js_error)?;
 
-    form_data.append_with_blob("source", &file); // appped  the  current chunked file   to the form
+    // appped  the  current chunked file   to the form
+    form_data
+        .append_with_blob("source", &file)
+        .map_err(js_error)?;
 
     if !video_params.description.is_empty() {
-        form_data.append_with_str("description", &video_params.description);
+        form_data
+            .append_with_str("description", &video_params.description)
+            .map_err(js_error)?;
     }
 
     if let Some(thumb) = video_params.thumb {
-        form_data.append_with_blob("thumb", &thumb);
+        form_data
+            .append_with_blob("thumb", &thumb)
+            .map_err(js_error)?;
     }
 
-    form_data
+    Ok(form_data)
 }
 
 #[cfg(any(feature = "web-sys"))]
@@ -27,50 +36,65 @@ pub fn resumable_form_data_seed(
     upload_session_id: String,
     start_offset: String,
     video_params: VideoParams,
-) -> FormData {
+) -> Result<FormData, ClientErr> {
     // phase is expected to be of an enum of either , start, transfer, and end
     // depending on the  uplaoding stage
     let mut current_upload_phase = "";
-    let mut form_data = FormData::new().unwrap();
-    use crate::prelude::FileResult;
+    let form_data = FormData::new().map_err(js_error)?;
 
     match upload_phase {
         UploadPhase::start => {
             current_upload_phase = "start";
-            form_data.append_with_str(
-                "file_size",
-                FileResult::file_analyze(uploaded_file)
-                    .file_size_byte_string()
-                    .as_str(),
-            ); // add the video size
+            // add the video size
+            form_data
+                .append_with_str("file_size", &(uploaded_file.size() as u64).to_string())
+                .map_err(js_error)?;
         }
         UploadPhase::transfer => {
             current_upload_phase = "transfer";
-            form_data.append_with_str("start_offset", &start_offset);
-            form_data.append_with_blob("video_file_chunk", &current_blob_file);
+            form_data
+                .append_with_str("start_offset", &start_offset)
+                .map_err(js_error)?;
+            form_data
+                .append_with_blob("video_file_chunk", &current_blob_file)
+                .map_err(js_error)?;
         }
 
         UploadPhase::finish => {
             current_upload_phase = "finish";
 
-            form_data.append_with_str("upload_session_id", &upload_session_id);
+            form_data
+                .append_with_str("upload_session_id", &upload_session_id)
+                .map_err(js_error)?;
 
             if !video_params.description.is_empty() {
-                form_data.append_with_str("description", &video_params.description);
+                form_data
+                    .append_with_str("description", &video_params.description)
+                    .map_err(js_error)?;
             }
 
             if let Some(thumb) = video_params.thumb {
-                form_data.append_with_blob("thumb", &thumb);
+                form_data
+                    .append_with_blob("thumb", &thumb)
+                    .map_err(js_error)?;
             }
         }
         // this method has not been implimented yet.
         UploadPhase::cancel => {
-            form_data.append_with_str("upload_session_id", &upload_session_id);
-            form_data.append_with_str("start_offset", &start_offset);
+            form_data
+                .append_with_str("upload_session_id", &upload_session_id)
+                .map_err(js_error)?;
+            form_data
+                .append_with_str("start_offset", &start_offset)
+                .map_err(js_error)?;
         }
     }
 
-    form_data.append_with_str("upload_session_id", &upload_session_id);
-    form_data.append_with_str("upload_phase", current_upload_phase);
     form_data
+        .append_with_str("upload_session_id", &upload_session_id)
+        .map_err(js_error)?;
+    form_data
+        .append_with_str("upload_phase", current_upload_phase)
+        .map_err(js_error)?;
+    Ok(form_data)
 }
@@ -3,3 +3,8 @@ pub mod video_by_reqwest;
 
 #[cfg(any(feature = "web-sys"))]
 pub mod video_by_web_sys;
+
+pub mod progress;
+
+#[cfg(feature = "reqwest")]
+pub mod source;
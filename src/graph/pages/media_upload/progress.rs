@@ -0,0 +1,214 @@
+//! Progress reporting of the video and photo uploads.
+//!
+//! Give a callback to [VideoApi](crate::prelude::video::VideoApi) or
+//! [PhotoApi](crate::prelude::photo::PhotoApi) with `add_progress_callback`
+//! and it is called with an [UploadProgress](UploadProgress) while the file
+//! is sent, e.g. to show an upload bar.
+//!
+//! # Example
+//! ```no_run
+//! use facebook_api_rs::prelude::video::VideoParams;
+//! use facebook_api_rs::prelude::{Client, UserToken};
+//! use std::fs::File;
+//!
+//! # async fn upload() {
+//! let video = Client::new(UserToken::default(), "page_token".to_string())
+//!     .video_upload("page_id".to_string())
+//!     .add_progress_callback(|progress| {
+//!         println!("{:.0}% sent", progress.fraction() * 100.0);
+//!     });
+//!
+//! let file = File::open("video.mp4").unwrap();
+//! video
+//!     .non_resumable_post(VideoParams::default(), file, None)
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+
+use crate::prelude::video::UploadPhase;
+use std::fmt;
+use std::sync::Arc;
+
+/// How much of a file has been sent to Facebook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadProgress {
+    /// The phase of a resumable upload. Files sent in one request are
+    /// reported in the `transfer` phase.
+    pub phase: UploadPhase,
+    /// The bytes of the file sent so far.
+    pub bytes_sent: u64,
+    /// The size of the file.
+    pub total_bytes: u64,
+    /// The first byte of the part of the file being sent, as asked by
+    /// Facebook for a resumable upload.
+    pub start_offset: u64,
+    /// The end, excluded, of the part of the file being sent.
+    pub end_offset: u64,
+}
+
+impl UploadProgress {
+    /// The part of the file sent so far, between 0 and 1.
+    pub fn fraction(&self) -> f64 {
+        if self.total_bytes == 0 {
+            return 1.0;
+        }
+        (self.bytes_sent as f64 / self.total_bytes as f64).min(1.0)
+    }
+}
+
+/// A callback receiving the [UploadProgress](UploadProgress) of an upload.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(UploadProgress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(UploadProgress) + Send + Sync + 'static,
+    {
+        ProgressCallback(Arc::new(callback))
+    }
+
+    pub fn report(&self, progress: UploadProgress) {
+        (self.0)(progress)
+    }
+
+    /// The [RequestProgress](RequestProgress) of a request sending the bytes
+    /// `start_offset..end_offset` of a file of `total_bytes`.
+    pub(crate) fn request_progress(
+        &self,
+        phase: UploadPhase,
+        start_offset: u64,
+        end_offset: u64,
+        total_bytes: u64,
+    ) -> RequestProgress {
+        let callback = self.clone();
+        let part_size = end_offset.saturating_sub(start_offset);
+        RequestProgress::new(move |sent, request_size| {
+            // The request may be larger than the part of the file, e.g. with
+            // the fields of a multipart body, so its progress is scaled.
+            let part_sent = if request_size == 0 {
+                part_size
+            } else {
+                (u128::from(sent.min(request_size)) * u128::from(part_size)
+                    / u128::from(request_size)) as u64
+            };
+            callback.report(UploadProgress {
+                phase,
+                bytes_sent: start_offset + part_sent,
+                total_bytes,
+                start_offset,
+                end_offset,
+            })
+        })
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// A callback receiving the bytes of a file sent so far by one request, and
+/// the bytes of the file sent by the request.
+///
+/// A [GenericClientConnection](crate::prelude::GenericClientConnection)
+/// given one with `with_request_progress` adds it to the extensions of its
+/// requests, where an [HttpClient](crate::prelude::HttpClient) sending a file
+/// can find it.
+#[derive(Clone)]
+pub struct RequestProgress(Arc<dyn Fn(u64, u64) + Send + Sync>);
+
+impl RequestProgress {
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(u64, u64) + Send + Sync + 'static,
+    {
+        RequestProgress(Arc::new(callback))
+    }
+
+    pub fn report(&self, sent: u64, total: u64) {
+        (self.0)(sent, total)
+    }
+}
+
+impl fmt::Debug for RequestProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RequestProgress")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_request_progress_is_scaled_to_the_part() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let callback = {
+            let events = Arc::clone(&events);
+            ProgressCallback::new(move |progress| events.lock().unwrap().push(progress))
+        };
+
+        let request_progress = callback.request_progress(UploadPhase::transfer, 100, 200, 400);
+        request_progress.report(0, 1000);
+        request_progress.report(500, 1000);
+        request_progress.report(1000, 1000);
+
+        let events = events.lock().unwrap();
+        let sent: Vec<u64> = events.iter().map(|progress| progress.bytes_sent).collect();
+        assert_eq!(sent, vec![100, 150, 200]);
+        assert_eq!(events[2].fraction(), 0.5);
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn test_non_resumable_post_reports_progress() {
+        use crate::prelude::mock_server::MockGraphServer;
+        use crate::prelude::video::VideoParams;
+        use std::io::{Seek, Write};
+
+        let server = MockGraphServer::start().unwrap();
+        let content = vec![b'v'; 200 * 1024];
+        let file = std::env::temp_dir().join(format!(
+            "facebook_api_rs_progress_{}.mp4",
+            std::process::id()
+        ));
+        let mut video = std::fs::File::options()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(&file)
+            .unwrap();
+        video.write_all(&content).unwrap();
+        video.rewind().unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let response = server
+            .client("page_token".to_string())
+            .video_upload("1234".to_string())
+            .add_progress_callback(move |progress| recorded.lock().unwrap().push(progress))
+            .non_resumable_post(VideoParams::default(), video, None)
+            .await;
+        std::fs::remove_file(&file).unwrap();
+        response.unwrap();
+
+        let events = events.lock().unwrap();
+        assert!(events.len() > 2);
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].bytes_sent <= pair[1].bytes_sent));
+        let last = events.last().unwrap();
+        assert_eq!(last.phase, UploadPhase::transfer);
+        assert_eq!(last.bytes_sent, content.len() as u64);
+        assert_eq!(last.total_bytes, content.len() as u64);
+        assert_eq!(
+            server.requests()[0].files.get("source").map(Vec::len),
+            Some(content.len())
+        );
+    }
+}
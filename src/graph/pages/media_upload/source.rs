@@ -0,0 +1,192 @@
+//! The content of a photo, a video or a thumbnail to upload.
+//!
+//! A [MediaSource](MediaSource) can be built from bytes already in memory, a
+//! local file, or a stream of a known size such as the body of a download
+//! from an object storage. Streams are read while the file is uploaded, so a
+//! resumable video upload only keeps one chunk in memory.
+//!
+//! # Example
+//! ```no_run
+//! use facebook_api_rs::prelude::media_upload::source::MediaSource;
+//! use facebook_api_rs::prelude::video::VideoParams;
+//! use facebook_api_rs::prelude::{Client, UserToken};
+//!
+//! # async fn upload(object: impl tokio::io::AsyncRead + Send + 'static, size: u64) {
+//! let video = MediaSource::from_reader(object, size);
+//! Client::new(UserToken::default(), "page_token".to_string())
+//!     .video_upload("page_id".to_string())
+//!     .resumable_post_media(video, VideoParams::default(), None)
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+
+use crate::prelude::errors::ClientErr;
+use bytes::Bytes;
+use std::fmt;
+use std::fs::File;
+use std::io::SeekFrom;
+use std::pin::Pin;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+/// The content of a file to upload.
+pub struct MediaSource {
+    content: Content,
+    size: u64,
+}
+
+enum Content {
+    Bytes(Bytes),
+    File(tokio::fs::File),
+    Reader {
+        reader: Pin<Box<dyn AsyncRead + Send>>,
+        position: u64,
+    },
+}
+
+impl MediaSource {
+    /// A file in memory.
+    pub fn from_bytes(bytes: impl Into<Bytes>) -> Self {
+        let bytes = bytes.into();
+        MediaSource {
+            size: bytes.len() as u64,
+            content: Content::Bytes(bytes),
+        }
+    }
+
+    /// A local file. It is read without blocking the async runtime.
+    pub fn from_file(file: File) -> Result<Self, ClientErr> {
+        let size = file.metadata().map_err(read_error)?.len();
+        Ok(MediaSource {
+            size,
+            content: Content::File(tokio::fs::File::from_std(file)),
+        })
+    }
+
+    /// A stream of `size` bytes. It is read once, from its start.
+    pub fn from_reader<R>(reader: R, size: u64) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        MediaSource {
+            content: Content::Reader {
+                reader: Box::pin(reader),
+                position: 0,
+            },
+            size,
+        }
+    }
+
+    /// The size of the file, in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Read the whole file.
+    pub async fn into_bytes(self) -> Result<Bytes, ClientErr> {
+        let size = self.size;
+        let mut source = self;
+        match source.content {
+            Content::Bytes(bytes) => Ok(bytes),
+            _ => source.read_chunk(0, size).await.map(Bytes::from),
+        }
+    }
+
+    /// Read the bytes from `start_offset` included to `end_offset` excluded,
+    /// as asked by Facebook during a resumable upload. A stream can not go
+    /// back, so its offsets must not decrease.
+    pub(crate) async fn read_chunk(
+        &mut self,
+        start_offset: u64,
+        end_offset: u64,
+    ) -> Result<Vec<u8>, ClientErr> {
+        if end_offset > self.size || start_offset > end_offset {
+            return Err(ClientErr::CustomError(format!(
+                "the chunk {}..{} is outside of the file of {} bytes",
+                start_offset, end_offset, self.size
+            )));
+        }
+
+        match &mut self.content {
+            Content::Bytes(bytes) => Ok(bytes[start_offset as usize..end_offset as usize].to_vec()),
+            Content::File(file) => {
+                file.seek(SeekFrom::Start(start_offset))
+                    .await
+                    .map_err(read_error)?;
+                let mut chunk = vec![0; (end_offset - start_offset) as usize];
+                file.read_exact(&mut chunk).await.map_err(read_error)?;
+                Ok(chunk)
+            }
+            Content::Reader { reader, position } => {
+                if start_offset < *position {
+                    return Err(ClientErr::CustomError(format!(
+                        "the stream was already read past the offset {}",
+                        start_offset
+                    )));
+                }
+                let skipped = start_offset - *position;
+                tokio::io::copy(&mut reader.as_mut().take(skipped), &mut tokio::io::sink())
+                    .await
+                    .map_err(read_error)?;
+
+                let mut chunk = vec![0; (end_offset - start_offset) as usize];
+                reader.read_exact(&mut chunk).await.map_err(read_error)?;
+                *position = end_offset;
+                Ok(chunk)
+            }
+        }
+    }
+}
+
+impl From<Bytes> for MediaSource {
+    fn from(bytes: Bytes) -> Self {
+        MediaSource::from_bytes(bytes)
+    }
+}
+
+impl From<Vec<u8>> for MediaSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        MediaSource::from_bytes(bytes)
+    }
+}
+
+impl fmt::Debug for MediaSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let content = match self.content {
+            Content::Bytes(_) => "Bytes",
+            Content::File(_) => "File",
+            Content::Reader { .. } => "Reader",
+        };
+        f.debug_struct("MediaSource")
+            .field("content", &content)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+fn read_error(error: std::io::Error) -> ClientErr {
+    ClientErr::CustomError(format!("can not read the media: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reader_chunks() {
+        let content: Vec<u8> = (0..100).collect();
+        let mut source = MediaSource::from_reader(std::io::Cursor::new(content.clone()), 100);
+
+        assert_eq!(source.read_chunk(0, 10).await.unwrap(), &content[0..10]);
+        // Skipping bytes forward is fine, going back is not.
+        assert_eq!(source.read_chunk(50, 60).await.unwrap(), &content[50..60]);
+        assert!(source.read_chunk(20, 30).await.is_err());
+        assert!(source.read_chunk(90, 110).await.is_err());
+
+        let bytes = MediaSource::from_reader(std::io::Cursor::new(content.clone()), 100)
+            .into_bytes()
+            .await
+            .unwrap();
+        assert_eq!(bytes, content);
+    }
+}
@@ -14,16 +14,18 @@
 //! video API  <https://developers.facebook.com/docs/video-api/guides/publishing>
 //! For information on different opertaions on facebook page  check  <https://developers.facebook.com/docs/graph-api/reference/page/videos/#Creating>
 use crate::prelude::errors::ClientErr;
-use crate::prelude::HttpConnection;
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
 
-use crate::graph::prelude::form_data::reqwest_form::resumable_form_data_reqwest;
 use crate::prelude::utils::{ChunksUploadResponse, PostResponse, UploadingData};
 
-use crate::prelude::file_analyze::FileResultServer;
-use crate::prelude::video::{FinalResponeResumableUpload, UploadPhase, VideoParams};
+use crate::prelude::media_upload::progress::{ProgressCallback, UploadProgress};
+use crate::prelude::media_upload::source::MediaSource;
+use crate::prelude::video::{FinalResponeResumableUpload, UploadPhase, UploadSession, VideoParams};
+use crate::universal::form_data::MultipartData;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::prelude::*;
 /// Facebook video api accepts different parameters that could be passed to the
 /// post request while uploading the video. this struck will have the possible
 /// parameters that a user might need to pass along the video while publishing.
@@ -40,6 +42,7 @@ struct ResumableUploadFinal {
 #[derive(Deserialize, Debug)]
 struct InitializeUploadResponse {
     pub video_id: String,
+    pub start_offset: Option<String>,
     pub end_offset: String,
     pub upload_session_id: String,
 }
@@ -47,17 +50,67 @@ struct InitializeUploadResponse {
 /// This struct is the response gotten when initializing the resumable uploading
 /// method process.
 
-#[derive(Deserialize, Clone)]
-pub struct VideoApi_reqwest {
+#[derive(Clone)]
+pub struct VideoApi_reqwest<HttpC: HttpClient = DefaultHttpClient> {
     base_url: String,
     page_access_token: String,
+    http_connection: GenericClientConnection<HttpC>,
+    progress: Option<ProgressCallback>,
 }
 
-impl VideoApi_reqwest {
-    pub fn new(base_url: String, page_access_token: String) -> VideoApi_reqwest {
+impl<HttpC: HttpClient> VideoApi_reqwest<HttpC> {
+    pub fn new(
+        base_url: String,
+        page_access_token: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> VideoApi_reqwest<HttpC> {
         VideoApi_reqwest {
             base_url,
             page_access_token,
+            http_connection,
+            progress: None,
+        }
+    }
+
+    /// Report the progress of the uploads to `progress`.
+    pub fn with_progress(mut self, progress: Option<ProgressCallback>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// The connection sending the bytes `start_offset..end_offset` of a file,
+    /// reporting their progress when there is a progress callback.
+    fn upload_connection(
+        &self,
+        phase: UploadPhase,
+        start_offset: u64,
+        end_offset: u64,
+        total_bytes: u64,
+    ) -> GenericClientConnection<HttpC> {
+        match &self.progress {
+            Some(progress) => {
+                self.http_connection
+                    .clone()
+                    .with_request_progress(progress.request_progress(
+                        phase,
+                        start_offset,
+                        end_offset,
+                        total_bytes,
+                    ))
+            }
+            None => self.http_connection.clone(),
+        }
+    }
+
+    fn report_progress(&self, phase: UploadPhase, bytes_sent: u64, total_bytes: u64) {
+        if let Some(progress) = &self.progress {
+            progress.report(UploadProgress {
+                phase,
+                bytes_sent,
+                total_bytes,
+                start_offset: bytes_sent,
+                end_offset: bytes_sent,
+            });
         }
     }
 
@@ -72,39 +125,51 @@ impl VideoApi_reqwest {
     /// not be made but a Fetcherror will be gerated.
     ///
     /// For more information check <https://developers.facebook.com/docs/video-api/guides/publishing>
-
     pub async fn non_resumable_post(
         &self,
         video_params: VideoParams,
-        mut file: File,
+        file: File,
         thumb_file: Option<File>,
     ) -> Result<PostResponse, ClientErr> {
-        // let file_result =
-        // FileResultServer::file_analyze(video_params.file_path.clone()); check
-        // if the uploading method
-        let mut buffer = vec![];
-        file.read_to_end(&mut buffer).unwrap();
-        let base_url = self.base_url.replace("EDGE", "videos");
-        let url = base_url + "?access_token=" + &self.page_access_token;
-
-        let resp = HttpConnection::request_by_bytes_and_params::<PostResponse>(
-            url,
-            (buffer, video_params.clone()),
-        )
-        .await?;
-        Ok(resp)
+        let thumb = thumb_file.map(MediaSource::from_file).transpose()?;
+        self.non_resumable_post_media(video_params, MediaSource::from_file(file)?, thumb)
+            .await
+    }
+
+    /// Same as [non_resumable_post](VideoApi_reqwest::non_resumable_post),
+    /// with a video and a thumbnail which are not local files. The video is
+    /// read in memory before it is sent.
+    pub async fn non_resumable_post_media(
+        &self,
+        video_params: VideoParams,
+        media: MediaSource,
+        thumb: Option<MediaSource>,
+    ) -> Result<PostResponse, ClientErr> {
+        let video = media.into_bytes().await?;
+        let total_bytes = video.len() as u64;
+
+        let mut form_data = MultipartData::new()
+            .file("source", "source", video)
+            .text("description", video_params.description)
+            .text("title", video_params.title);
+        if let Some(thumb) = thumb {
+            form_data = form_data.file("thumb", "thumb", thumb.into_bytes().await?);
+        }
+
+        self.upload_connection(UploadPhase::transfer, 0, total_bytes, total_bytes)
+            .multipart_post::<PostResponse>(self.videos_url(), form_data)
+            .await
     }
 }
 
-impl VideoApi_reqwest {
+impl<HttpC: HttpClient> VideoApi_reqwest<HttpC> {
     /// This method is used for uploading large video files, it does that by
     /// chunking the file and uplaoding them individually until is complete.
-    /// The method takes two parameter( file, video parameter struct).
-    /// the waiting time depend  on the video size uplaoded
+    /// The chunks are read at the offsets asked by Facebook after each
+    /// request, and a chunk which fails is sent again according to the
+    /// [RetryPolicy](crate::prelude::retry::RetryPolicy) of the connection,
+    /// including after a connection error.
     ///
-    /// Note there is an issue with chunking method that only chunk smaller size
-    /// so extra time than usuall is expect until the issue is fixed.
-    ///  
     /// for more infromation  check  https://developers.facebook.com/docs/video-api/guides/publishing
     pub async fn resumable_post(
         &self,
@@ -112,165 +177,390 @@ impl VideoApi_reqwest {
         file: File,
         thumb_file: Option<File>,
     ) -> Result<FinalResponeResumableUpload, ClientErr> {
-        let mut start_offset = Some("0".to_string()); // this  data will be updated  fopm the respones
-        let mut end_offset = Some("0".to_string()); // this  data will be updated  fopm the respones
-        let video_params = video_param.clone();
-        let self_data = self.clone();
-
-        let base_url = self.base_url.replace("EDGE", "videos").clone();
-
-        let form_data = resumable_form_data_reqwest(
-            UploadPhase::start,
-            "".to_string(),
-            "0".to_string(),
-            video_param.clone(),
-            file.try_clone().unwrap(),
-        );
+        let thumb = thumb_file.map(MediaSource::from_file).transpose()?;
+        self.resumable_post_media(video_param, MediaSource::from_file(file)?, thumb)
+            .await
+    }
 
-        let uploadind_data = UploadingData::new(
-            "empty:".to_owned(),
-            0,
-            0,
-            "start".to_string(),
-            "".to_string(),
-        );
+    /// Same as [resumable_post](VideoApi_reqwest::resumable_post), with a
+    /// video and a thumbnail which are not local files. Only the chunk being
+    /// sent is kept in memory.
+    pub async fn resumable_post_media(
+        &self,
+        video_param: VideoParams,
+        media: MediaSource,
+        thumb: Option<MediaSource>,
+    ) -> Result<FinalResponeResumableUpload, ClientErr> {
+        let mut session = self.start_resumable_post(media.size()).await?;
+        self.upload_chunks(media, &mut session, video_param, thumb)
+            .await
+    }
 
-        let url = base_url.clone() + "?access_token=" + &self.page_access_token;
+    /// Open a resumable upload session for a file of `file_size` bytes. The
+    /// session can be saved, and the file sent with
+    /// [resume_post](VideoApi_reqwest::resume_post).
+    pub async fn start_resumable_post(&self, file_size: u64) -> Result<UploadSession, ClientErr> {
+        self.report_progress(UploadPhase::start, 0, file_size);
+        let uploading_data =
+            UploadingData::new(String::new(), 0, 0, "start".to_string(), String::new())
+                .with_file_size(file_size);
+
+        let response = self
+            .http_connection
+            .resumable_video_post::<InitializeUploadResponse>(self.videos_url(), uploading_data)
+            .await?;
+        if response.upload_session_id.is_empty() {
+            return Err(ClientErr::FacebookError("The video upload initialization was not sucessfull, try upload again  or try with another video  ".to_string()));
+        }
 
-        let response =
-            HttpConnection::resumable_video_post::<InitializeUploadResponse>(url, uploadind_data)
-                .await?;
+        Ok(UploadSession {
+            start_offset: parse_offset(response.start_offset.as_deref().unwrap_or("0"))?,
+            end_offset: parse_offset(&response.end_offset)?,
+            video_id: response.video_id,
+            upload_session_id: response.upload_session_id,
+        })
+    }
 
-        let start_phase_data = response;
-        end_offset = Some(start_phase_data.end_offset); // update from the facebook response
-
-        #[cfg(any(feature = "reqwest"))]
-        let chunked_file_data = FileResultServer::file_analyze("your file path".to_owned());
-
-        let final_response = FinalResponeResumableUpload::default().update_params(
-            start_phase_data.video_id.clone(),
-            start_phase_data.upload_session_id.clone(),
-        ); // update some of the parameter of final response
-
-        let chunk_size = chunked_file_data.file_size_byte_f64();
-        // get the size of each chunk:  Note: the zero passed in  is just a dommy data
-        // when estimating the chunking size
-        // let uploaded_file = file.clone();
-
-        if !start_phase_data.upload_session_id.is_empty() {
-            // check if the  first request was sucessfull, if there is an  upload_session_id
-            // it means it was successfull
-            let mut final_response_status = false;
-            let mut current_chunk_size = chunk_size;
-            let mut start_chunk = 0.0;
-            // let uploaded_file = file.clone();
-            let upload_session_id = &start_phase_data.upload_session_id;
-
-            // loop and upload the chunked files until is completed then end the loop
-
-            while let Some(end_offset_status) = end_offset.clone() {
-                #[cfg(any(feature = "seed_async"))]
-                if current_chunk_size > file.size() {
-                    // update the current chunking sizing
-                    current_chunk_size = file.size()
-                };
-
-                // check if there is a response data from the initialzition request
-                if let Some(start_offset_status) = start_offset.clone() {
-                    //   start_offset_status = tee.clone();
-                    let self_data = self.clone();
-
-                    if end_offset_status != start_offset_status {
-                        let base_url = self.base_url.replace("EDGE", "videos");
-                        let url =
-                            base_url.clone() + "?access_token=" + &self.page_access_token.clone();
-
-                        let form_data = resumable_form_data_reqwest(
-                            UploadPhase::transfer,
-                            upload_session_id.to_string(),
-                            start_offset_status.clone(),
-                            video_params.clone(),
-                            file.try_clone().unwrap(),
-                        );
-
-                        let uploadind_data = UploadingData::new(
-                            "your file path".to_owned(),
-                            1048576,
-                            0,
-                            "transfer".to_string(),
-                            upload_session_id.to_string(),
-                        );
-
-                        let response =
-                            HttpConnection::resumable_video_post::<ChunksUploadResponse>(
-                                url,
-                                uploadind_data.clone(),
-                            )
-                            .await?;
-                        let chunk_upload_response = response;
-                        let result = ChunksUploadResponse::new(
-                            chunk_upload_response.start_offset,
-                            chunk_upload_response.end_offset,
-                        )
-                        .clone();
-                        start_offset = Some(result.start_offset); // == start_offset_status
-                        end_offset = Some(result.end_offset);
-
-                        start_chunk = current_chunk_size; // update the start chunk file
-                        current_chunk_size += chunk_size as f64;
-                    } else {
-                        let base_url = self.base_url.replace("EDGE", "videos");
-                        let url = base_url.clone() + "?access_token=" + &self.page_access_token;
-
-                        // There is an issue with the formdata for blob chunked file, it can only
-                        // take small sized of blob file, this making the
-                        // upload to take longer than expect. if larger blob
-                        // file is appeded to the formData, the data will not be posted along the
-                        // request causing an erorr.
-                        // allthough the formData can take large file that are not chunked, thefore
-                        // it is not certain   where the issue is coming
-                        //
-                        let uploadind_data = UploadingData::new(
-                            "your file path".to_owned(),
-                            0,
-                            0,
-                            "finish".to_string(),
-                            upload_session_id.to_string(),
-                        );
-
-                        let form_data = resumable_form_data_reqwest(
-                            UploadPhase::finish,
-                            upload_session_id.to_string(),
-                            "".to_string(),
-                            video_params.clone(),
-                            file.try_clone().unwrap(),
-                        );
-
-                        let uploadind_data = UploadingData::default();
-
-                        let resp = HttpConnection::resumable_video_post::<ResumableUploadFinal>(
-                            url,
-                            uploadind_data,
-                        )
-                        .await?;
-                        final_response_status = resp.success.clone();
-                        end_offset = None // end the while loop
-                    }
-                }
-            }
+    /// Send the rest of a file in an upload session, from the offsets of the
+    /// session, then finish the upload. This resumes an upload which was
+    /// interrupted, e.g. by a network failure or a restart of the
+    /// application.
+    ///
+    /// The offsets of the session are updated after every chunk, so when
+    /// the upload fails the session can be given again to resume from the
+    /// chunk which failed.
+    pub async fn resume_post(
+        &self,
+        video_param: VideoParams,
+        file: File,
+        session: &mut UploadSession,
+        thumb_file: Option<File>,
+    ) -> Result<FinalResponeResumableUpload, ClientErr> {
+        let thumb = thumb_file.map(MediaSource::from_file).transpose()?;
+        self.resume_post_media(video_param, MediaSource::from_file(file)?, session, thumb)
+            .await
+    }
 
-            // after the chunk file upload is completed,  trigger and Ok response to send
-            // the data.
-            Ok(final_response.update_success(final_response_status))
-        } else {
-            Err(ClientErr::FacebookError("The video upload initialization was not sucessfull, try upload again  or try with another video  ".to_string()))
-            // try to generate a customer
-            // error
+    /// Same as [resume_post](VideoApi_reqwest::resume_post), with a video
+    /// and a thumbnail which are not local files. A stream is read from its
+    /// start, skipping the bytes before the offsets of the session.
+    pub async fn resume_post_media(
+        &self,
+        video_param: VideoParams,
+        media: MediaSource,
+        session: &mut UploadSession,
+        thumb: Option<MediaSource>,
+    ) -> Result<FinalResponeResumableUpload, ClientErr> {
+        self.upload_chunks(media, session, video_param, thumb).await
+    }
+
+    /// Send the chunks asked by Facebook, keeping the offsets of the next
+    /// chunk in `session`, then finish the upload.
+    async fn upload_chunks(
+        &self,
+        mut media: MediaSource,
+        session: &mut UploadSession,
+        video_param: VideoParams,
+        thumb: Option<MediaSource>,
+    ) -> Result<FinalResponeResumableUpload, ClientErr> {
+        let total_bytes = media.size();
+        // Facebook ignores a chunk sent twice, so the chunks are also sent again
+        // when the connection failed.
+        let retry_policy = self
+            .http_connection
+            .retry_policy()
+            .clone()
+            .with_retry_connection_errors(true);
+
+        while session.start_offset < session.end_offset {
+            let chunk = media
+                .read_chunk(session.start_offset, session.end_offset)
+                .await?;
+            let uploading_data = UploadingData::new(
+                String::new(),
+                session.end_offset,
+                session.start_offset,
+                "transfer".to_string(),
+                session.upload_session_id.clone(),
+            )
+            .with_chunk(chunk);
+
+            let response = self
+                .upload_connection(
+                    UploadPhase::transfer,
+                    session.start_offset,
+                    session.end_offset,
+                    total_bytes,
+                )
+                .with_retry_policy(retry_policy.clone())
+                .resumable_video_post::<ChunksUploadResponse>(self.videos_url(), uploading_data)
+                .await?;
+            session.start_offset = parse_offset(&response.start_offset)?;
+            session.end_offset = parse_offset(&response.end_offset)?;
         }
+
+        let thumb = match thumb {
+            Some(thumb) => Some(thumb.into_bytes().await?),
+            None => None,
+        };
+        let uploading_data = UploadingData::new(
+            String::new(),
+            0,
+            0,
+            "finish".to_string(),
+            session.upload_session_id.clone(),
+        )
+        .with_description(video_param.description, video_param.title)
+        .with_thumb(thumb);
+        let response = self
+            .http_connection
+            .resumable_video_post::<ResumableUploadFinal>(self.videos_url(), uploading_data)
+            .await?;
+        self.report_progress(UploadPhase::finish, total_bytes, total_bytes);
+
+        Ok(FinalResponeResumableUpload::new(
+            session.video_id.clone(),
+            session.upload_session_id.clone(),
+            response.success,
+        ))
+    }
+
+    fn videos_url(&self) -> String {
+        RequestUrl::new(self.base_url.as_str())
+            .edge("videos")
+            .access_token(&self.page_access_token)
+            .build()
     }
 }
 
+fn parse_offset(offset: &str) -> Result<u64, ClientErr> {
+    offset
+        .parse()
+        .map_err(|_| ClientErr::FacebookError(format!("invalid upload offset: {}", offset)))
+}
+
 #[derive(Deserialize, Debug, Default, Serialize)]
 struct FeedPostSuccess {
     id: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::mock_server::{MockGraphServer, MockResponse, MOCK_UPLOAD_CHUNK_SIZE};
+    use crate::prelude::retry::RetryPolicy;
+    use std::io::Seek;
+    use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// A video of 2.5 chunks of the mock server, removed when dropped.
+    struct TestVideo {
+        path: std::path::PathBuf,
+        content: Vec<u8>,
+    }
+
+    impl TestVideo {
+        fn new(name: &str) -> TestVideo {
+            let path = std::env::temp_dir().join(format!(
+                "facebook_api_rs_{}_{}.mp4",
+                name,
+                std::process::id()
+            ));
+            let size = (MOCK_UPLOAD_CHUNK_SIZE * 5 / 2) as usize;
+            let content: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+            std::fs::write(&path, &content).unwrap();
+            TestVideo { path, content }
+        }
+
+        fn file(&self) -> File {
+            let mut file = File::open(&self.path).unwrap();
+            file.rewind().unwrap();
+            file
+        }
+    }
+
+    impl Drop for TestVideo {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    /// The chunks received by the server, in order.
+    fn received_chunks(server: &MockGraphServer) -> Vec<Vec<u8>> {
+        server
+            .requests()
+            .iter()
+            .filter(|request| request.param("upload_phase") == Some("transfer"))
+            .filter_map(|request| request.files.get("video_file_chunk").cloned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_resumable_post_sends_the_chunks_asked_by_facebook() {
+        let server = MockGraphServer::start().unwrap();
+        let video = TestVideo::new("resumable");
+        let params = VideoParams::default().update_video_params(VideoParams {
+            title: "My video".to_string(),
+            ..VideoParams::default()
+        });
+
+        let response = server
+            .client("page_token".to_string())
+            .video_upload("1234".to_string())
+            .resumable_post(video.file(), params, None)
+            .await
+            .unwrap();
+        assert!(response.success());
+        assert!(!response.video_id().is_empty());
+
+        let chunks = received_chunks(&server);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.concat() == video.content);
+
+        let requests = server.requests();
+        let start = &requests[0];
+        assert_eq!(start.param("upload_phase"), Some("start"));
+        assert_eq!(
+            start.param("file_size"),
+            Some(video.content.len().to_string().as_str())
+        );
+        let finish = requests.last().unwrap();
+        assert_eq!(finish.param("upload_phase"), Some("finish"));
+        assert_eq!(finish.param("title"), Some("My video"));
+        assert_eq!(
+            finish.param("upload_session_id"),
+            Some(response.upload_session_id())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_chunk_is_retried() {
+        let server = MockGraphServer::start().unwrap();
+        let video = TestVideo::new("retried_chunk");
+        let client = server.client("page_token".to_string()).add_retry_policy(
+            RetryPolicy::default()
+                .with_max_retries(1)
+                .with_initial_delay(Duration::from_millis(1)),
+        );
+
+        let videos = client.video_upload("1234".to_string());
+        let mut session = videos
+            .start_resumable_post(video.content.len() as u64)
+            .await
+            .unwrap();
+        server.mock_once(
+            "POST",
+            "/1234/videos",
+            MockResponse::error(500, 2, "OAuthException", "Service temporarily unavailable"),
+        );
+        let response = videos
+            .resume_post(video.file(), VideoParams::default(), &mut session, None)
+            .await
+            .unwrap();
+
+        assert!(response.success());
+        // The first chunk was sent twice.
+        assert_eq!(received_chunks(&server).len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_upload_is_resumed_from_the_saved_session() {
+        let server = Arc::new(MockGraphServer::start().unwrap());
+        let video = TestVideo::new("resumed");
+        let videos = server
+            .client("page_token".to_string())
+            .add_retry_policy(RetryPolicy::none())
+            .video_upload("1234".to_string());
+
+        let mut session = videos
+            .start_resumable_post(video.content.len() as u64)
+            .await
+            .unwrap();
+        let failing_server = Arc::clone(&server);
+        let failed = Arc::new(AtomicBool::new(false));
+        let videos = videos.add_progress_callback(move |progress| {
+            // Fail the upload of the second chunk, once.
+            if progress.start_offset == MOCK_UPLOAD_CHUNK_SIZE && !failed.swap(true, SeqCst) {
+                failing_server.mock_once(
+                    "POST",
+                    "/1234/videos",
+                    MockResponse::error(500, 2, "OAuthException", "Service unavailable"),
+                );
+            }
+        });
+
+        assert!(videos
+            .resume_post(video.file(), VideoParams::default(), &mut session, None)
+            .await
+            .is_err());
+        // The session has the offsets of the chunk which failed.
+        assert_eq!(session.start_offset, MOCK_UPLOAD_CHUNK_SIZE);
+        assert_eq!(session.end_offset, 2 * MOCK_UPLOAD_CHUNK_SIZE);
+
+        let mut saved: UploadSession =
+            serde_json::from_str(&serde_json::to_string(&session).unwrap()).unwrap();
+        let response = videos
+            .resume_post(video.file(), VideoParams::default(), &mut saved, None)
+            .await
+            .unwrap();
+        assert!(response.success());
+
+        let chunks = received_chunks(&server);
+        // The second chunk was sent by both uploads.
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks[1] == chunks[2]);
+        assert!([&chunks[0][..], &chunks[2], &chunks[3]].concat() == video.content);
+    }
+
+    #[tokio::test]
+    async fn test_video_and_thumbnail_from_bytes() {
+        let server = MockGraphServer::start().unwrap();
+        let video = vec![b'v'; 1024];
+        let thumb = vec![b't'; 64];
+
+        server
+            .client("page_token".to_string())
+            .video_upload("1234".to_string())
+            .non_resumable_post_media(
+                VideoParams::default(),
+                video.clone(),
+                Some(thumb.clone().into()),
+            )
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(request.files.get("source"), Some(&video));
+        assert_eq!(request.files.get("thumb"), Some(&thumb));
+    }
+
+    #[tokio::test]
+    async fn test_resumable_post_from_a_stream() {
+        let server = MockGraphServer::start().unwrap();
+        let video = TestVideo::new("stream");
+        let size = video.content.len() as u64;
+        let stream = std::io::Cursor::new(video.content.clone());
+        let thumb = vec![b't'; 64];
+
+        let response = server
+            .client("page_token".to_string())
+            .video_upload("1234".to_string())
+            .resumable_post_media(
+                MediaSource::from_reader(stream, size),
+                VideoParams::default(),
+                Some(thumb.clone().into()),
+            )
+            .await
+            .unwrap();
+
+        assert!(response.success());
+        assert!(received_chunks(&server).concat() == video.content);
+        let finish = server
+            .requests()
+            .into_iter()
+            .find(|request| request.param("upload_phase") == Some("finish"))
+            .unwrap();
+        assert_eq!(finish.files.get("thumb"), Some(&thumb));
+    }
+}
@@ -15,7 +15,11 @@
 //! For information on different opertaions on facebook page  check  <https://developers.facebook.com/docs/graph-api/reference/page/videos/#Creating>
 use crate::graph::utils::FileResult;
 use crate::prelude::errors::ClientErr;
-use crate::prelude::HttpConnection;
+use crate::prelude::media_upload::progress::{ProgressCallback, UploadProgress};
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::web_sys_client::js_error;
+use crate::universal::HttpClient;
 // use seed::fetch::{fetch, FormData};
 // use seed::{prelude::*, *};
 use crate::prelude::utils::PostResponse;
@@ -56,20 +60,77 @@ struct InitializeUploadResponse {
 /// This struct is the response gotten when initializing the resumable uploading
 /// method process.
 
-#[derive(Deserialize, Clone, Serialize, Default)]
-pub struct VideoApi_seed {
+#[derive(Clone)]
+pub struct VideoApi_seed<HttpC: HttpClient = DefaultHttpClient> {
     base_url: String,
     page_access_token: String,
+    http_connection: GenericClientConnection<HttpC>,
+    progress: Option<ProgressCallback>,
 }
 
-impl VideoApi_seed {
-    pub fn new(base_url: String, page_access_token: String) -> VideoApi_seed {
+impl<HttpC: HttpClient> VideoApi_seed<HttpC> {
+    pub fn new(
+        base_url: String,
+        page_access_token: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> VideoApi_seed<HttpC> {
         VideoApi_seed {
             base_url,
             page_access_token,
+            http_connection,
+            progress: None,
         }
     }
 
+    /// Report the progress of the uploads to `progress`.
+    pub fn with_progress(mut self, progress: Option<ProgressCallback>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// The connection sending the bytes `start_offset..end_offset` of a file,
+    /// reporting their progress when there is a progress callback.
+    fn upload_connection(
+        &self,
+        phase: UploadPhase,
+        start_offset: u64,
+        end_offset: u64,
+        total_bytes: u64,
+    ) -> GenericClientConnection<HttpC> {
+        match &self.progress {
+            Some(progress) => {
+                self.http_connection
+                    .clone()
+                    .with_request_progress(progress.request_progress(
+                        phase,
+                        start_offset,
+                        end_offset,
+                        total_bytes,
+                    ))
+            }
+            None => self.http_connection.clone(),
+        }
+    }
+
+    fn report_progress(&self, phase: UploadPhase, bytes_sent: u64, total_bytes: u64) {
+        if let Some(progress) = &self.progress {
+            progress.report(UploadProgress {
+                phase,
+                bytes_sent,
+                total_bytes,
+                start_offset: bytes_sent,
+                end_offset: bytes_sent,
+            });
+        }
+    }
+
+    fn videos_url(&self) -> String {
+        RequestUrl::new(self.base_url.as_str())
+            .edge("videos")
+            .access_token(&self.page_access_token)
+            .build()
+    }
+
     /// facebook recommend that you upload files using the Resumable Upload
     /// method because it handles connection interruptions more efficiently
     /// and supports larger files. However, if you prefer to upload files
@@ -88,14 +149,17 @@ impl VideoApi_seed {
         file: File,
     ) -> Result<PostResponse, ClientErr> {
         let uploaded_file = file.clone();
-        let file_result = FileResult::file_analyze(file);
+        let total_bytes = file.size() as u64;
+        let file_result = FileResult::file_analyze(file)?;
         // check if the uploading method
         if file_result.uploading_method() == "non_resumable" {
-            let form_data = form_data_seed(uploaded_file, video_params);
-            let base_url = self.base_url.replace("EDGE", "videos");
-            let url = base_url + "?access_token=" + &self.page_access_token;
+            let form_data = form_data_seed(uploaded_file, video_params)?;
+            let url = self.videos_url();
 
-            let resp = HttpConnection::video_post::<PostResponse>(url, form_data).await?;
+            let resp = self
+                .upload_connection(UploadPhase::transfer, 0, total_bytes, total_bytes)
+                .video_post::<PostResponse>(url, form_data)
+                .await?;
             Ok(resp)
         } else {
             Err(ClientErr::FacebookError(
@@ -106,7 +170,7 @@ impl VideoApi_seed {
     }
 }
 
-impl VideoApi_seed {
+impl<HttpC: HttpClient> VideoApi_seed<HttpC> {
     /// This method is used for uploading large video files, it does that by
     /// chunking the file and uplaoding them individually until is complete.
     /// The method takes two parameter( file, video parameter struct).
@@ -127,25 +191,28 @@ impl VideoApi_seed {
         let video_params = video_param.clone();
         let self_data = self.clone();
 
-        let base_url = self.base_url.replace("EDGE", "videos").clone();
         let mut form_data = resumable_form_data_seed(
             UploadPhase::start,
-            Blob::new().unwrap(),
+            Blob::new().map_err(js_error)?,
             uploaded_file.clone(),
             "".to_string(),
             "0".to_string(),
             video_param.clone(),
-        );
+        )?;
 
-        let url = base_url.clone() + "?access_token=" + &self.page_access_token;
+        let url = self.videos_url();
+        let total_bytes = file.size() as u64;
+        self.report_progress(UploadPhase::start, 0, total_bytes);
 
-        let response =
-            HttpConnection::video_post::<InitializeUploadResponse>(url, form_data).await?;
+        let response = self
+            .http_connection
+            .video_post::<InitializeUploadResponse>(url, form_data)
+            .await?;
 
         let start_phase_data = response;
 
         end_offset = Some(start_phase_data.end_offset); // update from the facebook response
-        let chunked_file_data = FileResult::file_analyze(file.clone()).clone();
+        let chunked_file_data = FileResult::file_analyze(file.clone())?;
 
         let final_response = FinalResponeResumableUpload::default().update_params(
             start_phase_data.video_id.clone(),
@@ -154,7 +221,7 @@ impl VideoApi_seed {
 
         let chunk_size = chunked_file_data
             .clone()
-            .chunk_file(0.0, 0.0)
+            .chunk_file(0.0, 0.0)?
             .upload_chunking_size(); // get the size of each chunk:  Note: the zero passed in  is just a dommy data
                                      // when estimating the chunking size
                                      // let uploaded_file = file.clone();
@@ -187,24 +254,29 @@ impl VideoApi_seed {
                     let self_data = self.clone();
 
                     if end_offset_status != start_offset_status {
-                        let base_url = self.base_url.replace("EDGE", "videos");
-                        let url =
-                            base_url.clone() + "?access_token=" + &self.page_access_token.clone();
+                        let url = self.videos_url();
 
                         let form_data = resumable_form_data_seed(
                             UploadPhase::transfer,
-                            FileResult::file_analyze(file.clone())
-                                .chunk_file(start_chunk, current_chunk_size)
+                            FileResult::file_analyze(file.clone())?
+                                .chunk_file(start_chunk, current_chunk_size)?
                                 .chunked_file(),
                             file.clone(),
                             upload_session_id.to_string(),
                             start_offset_status.clone(),
                             video_params.clone(),
-                        );
-
-                        let response =
-                            HttpConnection::video_post::<ChunksUploadResponse>(url, form_data)
-                                .await?;
+                        )?;
+
+                        let chunk_end = (current_chunk_size as u64).min(total_bytes);
+                        let response = self
+                            .upload_connection(
+                                UploadPhase::transfer,
+                                start_chunk as u64,
+                                chunk_end,
+                                total_bytes,
+                            )
+                            .video_post::<ChunksUploadResponse>(url, form_data)
+                            .await?;
 
                         let chunk_upload_response = response;
 
@@ -219,8 +291,7 @@ impl VideoApi_seed {
                         start_chunk = current_chunk_size; // update the start chunk file
                         current_chunk_size += chunk_size as f64;
                     } else {
-                        let base_url = self.base_url.replace("EDGE", "videos");
-                        let url = base_url.clone() + "?access_token=" + &self.page_access_token;
+                        let url = self.videos_url();
 
                         // There is an issue with the formdata for blob chunked file, it can only
                         // take small sized of blob file, this making the
@@ -232,19 +303,21 @@ impl VideoApi_seed {
                         //
                         let form_data = resumable_form_data_seed(
                             UploadPhase::finish,
-                            Blob::new().unwrap(), // not important in the uplaod phase
+                            Blob::new().map_err(js_error)?, // not important in the uplaod phase
                             file.clone(),
                             upload_session_id.to_string(),
                             "".to_string(),
                             video_params.clone(),
-                        );
+                        )?;
 
-                        let resp =
-                            HttpConnection::video_post::<ResumableUploadFinal>(url, form_data)
-                                .await?;
+                        let resp = self
+                            .http_connection
+                            .video_post::<ResumableUploadFinal>(url, form_data)
+                            .await?;
                         let uploadind_data = UploadingData::default();
 
                         final_response_status = resp.success.clone();
+                        self.report_progress(UploadPhase::finish, total_bytes, total_bytes);
                         end_offset = None // end the while loop
                     }
                 }
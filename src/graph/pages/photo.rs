@@ -1,7 +1,7 @@
 //! The photo API of Facebook Page lets you publish photos and retrieve profile pictures and uploaded photos.
 //!
 //! # Features
-//! - Upload photos by file (web-sys feature) or URL (reqwest feature)
+//! - Upload photos by file, bytes or stream (reqwest feature), by file (web-sys feature), or by URL
 //! - Retrieve all photos by page_id with optional type filter
 //! - Support for published and unpublished photos
 //! - Temporary photos for scheduled posts
@@ -23,11 +23,24 @@
 //!
 //! For more information check <https://developers.facebook.com/docs/graph-api/reference/v23.0/page/photos>.
 
-use crate::graph::data::Data;
+use crate::graph::data::{paginate, Data, PageOptions};
 use crate::prelude::errors::ClientErr;
+use crate::prelude::media_upload::progress::{ProgressCallback, UploadProgress};
+#[cfg(feature = "reqwest")]
+use crate::prelude::media_upload::source::MediaSource;
 use crate::prelude::utils::GetPostResponse;
-use crate::prelude::HttpConnection;
+use crate::prelude::video::UploadPhase;
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
+#[cfg(feature = "reqwest")]
+use crate::universal::form_data::photo_form_data;
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
+#[cfg(feature = "reqwest")]
+use bytes::Bytes;
+use futures_util::Stream;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "reqwest")]
+use std::fs::File;
 #[cfg(feature = "web-sys")]
 use web_sys::{File, FormData};
 
@@ -39,12 +52,14 @@ use web_sys::{File, FormData};
 ///
 /// For more information check <https://developers.facebook.com/docs/graph-api/reference/v23.0/page/photos>.
 #[derive(Clone)]
-pub struct PhotoApi {
+pub struct PhotoApi<HttpC: HttpClient = DefaultHttpClient> {
     base_url: String,
     page_access_token: String,
+    http_connection: GenericClientConnection<HttpC>,
+    progress: Option<ProgressCallback>,
 }
 
-impl PhotoApi {
+impl<HttpC: HttpClient> PhotoApi<HttpC> {
     /// Creates a new instance of PhotoApi.
     /// This is a static method used to create an instance of the PhotoApi.
     /// Note: This method is called inside the Client.
@@ -52,13 +67,30 @@ impl PhotoApi {
     /// # Arguments
     /// * `base_url` - The base URL for the API endpoint
     /// * `page_access_token` - The page access token for authentication
-    pub fn new(base_url: String, page_access_token: String) -> PhotoApi {
+    /// * `http_connection` - The connection the requests are sent through
+    pub fn new(
+        base_url: String,
+        page_access_token: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> PhotoApi<HttpC> {
         PhotoApi {
             base_url,
             page_access_token,
+            http_connection,
+            progress: None,
         }
     }
 
+    /// Call `callback` with the [UploadProgress](UploadProgress) of an
+    /// upload, while the photo is sent.
+    pub fn add_progress_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(UploadProgress) + Send + Sync + 'static,
+    {
+        self.progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
     /// Posts a photo to the Facebook page using a file upload (multipart/form-data).
     /// This method is only available with the web-sys feature for browser environments.
     ///
@@ -85,15 +117,85 @@ impl PhotoApi {
         file: File,
         photo_params: PhotoParams,
     ) -> Result<PhotoResponse, ClientErr> {
-        let form_data = self.clone().form_data(photo_params, file);
-        let base_url = self.base_url.replace("EDGE", "photos");
-        let url = base_url + "?access_token=" + &self.page_access_token;
-        let resp = HttpConnection::video_post::<PhotoResponse>(url, form_data).await?;
+        let total_bytes = file.size() as u64;
+        let form_data = self.clone().form_data(photo_params, file)?;
+        let url = self.photos_request_url().build();
+        let resp = self
+            .upload_connection(total_bytes)
+            .video_post::<PhotoResponse>(url, form_data)
+            .await?;
         Ok(resp)
     }
 
+    /// Posts a photo to the Facebook page from its content, e.g. a `Vec<u8>`,
+    /// a `Bytes` or a stream of a known size, see [MediaSource](MediaSource).
+    /// This method is only available with the reqwest feature.
+    ///
+    /// The photo is uploaded as multipart/form-data with the parameter name
+    /// `source`, along with the `message`, `place`, `published` and
+    /// `temporary` of `photo_params`.
+    ///
+    /// # Arguments
+    /// * `media` - The content of the photo to upload
+    /// * `photo_params` - Parameters for the photo upload (message, published status, etc.)
+    ///
+    /// # Returns
+    /// Returns a `PhotoResponse` containing the photo ID and post ID on success.
+    ///
+    /// For more information check <https://developers.facebook.com/docs/graph-api/reference/v23.0/page/photos#creating>.
+    #[cfg(feature = "reqwest")]
+    pub async fn post_by_media(
+        &self,
+        media: impl Into<MediaSource>,
+        photo_params: PhotoParams,
+    ) -> Result<PhotoResponse, ClientErr> {
+        let photo = media.into().into_bytes().await?;
+        let total_bytes = photo.len() as u64;
+        let form_data = photo_form_data(&photo_params, photo);
+
+        let url = self.photos_request_url().build();
+        self.upload_connection(total_bytes)
+            .multipart_post::<PhotoResponse>(url, form_data)
+            .await
+    }
+
+    /// Posts a local photo file to the Facebook page, see
+    /// [post_by_media](PhotoApi::post_by_media).
+    /// This method is only available with the reqwest feature.
+    ///
+    /// # Arguments
+    /// * `file` - The photo file to upload
+    /// * `photo_params` - Parameters for the photo upload (message, published status, etc.)
+    #[cfg(feature = "reqwest")]
+    pub async fn post_by_file(
+        &self,
+        file: File,
+        photo_params: PhotoParams,
+    ) -> Result<PhotoResponse, ClientErr> {
+        self.post_by_media(MediaSource::from_file(file)?, photo_params)
+            .await
+    }
+
+    /// Posts a photo held in memory to the Facebook page, see
+    /// [post_by_media](PhotoApi::post_by_media).
+    /// This method is only available with the reqwest feature.
+    ///
+    /// # Arguments
+    /// * `bytes` - The content of the photo, e.g. a `Vec<u8>`
+    /// * `photo_params` - Parameters for the photo upload (message, published status, etc.)
+    #[cfg(feature = "reqwest")]
+    pub async fn post_by_bytes(
+        &self,
+        bytes: impl Into<Bytes>,
+        photo_params: PhotoParams,
+    ) -> Result<PhotoResponse, ClientErr> {
+        self.post_by_media(MediaSource::from_bytes(bytes), photo_params)
+            .await
+    }
+
     /// Posts a photo to the Facebook page using a URL.
     /// This method uploads a photo that is already on the internet.
+    /// The url and the other parameters are sent as url encoded POST fields.
     ///
     /// # Arguments
     /// * `photo_params` - Parameters including the URL of the photo to upload
@@ -103,30 +205,18 @@ impl PhotoApi {
     ///
     /// For more information check <https://developers.facebook.com/docs/graph-api/reference/v23.0/page/photos#creating>.
     pub async fn post_by_url(&self, photo_params: PhotoParams) -> Result<PhotoResponse, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "photos");
-        let mut url = base_url + "?access_token=" + &self.page_access_token;
-
-        if !photo_params.url.is_empty() {
-            url = url + "&url=" + &photo_params.url;
-        }
-
-        if !photo_params.message.is_empty() {
-            url = url + "&message=" + &photo_params.message;
-        }
-
-        if !photo_params.published {
-            url = url + "&published=false";
-        }
-
-        if photo_params.temporary {
-            url = url + "&temporary=true";
-        }
-
-        if !photo_params.place.is_empty() {
-            url = url + "&place=" + &photo_params.place;
-        }
-
-        let resp = HttpConnection::get::<PhotoResponse>(url, "".to_string()).await?;
+        // The fields left in the query of a POST without a body are sent as
+        // an url encoded body.
+        let url = self
+            .photos_request_url()
+            .non_empty_param("url", &photo_params.url)
+            .params(photo_params.fields())
+            .build();
+
+        let resp = self
+            .http_connection
+            .post::<PhotoResponse, String>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
@@ -146,20 +236,63 @@ impl PhotoApi {
         &self,
         photo_type: Option<PhotoType>,
     ) -> Result<Data<GetPostResponse>, ClientErr> {
-        let mut url = self.base_url.replace("EDGE", "photos");
+        let url = self.photos_url(photo_type);
 
-        if let Some(ptype) = photo_type {
-            url = url + "?type=" + ptype.as_str();
-            url = url + "&access_token=" + &self.page_access_token;
-        } else {
-            url = url + "?access_token=" + &self.page_access_token;
-        }
-
-        let resp = HttpConnection::get::<Data<GetPostResponse>>(url, "".to_string()).await?;
+        let resp = self
+            .http_connection
+            .get::<Data<GetPostResponse>>(url, "".to_string())
+            .await?;
 
         Ok(resp)
     }
 
+    /// Same as [all_photos](PhotoApi::all_photos), but returns the photos of
+    /// every page, following the `next` page while the stream is consumed.
+    pub fn all_photos_stream(
+        &self,
+        photo_type: Option<PhotoType>,
+        options: PageOptions,
+    ) -> impl Stream<Item = Result<GetPostResponse, ClientErr>> {
+        paginate(
+            self.http_connection.clone(),
+            self.photos_url(photo_type),
+            options,
+        )
+    }
+
+    fn photos_url(&self, photo_type: Option<PhotoType>) -> String {
+        RequestUrl::new(self.base_url.as_str())
+            .edge("photos")
+            .optional_param("type", photo_type.as_ref().map(PhotoType::as_str))
+            .access_token(&self.page_access_token)
+            .build()
+    }
+
+    /// The url of the `photos` edge, with the access token.
+    fn photos_request_url(&self) -> RequestUrl {
+        RequestUrl::new(self.base_url.as_str())
+            .edge("photos")
+            .access_token(&self.page_access_token)
+    }
+
+    /// The connection sending a photo of `total_bytes`, which reports the
+    /// progress of the upload to the callback, if any.
+    fn upload_connection(&self, total_bytes: u64) -> GenericClientConnection<HttpC> {
+        match &self.progress {
+            Some(progress) => {
+                self.http_connection
+                    .clone()
+                    .with_request_progress(progress.request_progress(
+                        UploadPhase::transfer,
+                        0,
+                        total_bytes,
+                        total_bytes,
+                    ))
+            }
+            None => self.http_connection.clone(),
+        }
+    }
+
     /// Creates a FormData object for uploading a photo with parameters.
     /// This is a helper method for constructing the multipart form data.
     /// The parameter name `source` is used historically for photo uploads.
@@ -168,31 +301,21 @@ impl PhotoApi {
     /// * `photo_params` - Parameters for the photo upload
     /// * `file` - The File object to upload
     #[cfg(feature = "web-sys")]
-    fn form_data(self, photo_params: PhotoParams, file: File) -> FormData {
-        let form_data = FormData::new().unwrap();
+    fn form_data(self, photo_params: PhotoParams, file: File) -> Result<FormData, ClientErr> {
+        let form_data = FormData::new().map_err(crate::universal::web_sys_client::js_error)?;
 
         // The name "source" is used historically for photo uploads
-        form_data.append_with_blob("source", &file).ok();
+        form_data
+            .append_with_blob("source", &file)
+            .map_err(crate::universal::web_sys_client::js_error)?;
 
-        if !photo_params.message.is_empty() {
+        for (name, value) in photo_params.fields() {
             form_data
-                .append_with_str("message", &photo_params.message)
-                .ok();
-        }
-
-        if !photo_params.published {
-            form_data.append_with_str("published", "false").ok();
-        }
-
-        if photo_params.temporary {
-            form_data.append_with_str("temporary", "true").ok();
+                .append_with_str(name, &value)
+                .map_err(crate::universal::web_sys_client::js_error)?;
         }
 
-        if !photo_params.place.is_empty() {
-            form_data.append_with_str("place", &photo_params.place).ok();
-        }
-
-        form_data
+        Ok(form_data)
     }
 }
 
@@ -315,6 +438,31 @@ impl PhotoParams {
         self.file_path = file_path.into();
         self
     }
+
+    /// The fields sent with an uploaded photo, or with the url of a photo:
+    /// `message`, `published`, `temporary` and `place`, when they are not
+    /// the default of Facebook.
+    pub(crate) fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+
+        if !self.message.is_empty() {
+            fields.push(("message", self.message.clone()));
+        }
+
+        if !self.published {
+            fields.push(("published", "false".to_string()));
+        }
+
+        if self.temporary {
+            fields.push(("temporary", "true".to_string()));
+        }
+
+        if !self.place.is_empty() {
+            fields.push(("place", self.place.clone()));
+        }
+
+        fields
+    }
 }
 
 // Helper functions for serde skip_serializing_if
@@ -335,3 +483,95 @@ pub struct PhotoResponse {
     /// The ID of the post that was created with this photo
     pub post_id: String,
 }
+
+#[cfg(all(test, feature = "reqwest"))]
+mod tests {
+    use super::*;
+    use crate::prelude::mock_server::MockGraphServer;
+
+    #[tokio::test]
+    async fn test_post_by_media_sends_the_params_in_the_body() {
+        let server = MockGraphServer::start().unwrap();
+        let photo = vec![b'p'; 512];
+        let params = PhotoParams::new()
+            .message("Hello & welcome")
+            .published(false)
+            .temporary(true)
+            .place("110843418940484");
+
+        let response = server
+            .client("page_token".to_string())
+            .photos("1234".to_string())
+            .post_by_media(photo.clone(), params)
+            .await
+            .unwrap();
+
+        assert!(response.post_id.starts_with("1234_"));
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.files.get("source"), Some(&photo));
+        assert_eq!(
+            request.form.get("message").map(String::as_str),
+            Some("Hello & welcome")
+        );
+        assert_eq!(
+            request.form.get("published").map(String::as_str),
+            Some("false")
+        );
+        assert_eq!(
+            request.form.get("temporary").map(String::as_str),
+            Some("true")
+        );
+        assert_eq!(
+            request.form.get("place").map(String::as_str),
+            Some("110843418940484")
+        );
+        assert!(!request.query.contains_key("message"));
+    }
+
+    #[tokio::test]
+    async fn test_post_by_file() {
+        let server = MockGraphServer::start().unwrap();
+        let path =
+            std::env::temp_dir().join(format!("facebook_api_rs_photo_{}.jpg", std::process::id()));
+        let photo = vec![b'j'; 256];
+        std::fs::write(&path, &photo).unwrap();
+
+        let response = server
+            .client("page_token".to_string())
+            .photos("1234".to_string())
+            .post_by_file(File::open(&path).unwrap(), PhotoParams::new())
+            .await;
+        std::fs::remove_file(&path).unwrap();
+        response.unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(request.files.get("source"), Some(&photo));
+        // Published by default.
+        assert!(!request.form.contains_key("published"));
+    }
+
+    #[tokio::test]
+    async fn test_post_by_url_sends_encoded_fields() {
+        let server = MockGraphServer::start().unwrap();
+        let params = PhotoParams::new()
+            .url("https://example.com/image.png?size=large&v=2")
+            .message("Tom & Jerry #1");
+
+        server
+            .client("page_token".to_string())
+            .photos("1234".to_string())
+            .post_by_url(params)
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "POST");
+        assert!(request.query.is_empty());
+        assert_eq!(
+            request.param("url"),
+            Some("https://example.com/image.png?size=large&v=2")
+        );
+        assert_eq!(request.param("message"), Some("Tom & Jerry #1"));
+    }
+}
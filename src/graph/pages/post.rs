@@ -7,8 +7,13 @@
 //! <https://developers.facebook.com/docs/graph-api/reference/post>
 use crate::graph::pages::utils::{Fields, From, GetPostResponse};
 use crate::prelude::errors::ClientErr;
+use crate::prelude::fields::FieldSelection;
 use crate::prelude::utils::Id;
-use crate::prelude::{Data, HttpConnection};
+use crate::prelude::{paginate, Data, DefaultHttpClient, GenericClientConnection, PageOptions};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 /// The Post API let  you perform operation on individual post in a profile's
@@ -16,18 +21,24 @@ use serde::{Deserialize, Serialize};
 /// Note: This end point does not allow you to create post or delete.
 /// for more details check facebook documentation on post Api.
 /// <https://developers.facebook.com/docs/graph-api/reference/page-post/>
-pub struct PostApi {
+pub struct PostApi<HttpC: HttpClient = DefaultHttpClient> {
     base_url: String,
     page_access_token: String,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl PostApi {
+impl<HttpC: HttpClient> PostApi<HttpC> {
     /// This is a static method used to create an instance of the feedApi
     /// Note: this method is called inside of the Client method
-    pub fn new(base_url: String, access_token: String) -> PostApi {
+    pub fn new(
+        base_url: String,
+        access_token: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> PostApi<HttpC> {
         PostApi {
             base_url,
             page_access_token: access_token,
+            http_connection,
         }
     }
 
@@ -35,57 +46,103 @@ impl PostApi {
     /// /v23.0/{post-id}). it returns the data of the post_id  you have
     /// provided
     pub async fn get(self) -> Result<GetPostResponse, ClientErr> {
-        let mut url = self.base_url.replace("EDGE", "?fields=");
+        let fields = FieldSelection::from_iter(Fields::default().fields);
+        self.get_with_fields::<GetPostResponse>(fields).await
+    }
 
-        let field_count = Fields::default().fields.len();
-        for (count, field) in Fields::default().fields.into_iter().enumerate() {
-            if count < field_count - 1 {
-                url = url + &field + ",";
-            } else {
-                url = url + &field; // remove the comma in the last filed
-            }
-        }
-        let base_url = url + "&access_token=" + &self.page_access_token;
+    /// Same as [get](PostApi::get), with the fields chosen by the caller,
+    /// e.g. `comments.limit(5){message,from}`, see
+    /// [FieldSelection](FieldSelection). The response is returned as `T`,
+    /// which may be a type of the caller.
+    pub async fn get_with_fields<T: DeserializeOwned>(
+        self,
+        fields: FieldSelection,
+    ) -> Result<T, ClientErr> {
+        let url = RequestUrl::new(self.base_url.as_str())
+            .node()
+            .fields(&fields)
+            .access_token(&self.page_access_token)
+            .build();
 
-        let resp = HttpConnection::get::<GetPostResponse>(base_url, "".to_string()).await?;
+        let resp = self.http_connection.get::<T>(url, "".to_string()).await?;
         Ok(resp)
     }
 
     // TODO: impliment post uodated methods and test newly added methods
     pub async fn update() {}
 
+    /// The first page of the comments of the post, see
+    /// [comments_stream](PostApi::comments_stream) for all the comments.
     pub async fn comments(self) -> Result<Comments, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "commnets");
-        let url = base_url + "&access_token=" + &self.page_access_token;
-        let resp = HttpConnection::get::<Comments>(url, "".to_string()).await?;
+        let url = self.comments_url();
+        let resp = self
+            .http_connection
+            .get::<Comments>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
+    /// The comments of the post, following the pages of the comments while
+    /// the stream is consumed.
+    pub fn comments_stream(
+        self,
+        options: PageOptions,
+    ) -> impl Stream<Item = Result<Comment, ClientErr>> {
+        let url = self.comments_url();
+        paginate(self.http_connection, url, options)
+    }
+
+    fn comments_url(&self) -> String {
+        RequestUrl::new(self.base_url.as_str())
+            .edge("comments")
+            .access_token(&self.page_access_token)
+            .build()
+    }
+
     pub async fn comment_on_post(self, message: String) -> Result<Id, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "commnets");
-        let url = base_url + "?message=" + &message + "&access_token=" + &self.page_access_token;
+        let url = RequestUrl::new(self.base_url.as_str())
+            .edge("comments")
+            .param("message", message)
+            .access_token(&self.page_access_token)
+            .build();
 
-        let resp = HttpConnection::post::<Id, String>(url, "".to_string()).await?;
+        let resp = self
+            .http_connection
+            .post::<Id, String>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 
     pub async fn delete(self) -> Result<Id, ClientErr> {
-        let base_url = self.base_url.replace("EDGE", "");
-        let url = base_url + "&access_token=" + &self.page_access_token;
-        let resp = HttpConnection::delete::<Id>(url, "".to_string()).await?;
+        let url = RequestUrl::new(self.base_url.as_str())
+            .node()
+            .access_token(&self.page_access_token)
+            .build();
+        let resp = self
+            .http_connection
+            .delete::<Id>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 }
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct Comments {
+    #[serde(flatten)]
     data: Data<Comment>,
 }
 
+impl Comments {
+    /// The comments, and the paging of the comments.
+    pub fn data(&self) -> &Data<Comment> {
+        &self.data
+    }
+}
+
 #[derive(Deserialize, Debug, Serialize)]
 pub struct Comment {
-    from: From,
-    created_time: String,
-    message: String,
-    id: String,
+    pub from: From,
+    pub created_time: String,
+    pub message: String,
+    pub id: String,
 }
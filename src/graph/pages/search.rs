@@ -3,8 +3,11 @@
 //! a Page. For more information check <https://developers.facebook.com/docs/pages/searching>.
 
 use crate::graph::accounts::Account;
-use crate::prelude::HttpConnection;
+use crate::prelude::fields::FieldSelection;
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
 use crate::universal::errors::ClientErr;
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Default)]
@@ -69,16 +72,22 @@ pub struct Location {
     pub zip: String,
 }
 
-pub struct PagesSearchAPI {
+pub struct PagesSearchAPI<HttpC: HttpClient = DefaultHttpClient> {
     pub page_access_token: String,
     pub base_url: String,
+    http_connection: GenericClientConnection<HttpC>,
 }
 
-impl PagesSearchAPI {
-    pub fn new(base_url: String, page_access_token: String) -> PagesSearchAPI {
+impl<HttpC: HttpClient> PagesSearchAPI<HttpC> {
+    pub fn new(
+        base_url: String,
+        page_access_token: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> PagesSearchAPI<HttpC> {
         PagesSearchAPI {
             base_url,
             page_access_token,
+            http_connection,
         }
     }
 
@@ -91,14 +100,18 @@ impl PagesSearchAPI {
 
         //  note: g should be a dynamic value that will be pass in
         let q = "oslo";
-        let url = self.base_url
-            + "?q="
-            + q
-            + "&fields=id,name,location,link"
-            + "&access_token="
-            + &self.page_access_token;
-
-        let resp = HttpConnection::get::<PageSearch>(url, "".to_string()).await?;
+        let url = RequestUrl::new(self.base_url)
+            .param("q", q)
+            .fields(&FieldSelection::from_iter([
+                "id", "name", "location", "link",
+            ]))
+            .access_token(&self.page_access_token)
+            .build();
+
+        let resp = self
+            .http_connection
+            .get::<PageSearch>(url, "".to_string())
+            .await?;
         Ok(resp)
     }
 }
@@ -1,6 +1,7 @@
 //! This utils will contain different methods and struct that are shared within
 //! the pages  mod
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 #[cfg(any(feature = "web-sys"))]
 use web_sys::{Blob, File, FormData};
@@ -56,6 +57,10 @@ pub struct PostByLinkParams {
     file_url: String,
 }
 
+/// The data sent by one request of a resumable video upload.
+///
+/// The bytes of a `transfer` request are taken from `chunk`, or, when it is
+/// empty, read from `file_path` between `start_offset` and `end_offset`.
 #[derive(Clone, Deserialize, Debug, Serialize, Default)]
 pub struct UploadingData {
     pub file_path: String,
@@ -63,6 +68,21 @@ pub struct UploadingData {
     pub start_offset: u64,
     pub upload_phase: String,
     pub upload_session_id: String,
+    /// The size of the whole file, sent by the `start` request.
+    #[serde(default)]
+    pub file_size: u64,
+    /// The bytes sent by a `transfer` request.
+    #[serde(skip)]
+    pub chunk: Vec<u8>,
+    /// The description of the video, sent by the `finish` request.
+    #[serde(default)]
+    pub description: String,
+    /// The title of the video, sent by the `finish` request.
+    #[serde(default)]
+    pub title: String,
+    /// The thumbnail of the video, sent by the `finish` request.
+    #[serde(skip)]
+    pub thumb: Option<Bytes>,
 }
 
 impl UploadingData {
@@ -79,8 +99,30 @@ impl UploadingData {
             start_offset,
             upload_phase,
             upload_session_id,
+            ..Default::default()
         }
     }
+
+    pub fn with_file_size(mut self, file_size: u64) -> Self {
+        self.file_size = file_size;
+        self
+    }
+
+    pub fn with_chunk(mut self, chunk: Vec<u8>) -> Self {
+        self.chunk = chunk;
+        self
+    }
+
+    pub fn with_description(mut self, description: String, title: String) -> Self {
+        self.description = description;
+        self.title = title;
+        self
+    }
+
+    pub fn with_thumb(mut self, thumb: Option<Bytes>) -> Self {
+        self.thumb = thumb;
+        self
+    }
 }
 
 /// Response for successful uploading of video using non_resumable method
@@ -15,8 +15,11 @@
 //! For information on different opertaions on facebook page  check  <https://developers.facebook.com/docs/graph-api/reference/page/videos/#Creating>
 
 use crate::prelude::errors::ClientErr;
+use crate::prelude::media_upload::progress::{ProgressCallback, UploadProgress};
 use crate::prelude::utils::PostResponse;
-use crate::prelude::HttpConnection;
+use crate::prelude::{DefaultHttpClient, GenericClientConnection};
+use crate::universal::request_url::RequestUrl;
+use crate::universal::HttpClient;
 #[cfg(any(feature = "reqwest"))]
 use std::fs::File;
 
@@ -25,6 +28,8 @@ use serde::{Deserialize, Serialize};
 #[cfg(any(feature = "web-sys"))]
 use web_sys::{Blob, File, FormData};
 
+#[cfg(feature = "reqwest")]
+use crate::prelude::media_upload::source::MediaSource;
 #[cfg(any(feature = "reqwest"))]
 use crate::prelude::media_upload::video_by_reqwest::VideoApi_reqwest;
 
@@ -158,6 +163,18 @@ impl FinalResponeResumableUpload {
     pub fn response(self) -> FinalResponeResumableUpload {
         self
     }
+
+    pub fn success(&self) -> bool {
+        self.success
+    }
+
+    pub fn video_id(&self) -> &str {
+        &self.video_id
+    }
+
+    pub fn upload_session_id(&self) -> &str {
+        &self.upload_session_id
+    }
 }
 
 /// After complete uploading of the video through resumable, facebook will send
@@ -175,6 +192,7 @@ struct ResumableUploadFinal {
 /// "transfer" is send when uploading is in progress, "finished is send when the
 /// chunk files are finish uploading, and "cancel" is send when for you decide
 /// to stop the uploading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UploadPhase {
     start,
     transfer,
@@ -182,6 +200,31 @@ pub enum UploadPhase {
     cancel,
 }
 
+/// A resumable upload session opened by Facebook. It can be saved, e.g. as
+/// json, to resume an interrupted upload with `resume_post`.
+///
+/// The offsets are those of the next chunk Facebook expects. `resume_post`
+/// keeps them up to date while it sends the file. To save them during the
+/// upload, e.g. in case the application stops, call
+/// [update_offsets](UploadSession::update_offsets) from a progress callback.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct UploadSession {
+    pub video_id: String,
+    pub upload_session_id: String,
+    pub start_offset: u64,
+    pub end_offset: u64,
+}
+
+impl UploadSession {
+    /// Take the offsets of the chunk being sent by a `transfer` request.
+    pub fn update_offsets(&mut self, progress: &UploadProgress) {
+        if progress.phase == UploadPhase::transfer {
+            self.start_offset = progress.start_offset;
+            self.end_offset = progress.end_offset;
+        }
+    }
+}
+
 impl Default for VideoParams {
     /// this default method will create the struct will default
     fn default() -> Self {
@@ -222,19 +265,37 @@ impl VideoParams {
     }
 }
 
-#[derive(Deserialize, Clone, Serialize)]
-pub struct VideoApi {
+#[derive(Clone)]
+pub struct VideoApi<HttpC: HttpClient = DefaultHttpClient> {
     base_url: String,
     page_access_token: String,
+    http_connection: GenericClientConnection<HttpC>,
+    progress: Option<ProgressCallback>,
 }
 
-impl VideoApi {
-    pub fn new(base_url: String, page_access_token: String) -> VideoApi {
+impl<HttpC: HttpClient> VideoApi<HttpC> {
+    pub fn new(
+        base_url: String,
+        page_access_token: String,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> VideoApi<HttpC> {
         VideoApi {
             base_url,
             page_access_token,
+            http_connection,
+            progress: None,
         }
     }
+
+    /// Call `callback` with the [UploadProgress](UploadProgress) of the
+    /// resumable and non-resumable uploads, while the video is sent.
+    pub fn add_progress_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(UploadProgress) + Send + Sync + 'static,
+    {
+        self.progress = Some(ProgressCallback::new(callback));
+        self
+    }
 }
 
 #[derive(Deserialize, Debug, Default, Serialize)]
@@ -244,7 +305,7 @@ struct FeedPostSuccess {
 
 // -------- Handle  web-sys upload request ------
 #[cfg(any(feature = "web-sys"))]
-impl VideoApi {
+impl<HttpC: HttpClient> VideoApi<HttpC> {
     /// This method is used for uploading large video files. It does that by
     /// chunking the file and uploading them individually until is complete.
     ///
@@ -270,7 +331,8 @@ impl VideoApi {
         let base_url = self.base_url.clone();
         let page_token = self.page_access_token.clone();
 
-        VideoApi_seed::new(base_url, page_token)
+        VideoApi_seed::new(base_url, page_token, self.http_connection.clone())
+            .with_progress(self.progress.clone())
             .resumable_post(file, video_param)
             .await
     }
@@ -292,7 +354,8 @@ impl VideoApi {
     ) -> Result<PostResponse, ClientErr> {
         let base_url = self.base_url.clone();
         let page_token = self.page_access_token.clone();
-        VideoApi_seed::new(base_url, page_token)
+        VideoApi_seed::new(base_url, page_token, self.http_connection.clone())
+            .with_progress(self.progress.clone())
             .non_resumable_post(video_params, file)
             .await
     }
@@ -301,18 +364,19 @@ impl VideoApi {
 // -------- Handle request upload ------
 
 #[cfg(any(feature = "reqwest"))]
-impl VideoApi {
+impl<HttpC: HttpClient> VideoApi<HttpC> {
     /// This method is used for uploading large video files, it does that by
     /// chunking the file and uplaoding them individually until is complete.
     /// The method takes two parameter( file, video parameter struct).
     /// the waiting time depend  on the video size uplaoded
     ///
-    /// Note there is an issue with chunking method that only chunk smaller size
-    /// so extra time than usuall is expect until the issue is fixed.
+    /// The chunks are read at the offsets asked by Facebook, and a chunk
+    /// which fails is sent again according to the retry policy of the client.
+    /// To be able to resume the upload after a failure, use
+    /// [start_resumable_post](VideoApi::start_resumable_post) and
+    /// [resume_post](VideoApi::resume_post) instead.
     ///
     /// for more infromation  check  https://developers.facebook.com/docs/video-api/guides/publishing
-
-    //#[cfg(any(feature = "reqwest"))]
     pub async fn resumable_post(
         &self,
         file: File,
@@ -322,11 +386,111 @@ impl VideoApi {
         let base_url = self.base_url.clone();
         let page_token = self.page_access_token.clone();
 
-        VideoApi_reqwest::new(base_url, page_token)
+        VideoApi_reqwest::new(base_url, page_token, self.http_connection.clone())
+            .with_progress(self.progress.clone())
             .resumable_post(video_param, file, thumb_file)
             .await
     }
 
+    /// Open a resumable upload session for a file of `file_size` bytes,
+    /// without sending the file yet. Save the session to resume the upload
+    /// later with [resume_post](VideoApi::resume_post).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use facebook_api_rs::prelude::video::VideoParams;
+    /// use facebook_api_rs::prelude::{Client, UserToken};
+    /// use std::fs::File;
+    ///
+    /// # async fn upload() {
+    /// let video = Client::new(UserToken::default(), "page_token".to_string())
+    ///     .video_upload("page_id".to_string());
+    /// let file = File::open("video.mp4").unwrap();
+    /// let file_size = file.metadata().unwrap().len();
+    ///
+    /// let mut session = video.start_resumable_post(file_size).await.unwrap();
+    /// let result = video
+    ///     .resume_post(file, VideoParams::default(), &mut session, None)
+    ///     .await;
+    /// if result.is_err() {
+    ///     // `session` has the offsets of the chunk which failed: store it
+    ///     // and call `resume_post` with it later.
+    /// }
+    /// # }
+    /// ```
+    pub async fn start_resumable_post(&self, file_size: u64) -> Result<UploadSession, ClientErr> {
+        let base_url = self.base_url.clone();
+        let page_token = self.page_access_token.clone();
+
+        VideoApi_reqwest::new(base_url, page_token, self.http_connection.clone())
+            .with_progress(self.progress.clone())
+            .start_resumable_post(file_size)
+            .await
+    }
+
+    /// Send a file from the offsets of an upload session, then finish the
+    /// upload. The session comes from
+    /// [start_resumable_post](VideoApi::start_resumable_post), and can be one
+    /// saved when an earlier upload failed.
+    ///
+    /// The offsets of the session are updated after every chunk, so when
+    /// the upload fails the session can be saved, or given again, to resume
+    /// from the chunk which failed.
+    pub async fn resume_post(
+        &self,
+        file: File,
+        video_param: VideoParams,
+        session: &mut UploadSession,
+        thumb_file: Option<File>,
+    ) -> Result<FinalResponeResumableUpload, ClientErr> {
+        let base_url = self.base_url.clone();
+        let page_token = self.page_access_token.clone();
+
+        VideoApi_reqwest::new(base_url, page_token, self.http_connection.clone())
+            .with_progress(self.progress.clone())
+            .resume_post(video_param, file, session, thumb_file)
+            .await
+    }
+
+    /// Same as [resumable_post](VideoApi::resumable_post), with a video and
+    /// a thumbnail which are not local files, e.g. bytes downloaded from an
+    /// object storage or a stream of a known size, see
+    /// [MediaSource](MediaSource). Only the chunk being sent is kept in
+    /// memory.
+    pub async fn resumable_post_media(
+        &self,
+        media: impl Into<MediaSource>,
+        video_param: VideoParams,
+        thumb: Option<MediaSource>,
+    ) -> Result<FinalResponeResumableUpload, ClientErr> {
+        let base_url = self.base_url.clone();
+        let page_token = self.page_access_token.clone();
+
+        VideoApi_reqwest::new(base_url, page_token, self.http_connection.clone())
+            .with_progress(self.progress.clone())
+            .resumable_post_media(video_param, media.into(), thumb)
+            .await
+    }
+
+    /// Same as [resume_post](VideoApi::resume_post), with a video and a
+    /// thumbnail which are not local files. A stream is read from its start,
+    /// skipping the bytes before the offsets of the session.
+    pub async fn resume_post_media(
+        &self,
+        media: impl Into<MediaSource>,
+        video_param: VideoParams,
+        session: &mut UploadSession,
+        thumb: Option<MediaSource>,
+    ) -> Result<FinalResponeResumableUpload, ClientErr> {
+        let base_url = self.base_url.clone();
+        let page_token = self.page_access_token.clone();
+
+        VideoApi_reqwest::new(base_url, page_token, self.http_connection.clone())
+            .with_progress(self.progress.clone())
+            .resume_post_media(video_param, media.into(), session, thumb)
+            .await
+    }
+
     /// facebook recommend that you upload files using the Resumable Upload
     /// method because it handles connection interruptions more efficiently
     /// and supports larger files. However, if you prefer to upload files
@@ -346,15 +510,35 @@ impl VideoApi {
         let base_url = self.base_url.clone();
         let page_token = self.page_access_token.clone();
 
-        VideoApi_reqwest::new(base_url, page_token)
+        VideoApi_reqwest::new(base_url, page_token, self.http_connection.clone())
+            .with_progress(self.progress.clone())
             .non_resumable_post(video_params, file, thumb_file)
             .await
     }
+
+    /// Same as [non_resumable_post](VideoApi::non_resumable_post), with a
+    /// video and a thumbnail which are not local files, e.g. a `Vec<u8>`, a
+    /// `Bytes` or a stream of a known size, see [MediaSource](MediaSource).
+    /// The video is read in memory before it is sent.
+    pub async fn non_resumable_post_media(
+        &self,
+        video_params: VideoParams,
+        media: impl Into<MediaSource>,
+        thumb: Option<MediaSource>,
+    ) -> Result<PostResponse, ClientErr> {
+        let base_url = self.base_url.clone();
+        let page_token = self.page_access_token.clone();
+
+        VideoApi_reqwest::new(base_url, page_token, self.http_connection.clone())
+            .with_progress(self.progress.clone())
+            .non_resumable_post_media(video_params, media.into(), thumb)
+            .await
+    }
 }
 
 // -------- Posting by video host url ------
 
-impl VideoApi {
+impl<HttpC: HttpClient> VideoApi<HttpC> {
     /// This Method is used for posting media  by there url.
     ///
     /// useage
@@ -367,17 +551,18 @@ impl VideoApi {
         description: &str,
         title: &str,
     ) -> Result<FinalResponeResumableUpload, ClientErr> {
-        let url = self.base_url.replace("EDGE", "videos")
-            + "?file_url="
-            + file_url
-            + "&access_token="
-            + &self.page_access_token
-            + "&title="
-            + title
-            + &"description="
-            + description;
-
-        let video_id = HttpConnection::post::<FeedPostSuccess, String>(url, "".to_string()).await?;
+        let url = RequestUrl::new(self.base_url.as_str())
+            .edge("videos")
+            .param("file_url", file_url)
+            .non_empty_param("title", title)
+            .non_empty_param("description", description)
+            .access_token(&self.page_access_token)
+            .build();
+
+        let video_id = self
+            .http_connection
+            .post::<FeedPostSuccess, String>(url, "".to_string())
+            .await?;
         if video_id.id.is_empty() {
             Err(ClientErr::FacebookError(
                 "The video posting by url was not suceessfull ".to_string(),
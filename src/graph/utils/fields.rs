@@ -0,0 +1,214 @@
+//! The `fields` parameter of a Graph API request.
+//!
+//! A [FieldSelection](FieldSelection) lists the fields a request returns,
+//! including the fields of the edges and nodes nested in the response, with
+//! their modifiers, e.g. `comments.limit(5){message,from}` or
+//! `picture.type(large)`.
+//!
+//! For more information check <https://developers.facebook.com/docs/graph-api/guides/field-expansion>.
+//!
+//! # Example
+//! ```
+//! use facebook_api_rs::prelude::fields::{Field, FieldSelection};
+//!
+//! let fields = FieldSelection::new()
+//!     .field("id")
+//!     .field("message")
+//!     .field(
+//!         Field::new("comments")
+//!             .modifier("limit", 5)
+//!             .fields(FieldSelection::from_iter(["message", "from"])),
+//!     )
+//!     .field(Field::new("picture").modifier("type", "large"));
+//!
+//! assert_eq!(
+//!     fields.to_string(),
+//!     "id,message,comments.limit(5){message,from},picture.type(large)"
+//! );
+//! ```
+
+use std::fmt;
+use urlencoding::encode;
+
+/// The fields asked to a node or an edge.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldSelection {
+    fields: Vec<Field>,
+}
+
+impl FieldSelection {
+    pub fn new() -> Self {
+        FieldSelection::default()
+    }
+
+    /// Add a field, e.g. `"id"` or a [Field](Field) with modifiers and
+    /// nested fields.
+    pub fn field(mut self, field: impl Into<Field>) -> Self {
+        self.fields.push(field.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// The value of the `fields` parameter, url encoded.
+    pub fn query_value(&self) -> String {
+        encode(&self.to_string()).into_owned()
+    }
+}
+
+impl<F: Into<Field>> FromIterator<F> for FieldSelection {
+    fn from_iter<I: IntoIterator<Item = F>>(fields: I) -> Self {
+        FieldSelection {
+            fields: fields.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl fmt::Display for FieldSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, field) in self.fields.iter().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", field)?;
+        }
+        Ok(())
+    }
+}
+
+/// One field of a [FieldSelection](FieldSelection), with its modifiers,
+/// e.g. `limit(5)`, and the fields of the nested node or edge.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Field {
+    name: String,
+    modifiers: Vec<(String, String)>,
+    fields: FieldSelection,
+}
+
+impl Field {
+    pub fn new(name: impl Into<String>) -> Self {
+        Field {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add a modifier, e.g. `.modifier("limit", 5)` for `limit(5)`.
+    pub fn modifier(mut self, name: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.modifiers.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// The fields of the nested node or edge.
+    pub fn fields(mut self, fields: FieldSelection) -> Self {
+        self.fields = fields;
+        self
+    }
+}
+
+impl From<&str> for Field {
+    fn from(name: &str) -> Self {
+        Field::new(name)
+    }
+}
+
+impl From<String> for Field {
+    fn from(name: String) -> Self {
+        Field::new(name)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name.trim())?;
+        for (name, value) in &self.modifiers {
+            write!(f, ".{}({})", name, value)?;
+        }
+        if !self.fields.is_empty() {
+            write!(f, "{{{}}}", self.fields)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_fields() {
+        let fields = FieldSelection::new().field("id").field(
+            Field::new("comments")
+                .modifier("limit", 5)
+                .modifier("summary", true)
+                .fields(
+                    FieldSelection::new()
+                        .field("message")
+                        .field(Field::new("from").fields(FieldSelection::from_iter(["id"]))),
+                ),
+        );
+
+        assert_eq!(
+            fields.to_string(),
+            "id,comments.limit(5).summary(true){message,from{id}}"
+        );
+        assert_eq!(
+            fields.query_value(),
+            "id%2Ccomments.limit%285%29.summary%28true%29%7Bmessage%2Cfrom%7Bid%7D%7D"
+        );
+        // Stray spaces around a name are not sent.
+        assert_eq!(
+            FieldSelection::from_iter(["timestamp ", " id"]).to_string(),
+            "timestamp,id"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fields_chosen_by_the_caller() {
+        use crate::prelude::mock_server::{MockGraphServer, MockResponse};
+        use serde::Deserialize;
+        use serde_json::json;
+
+        #[derive(Deserialize)]
+        struct Post {
+            id: String,
+            comments: crate::prelude::Data<Comment>,
+        }
+
+        #[derive(Deserialize)]
+        struct Comment {
+            message: String,
+        }
+
+        let server = MockGraphServer::start().unwrap();
+        server.mock_once(
+            "GET",
+            "/1234_5678",
+            MockResponse::ok(json!({
+                "id": "1234_5678",
+                "comments": { "data": [{ "message": "First" }] },
+            })),
+        );
+
+        let fields = FieldSelection::new().field("id").field(
+            Field::new("comments")
+                .modifier("limit", 5)
+                .fields(FieldSelection::from_iter(["message", "from"])),
+        );
+        let post: Post = server
+            .client("page_token".to_string())
+            .post("1234_5678".to_string())
+            .get_with_fields(fields)
+            .await
+            .unwrap();
+
+        assert_eq!(post.id, "1234_5678");
+        assert_eq!(post.comments.data[0].message, "First");
+        assert_eq!(
+            server.requests()[0].param("fields"),
+            Some("id,comments.limit(5){message,from}")
+        );
+    }
+}
@@ -1,8 +1,8 @@
 //use web_sys::Blob;
 //use web_sys::File;
-use std::convert::TryInto;
+use crate::universal::errors::ClientErr;
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Read, Seek, SeekFrom};
 
 //#[derive(Deserialize, Debug, Serialize)]
 #[derive(Debug)]
@@ -18,17 +18,22 @@ pub struct FileResultServer {
 impl FileResultServer {
     /// This method will take the file  and return  a struct of   struc
     /// FileResult {  size_gb: f64,   file_byte: f64,   upload_method: String }
-    pub fn file_analyze(file_path: String) -> FileResultServer {
+    ///
+    /// A file which can not be opened or read is returned as a
+    /// [ClientErr::Io](ClientErr::Io).
+    pub fn file_analyze(file_path: String) -> Result<FileResultServer, ClientErr> {
+        let file = File::open(file_path)?;
+        Ok(FileResultServer::from_file(file)?)
+    }
+
+    /// Same as [file_analyze](FileResultServer::file_analyze), with a file
+    /// which is already open.
+    pub fn from_file(file: File) -> io::Result<FileResultServer> {
         let non_resumable_max_size_gb = 1.0; // Gb: facebook recommmended max 1 Gb for none resumabl upload
         let _resumable_max_size_gb = 4.0; // Gb: facebook recommmended max max of 4  Gb for  resumabl uploading video
-        let file = File::open(file_path).unwrap();
 
-        let file_size_byte = file.metadata().unwrap().len();
-        //f.sync_all()?;
-        println!("avalize {:?}", file_size_byte.clone());
-        // let file_size_byte = file.size() as f64; // file size in byte
+        let file_size_byte = file.metadata()?.len();
         let file_size_gb = file_size_byte as f64 / 10_f64.powf(9.0); // convert the file to Gb
-        println!("avalize size {:?}", file_size_gb.clone());
         let upload_method: String;
         if file_size_gb < non_resumable_max_size_gb {
             upload_method = "non_resumable".to_string();
@@ -36,42 +41,41 @@ impl FileResultServer {
             // this will be for larger videos
             upload_method = "resumable".to_string();
         }
-        println!("avalize  upload type {:?}", upload_method.clone());
 
-        FileResultServer {
+        Ok(FileResultServer {
             file_size_byte: file_size_byte as f64,
             file_size_gb,
             upload_method,
             upload_raw_file: file,
             chunk_upload_size: 0,
-        }
+        })
     }
 
-    pub fn chunk_file(mut self, start: u8) -> [u8; 2] {
-        // start_offset is the result from facebook response
-        // The chunked size of the video to be uploaded  was initially supposed to be
-        // determined by the sized of the  uploaded video but there are some
-        // issue in send ing the request with the form data when large chunk blob file
-        // is append to the FormDat untill this fixed, a pre-defined chunked
-        // size of 5mb will be used  as default.
-
-        let chunk_size: u64 = 120000000; // 15 mb
-
-        let file_size = self.file_size_byte as u8;
-        let mut buffer = [0, 0];
-
-        if start as u64 + chunk_size >= file_size as u64 {
-            buffer = [start, file_size]
-        } else {
-            buffer = [start, chunk_size.try_into().unwrap()]
+    /// Read the chunk of the file asked by Facebook during a resumable
+    /// upload, from `start_offset` included to `end_offset` excluded.
+    pub fn chunk_file(&mut self, start_offset: u64, end_offset: u64) -> io::Result<Vec<u8>> {
+        let chunk_size = end_offset.saturating_sub(start_offset);
+        self.upload_raw_file.seek(SeekFrom::Start(start_offset))?;
+
+        let mut chunk = Vec::with_capacity(chunk_size as usize);
+        (&mut self.upload_raw_file)
+            .take(chunk_size)
+            .read_to_end(&mut chunk)?;
+        if chunk.len() as u64 != chunk_size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "the file ends before the offset {} asked by facebook",
+                    end_offset
+                ),
+            ));
         }
+        self.chunk_upload_size = chunk_size;
+        Ok(chunk)
+    }
 
-        self.upload_raw_file.read_exact(&mut buffer).unwrap();
-
-        // self.chunk_upload_size = chunk_size;
-        //self.chunk_upload_size = test_chunk_size;
-
-        buffer
+    pub fn file_size_byte(&self) -> u64 {
+        self.file_size_byte as u64
     }
 
     pub fn upload_chunking_size(self) -> u64 {
@@ -1,8 +1,13 @@
+#[cfg(feature = "web-sys")]
+use crate::universal::errors::ClientErr;
+#[cfg(feature = "web-sys")]
+use crate::universal::web_sys_client::js_error;
 #[cfg(any(feature = "web-sys"))]
 use web_sys::Blob;
 #[cfg(any(feature = "web-sys"))]
 use web_sys::File;
 
+pub mod fields;
 #[cfg(any(feature = "reqwest"))]
 pub mod file_analyze;
 pub mod structs;
@@ -22,7 +27,7 @@ pub struct FileResult {
 impl FileResult {
     /// This method will take the file  and return  a struct of   struc
     /// FileResult {  size_gb: f64,   file_byte: f64,   upload_method: String }
-    pub fn file_analyze(file: File) -> FileResult {
+    pub fn file_analyze(file: File) -> Result<FileResult, ClientErr> {
         let non_resumable_max_size_gb = 1.0; // Gb: facebook recommmended max 1 Gb for none resumabl upload
         let _resumable_max_size_gb = 4.0; // Gb: facebook recommmended max max of 4  Gb for  resumabl uploading video
 
@@ -36,17 +41,21 @@ impl FileResult {
             // this will be for larger videos
             upload_method = "resumable".to_string();
         }
-        FileResult {
+        Ok(FileResult {
             file_size_byte,
             file_size_gb,
             upload_method,
             upload_raw_file: file,
-            chunked_file: Blob::new().unwrap(),
+            chunked_file: Blob::new().map_err(js_error)?,
             chunk_upload_size: 0,
-        }
+        })
     }
 
-    pub fn chunk_file(mut self, start: f64, current_blob_size: f64) -> FileResult {
+    pub fn chunk_file(
+        mut self,
+        start: f64,
+        current_blob_size: f64,
+    ) -> Result<FileResult, ClientErr> {
         // start_offset is the result from facebook response
         // The chunked size of the video to be uploaded  was initially supposed to be
         // determined by the sized of the  uploaded video but there are some
@@ -83,13 +92,13 @@ impl FileResult {
         let chunked_file = self
             .upload_raw_file
             .slice_with_f64_and_f64(start, current_blob_size)
-            .unwrap();
+            .map_err(js_error)?;
 
         // self.chunk_upload_size = chunk_size;
         self.chunk_upload_size = test_chunk_size;
 
         self.chunked_file = chunked_file;
-        self
+        Ok(self)
     }
 
     pub fn chunked_file(self) -> Blob {
@@ -1,3 +1,4 @@
+use crate::universal::errors::ClientErr;
 use serde::{Deserialize, Serialize};
 
 /// Default Facebook Graph API version
@@ -8,6 +9,20 @@ use serde::{Deserialize, Serialize};
 /// See: <https://developers.facebook.com/docs/graph-api/guides/versioning>
 pub const DEFAULT_API_VERSION: &str = "v23.0";
 
+/// Default Facebook host serving the login dialog.
+///
+/// Use [Config::add_facebook_host] to build the login url against another
+/// host, for example a local stand-in used in tests.
+pub const DEFAULT_FACEBOOK_HOST: &str = "https://www.facebook.com";
+
+fn facebook_oath_url(facebook_host: &str, api_version: &str) -> String {
+    format!(
+        "{}/{}/dialog/oauth?",
+        facebook_host.trim_end_matches('/'),
+        api_version
+    )
+}
+
 /// Validates if an API version string is in the correct format
 ///
 /// # Arguments
@@ -63,7 +78,7 @@ pub struct Config {
 impl Config {
     pub fn new(client_id: String, redirect_uri: String) -> Self {
         Config {
-            facebook_oath_url: "https://www.facebook.com/v23.0/dialog/oauth?".to_owned(),
+            facebook_oath_url: facebook_oath_url(DEFAULT_FACEBOOK_HOST, DEFAULT_API_VERSION),
             client_id,
             redirect_uri,
         }
@@ -81,7 +96,8 @@ impl Config {
     /// Using older versions (e.g., v11.0, v13.0) may result in deprecated endpoints.
     ///
     /// # Panics
-    /// Panics if the API version format is invalid. Use `is_valid_api_version()` to check first.
+    /// Panics if the API version format is invalid. Use `is_valid_api_version()` to check first,
+    /// or [try_new_with_version](Config::try_new_with_version) to get an error instead.
     ///
     /// # Example
     /// ```
@@ -94,19 +110,53 @@ impl Config {
     /// );
     /// ```
     pub fn new_with_version(client_id: String, redirect_uri: String, api_version: String) -> Self {
+        Config::try_new_with_version(client_id, redirect_uri, api_version)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as [new_with_version](Config::new_with_version), but an invalid
+    /// API version is returned as a
+    /// [ClientErr::InvalidApiVersion](ClientErr::InvalidApiVersion) instead
+    /// of panicking.
+    pub fn try_new_with_version(
+        client_id: String,
+        redirect_uri: String,
+        api_version: String,
+    ) -> Result<Self, ClientErr> {
         if !is_valid_api_version(&api_version) {
-            panic!(
-                "Invalid API version format: '{}'. Expected format: 'vXX.X' (e.g., 'v23.0')",
-                api_version
-            );
+            return Err(ClientErr::InvalidApiVersion(api_version));
         }
 
-        let facebook_oath_url = format!("https://www.facebook.com/{}/dialog/oauth?", api_version);
-        Config {
+        let facebook_oath_url = facebook_oath_url(DEFAULT_FACEBOOK_HOST, &api_version);
+        Ok(Config {
             facebook_oath_url,
             client_id,
             redirect_uri,
-        }
+        })
+    }
+
+    /// Build the login url against another Facebook host. The API version of
+    /// the config is kept.
+    ///
+    /// # Example
+    /// ```
+    /// use facebook_api_rs::prelude::Config;
+    ///
+    /// let config = Config::new("app_id".to_string(), "redirect".to_string())
+    ///     .add_facebook_host("http://127.0.0.1:8080".to_string());
+    ///
+    /// assert_eq!(
+    ///     config.facebook_oath_url(),
+    ///     "http://127.0.0.1:8080/v23.0/dialog/oauth?"
+    /// );
+    /// ```
+    pub fn add_facebook_host(mut self, facebook_host: String) -> Self {
+        let api_version = self
+            .api_version()
+            .unwrap_or(DEFAULT_API_VERSION)
+            .to_string();
+        self.facebook_oath_url = facebook_oath_url(&facebook_host, &api_version);
+        self
     }
 
     pub fn facebook_oath_url(&self) -> &str {
@@ -136,8 +186,9 @@ impl Config {
     pub fn api_version(&self) -> Option<&str> {
         // Extract version from URL: https://www.facebook.com/v23.0/dialog/oauth?
         self.facebook_oath_url
-            .split('/')
-            .find(|s| s.starts_with('v') && s.contains('.'))
+            .strip_suffix("/dialog/oauth?")
+            .and_then(|url| url.rsplit('/').next())
+            .filter(|version| is_valid_api_version(version))
     }
 }
 
@@ -176,6 +227,21 @@ mod tests {
         assert!(config.facebook_oath_url().contains("v22.0"));
     }
 
+    #[test]
+    fn test_config_custom_host() {
+        let config = Config::new_with_version(
+            "test_id".to_string(),
+            "http://test".to_string(),
+            "v22.0".to_string(),
+        )
+        .add_facebook_host("http://v1.localhost:8080/".to_string());
+        assert_eq!(
+            config.facebook_oath_url(),
+            "http://v1.localhost:8080/v22.0/dialog/oauth?"
+        );
+        assert_eq!(config.api_version(), Some("v22.0"));
+    }
+
     #[test]
     #[should_panic(expected = "Invalid API version format")]
     fn test_config_invalid_version() {
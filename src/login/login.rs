@@ -7,6 +7,7 @@
 //! //! For details of facebook manual login flow, check [facebook doc](https://developers.facebook.com/docs/facebook-login/guides/advanced/manual-flow#login).
 
 use crate::login::config::Config;
+use crate::universal::request_url::RequestUrl;
 
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
@@ -252,58 +253,32 @@ impl LoginUrlParameters {
     }
 
     fn build_login_url_as_string(&mut self) -> String {
-        let full_url = "".to_string()
-            + &self.facebook_oath_url
-            + "client_id="
-            + &self.client_id
-            + "&redirect_uri="
-            + &self.redirect_uri
-            + "&response_type="
-            + &self.response_type
-            + "&state="
-            + &*self.state
-            + "&scope="
-            + &self.scope_as_string;
-        self.full_url = full_url.clone();
-        full_url
+        self.full_url = self.login_url(None);
+        self.full_url.clone()
     }
 
     fn build_re_request_permission_url(&mut self) -> String {
-        let full_url = "".to_string()
-            + &self.facebook_oath_url
-            + "client_id="
-            + &self.client_id
-            + "&redirect_uri="
-            + &self.redirect_uri
-            + "&response_type="
-            + &self.response_type
-            + "&state="
-            + &*self.state
-            + "&auth_type=rerequest"
-            + "&scope="
-            + &self.scope_as_string;
-        self.full_url = full_url.clone();
-        full_url
+        self.full_url = self.login_url(Some("rerequest"));
+        self.full_url.clone()
     }
 
     /// Re-authentication enables your app to confirm a person's identity even
     /// if it was verified previously.
     fn build_enabling_re_authentication_url(&mut self) -> String {
-        let full_url = "".to_string()
-            + &self.facebook_oath_url
-            + "client_id="
-            + &self.client_id
-            + "&redirect_uri="
-            + &self.redirect_uri
-            + "&response_type="
-            + &self.response_type
-            + "&state="
-            + &*self.state
-            + "&auth_type=reauthenticate"
-            + "&scope="
-            + &self.scope_as_string;
-        self.full_url = full_url.clone();
-        full_url
+        self.full_url = self.login_url(Some("reauthenticate"));
+        self.full_url.clone()
+    }
+
+    /// The login dialog url, with the parameters percent encoded.
+    fn login_url(&self, auth_type: Option<&str>) -> String {
+        RequestUrl::new(self.facebook_oath_url.as_str())
+            .param("client_id", &self.client_id)
+            .param("redirect_uri", &self.redirect_uri)
+            .param("response_type", &self.response_type)
+            .param("state", &self.state)
+            .optional_param("auth_type", auth_type)
+            .param("scope", &self.scope_as_string)
+            .build()
     }
 
     pub fn full_login_url(mut self) -> String {
@@ -400,6 +375,6 @@ mod tests {
         assert_eq!(redirect_url.scope(), &scope);
 
         let full_url = redirect_url.full_login_url();
-        assert_eq!(full_url, "https://www.facebook.com/v23.0/dialog/oauth?client_id=1234567890&redirect_uri=http://localhost:8001&response_type=token&state=0987654321&scope=test")
+        assert_eq!(full_url, "https://www.facebook.com/v23.0/dialog/oauth?client_id=1234567890&redirect_uri=http%3A%2F%2Flocalhost%3A8001&response_type=token&state=0987654321&scope=test")
     }
 }
@@ -7,12 +7,22 @@
 //! temporary, secure access to Facebook APIs. Access tokens are obtained via a
 //! number of methods.
 //! Form more information about token  check  [facebook api Token doc](https://developers.facebook.com/docs/facebook-login/access-tokens/?translation)
+use crate::graph::client::{graph_url, DEFAULT_GRAPH_API_VERSION, DEFAULT_GRAPH_HOST};
 use crate::prelude::errors::ClientErr;
 use crate::prelude::HttpConnection;
+use crate::universal::client::GenericClientConnection;
+use crate::universal::request_url::{graph_path, RequestUrl};
+use crate::universal::secret::{redact, Secret};
+use crate::universal::HttpClient;
+use async_trait::async_trait;
 use chrono::prelude::*;
 use chrono::{DateTime, TimeZone, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 use url::Url;
 
 /// UserToken is Obtain after a successful login to Facebook.
@@ -99,7 +109,7 @@ use url::Url;
 /// // Capture the error
 ///   let user_token =   UserToken::extract_user_tokens(login_response_url).login_error;
 /// ```
-#[derive(Deserialize, Default, Clone, Debug)]
+#[derive(Deserialize, Default, Clone)]
 pub struct UserToken {
     /// Response data is included as URL parameters and contains code parameter
     /// (an encrypted string unique to each login request). This is the default
@@ -153,6 +163,53 @@ pub struct UserToken {
     /// the login. The reason of the error will be available.
     pub login_error: Option<LoginError>,
     url: String,
+    /// The connection of the [Client](crate::prelude::Client) the token comes
+    /// from, see [set_http_connection](UserToken::set_http_connection).
+    #[serde(skip)]
+    http_connection: Option<Arc<dyn TokenConnection>>,
+}
+
+/// The connection sending the requests of a [UserToken](UserToken), without
+/// the type of its http client, so the token can be taken from any
+/// [Client](crate::prelude::Client).
+#[async_trait(?Send)]
+trait TokenConnection {
+    async fn get(&self, url: String) -> Result<Value, ClientErr>;
+
+    async fn post(&self, url: String) -> Result<Value, ClientErr>;
+}
+
+#[async_trait(?Send)]
+impl<HttpC: HttpClient> TokenConnection for GenericClientConnection<HttpC> {
+    async fn get(&self, url: String) -> Result<Value, ClientErr> {
+        GenericClientConnection::get(self, url, String::new()).await
+    }
+
+    async fn post(&self, url: String) -> Result<Value, ClientErr> {
+        GenericClientConnection::post(self, url, String::new()).await
+    }
+}
+
+/// The code and the tokens are not printed.
+impl fmt::Debug for UserToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserToken")
+            .field("code", &Secret::new(self.code.as_str()))
+            .field("access_token", &Secret::new(self.access_token.as_str()))
+            .field(
+                "data_access_expiration_time",
+                &self.data_access_expiration_time,
+            )
+            .field("expires_in", &self.expires_in)
+            .field(
+                "long_lived_token",
+                &Secret::new(self.long_lived_token.as_str()),
+            )
+            .field("state", &self.state)
+            .field("login_error", &self.login_error)
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
 }
 
 impl UserToken {
@@ -167,6 +224,7 @@ impl UserToken {
             state: "".to_string(),
             login_error: None,
             url: "".to_string(),
+            http_connection: None,
         }
     }
     pub fn new_with_url(url: String) -> Self {
@@ -179,6 +237,7 @@ impl UserToken {
             state: "".to_string(),
             login_error: None,
             url,
+            http_connection: None,
         }
     }
 }
@@ -259,22 +318,30 @@ impl UserToken {
     ///
     ///  * Empty url query parameters ->  If no query parameters if found in the
     ///    url, panic will occur.
-
+    ///
+    /// Use [try_extract_user_tokens](UserToken::try_extract_user_tokens) to
+    /// get an error instead.
     pub fn extract_user_tokens(url: String) -> UserToken {
+        UserToken::try_extract_user_tokens(url).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Like [extract_user_tokens](UserToken::extract_user_tokens), but an
+    /// url which can not be parsed, or without query parameters, is returned
+    /// as a [ClientErr::InvalidUrl](ClientErr::InvalidUrl).
+    pub fn try_extract_user_tokens(url: String) -> Result<UserToken, ClientErr> {
         let mut response = UserToken::default();
         let updated_url = url.replace("#", "");
         let query_params: HashMap<_, _> = Url::parse(&updated_url)
-            .unwrap()
+            .map_err(|error| ClientErr::InvalidUrl(redact(&format!("{}: {}", url, error), &[])))?
             .query_pairs()
             .into_owned()
             .collect();
 
         if query_params.is_empty() {
-            //@Todo: replace panic with "warning" instead
-            panic!(
-                "There was no query parameter in uri argument that was passed in. error_message: number of argument found  {:?}.   The url argument : {} ",
-                query_params.len(), url
-            )
+            return Err(ClientErr::InvalidUrl(format!(
+                "There was no query parameter in uri argument that was passed in. The url argument : {}",
+                url
+            )));
         }
 
         let mut login_error = LoginError::default();
@@ -315,7 +382,7 @@ impl UserToken {
         if query_params.contains_key("error") {
             response.login_error = Some(login_error)
         }
-        response
+        Ok(response)
     }
 
     pub async fn exchange_short_live_for_long_live_token(
@@ -325,22 +392,16 @@ impl UserToken {
         client_id: String,
         redirect_uri: String,
     ) -> Result<ExchangeToken, ClientErr> {
-        let url = self.url.replace("NODE/EDGE", "oauth/access_token")
-            + "?client_id="
-            + &client_id
-            + "&client_secret="
-            + &app_secret
-            + "&fb_exchange_token="
-            + &short_live_token
-            + "&redirect_uri="
-            + &redirect_uri
-            + "&grant_type="
-            + "fb_exchange_token";
-
-        println!("url: {url}");
-
-        let access_token = HttpConnection::get::<ExchangeToken>(url, "".to_string()).await?;
-        Ok(access_token)
+        let url = self
+            .oauth_url()
+            .param("client_id", client_id)
+            .param("client_secret", app_secret)
+            .param("fb_exchange_token", short_live_token)
+            .param("redirect_uri", redirect_uri)
+            .param("grant_type", "fb_exchange_token")
+            .build();
+
+        self.post(url).await
     }
 
     pub async fn app_access_token_at_server(
@@ -348,15 +409,13 @@ impl UserToken {
         app_secret: String,
         app_id: String,
     ) -> Result<String, ClientErr> {
-        let base_url = "https://graph.facebook.com/oauth/access_token";
-        let url = format!(
-            "{}?client_id={}
-            &client_secret={}
-            &grant_type=client_credentials",
-            base_url, app_id, app_secret
-        );
-        let access_token = HttpConnection::get::<String>(url, "".to_string()).await?;
-        Ok(access_token)
+        let url = self
+            .oauth_url()
+            .param("client_id", app_id)
+            .param("client_secret", app_secret)
+            .param("grant_type", "client_credentials")
+            .build();
+        self.post(url).await
     }
 
     /// Exchanging Code for an access_token
@@ -366,7 +425,9 @@ impl UserToken {
     /// * `code`-  A string gotten from the extracted from login redirect url
     /// * `app_secret`- The app secret from your [App Dashboard](https://developers.facebook.com/apps)
     /// * `config` - A `Config` struct
-
+    ///
+    /// The app secret and the code are sent in the body of a POST request, so
+    /// they do not appear in the url.
     pub async fn exchange_code_for_access_token_at_server(
         self,
         code: String,
@@ -374,18 +435,15 @@ impl UserToken {
         client_id: String,
         redirect_uri: String,
     ) -> Result<ExchangeToken, ClientErr> {
-        let url = self.url.replace("NODE/EDGE", "oauth/access_token")
-            + "?client_id="
-            + &client_id
-            + "&client_secret="
-            + &app_secret
-            + "&redirect_uri="
-            + &redirect_uri
-            + "&code="
-            + &code;
-
-        let access_token = HttpConnection::get::<ExchangeToken>(url, "".to_string()).await?;
-        Ok(access_token)
+        let url = self
+            .oauth_url()
+            .param("client_id", client_id)
+            .param("client_secret", app_secret)
+            .param("redirect_uri", redirect_uri)
+            .param("code", code)
+            .build();
+
+        self.post(url).await
     }
 
     /// This method will make a get request to facebook api to return
@@ -409,14 +467,25 @@ impl UserToken {
         valid_access_token: String,
         debug_access_token: String,
     ) -> Result<AccessTokenInformation, ClientErr> {
-        let url = "https://graph.facebook.com/debug_token?".to_owned()
-            + "input_token="
-            + &debug_access_token
-            + "&access_token="
-            + &valid_access_token;
-
-        let access_token_response =
-            HttpConnection::get::<TokenResponseInformation>(url, "".to_string()).await?;
+        UserToken::default()
+            .debug_access_token(valid_access_token, debug_access_token)
+            .await
+    }
+
+    /// Same as [access_token_information](UserToken::access_token_information)
+    /// but the request is sent to the Graph API host of this token, see
+    /// [graph_url](UserToken::graph_url).
+    pub async fn debug_access_token(
+        &self,
+        valid_access_token: String,
+        debug_access_token: String,
+    ) -> Result<AccessTokenInformation, ClientErr> {
+        let url = RequestUrl::new(graph_path(&self.graph_url(), &["debug_token"]))
+            .param("input_token", debug_access_token)
+            .access_token(&valid_access_token)
+            .build();
+
+        let access_token_response: TokenResponseInformation = self.get(url).await?;
         let access_token_expiring_date = access_token_response.data.expires_at.to_owned();
         let mut access_token_information = AccessTokenInformation::default();
 
@@ -454,6 +523,53 @@ impl UserToken {
         self.url = url;
         self
     }
+
+    /// The graph url template used for the token requests. When no url was
+    /// set, the default Graph API host and version are used.
+    pub fn graph_url(&self) -> String {
+        if self.url.is_empty() {
+            graph_url(DEFAULT_GRAPH_HOST, DEFAULT_GRAPH_API_VERSION)
+        } else {
+            self.url.clone()
+        }
+    }
+
+    /// Send the token requests through `http_connection`, e.g. the
+    /// [http_connection](crate::prelude::Client::http_connection) of a
+    /// client, so they use its http client, retry policy and app secret.
+    ///
+    /// A token given by [user_token](crate::prelude::Client::user_token)
+    /// already uses the connection of its client. Other tokens use a new
+    /// [HttpConnection](HttpConnection).
+    pub fn set_http_connection<HttpC: HttpClient + 'static>(
+        mut self,
+        http_connection: GenericClientConnection<HttpC>,
+    ) -> Self {
+        self.http_connection = Some(Arc::new(http_connection));
+        self
+    }
+
+    /// The url of the `oauth/access_token` endpoint.
+    fn oauth_url(&self) -> RequestUrl {
+        RequestUrl::new(graph_path(&self.graph_url(), &["oauth", "access_token"]))
+    }
+
+    fn http_connection(&self) -> Result<Arc<dyn TokenConnection>, ClientErr> {
+        match &self.http_connection {
+            Some(http_connection) => Ok(Arc::clone(http_connection)),
+            None => Ok(Arc::new(HttpConnection::try_default()?)),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: String) -> Result<T, ClientErr> {
+        let response = self.http_connection()?.get(url).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    async fn post<T: DeserializeOwned>(&self, url: String) -> Result<T, ClientErr> {
+        let response = self.http_connection()?.post(url).await?;
+        Ok(serde_json::from_value(response)?)
+    }
 }
 
 // /// Extract data from the url fragment and return an `IndexMap`
@@ -568,7 +684,7 @@ pub enum TokenLiveType {
 #[derive(Deserialize, Clone, Debug)]
 pub struct ExchangeToken {
     /// {access-token},
-    access_token: String,
+    access_token: Secret,
     /// {type}
     token_type: String,
     /// {seconds-til-expiration}
@@ -577,7 +693,7 @@ pub struct ExchangeToken {
 
 impl ExchangeToken {
     pub fn access_token(&self) -> &str {
-        &self.access_token
+        self.access_token.expose_secret()
     }
     pub fn token_type(&self) -> &str {
         &self.token_type
@@ -0,0 +1,190 @@
+//! Signing of the requests with an `appsecret_proof`.
+//!
+//! When "Require App Secret" is turned on in the app dashboard, every Graph
+//! API call made with an access token must also send an `appsecret_proof`:
+//! the sha256 HMAC of the access token, keyed with the app secret. Once the
+//! app secret is given to the client with
+//! [add_app_secret](crate::prelude::Client::add_app_secret), every request
+//! carrying an access token is signed, including the items of a batch request.
+//!
+//! For more information check [facebook securing requests](https://developers.facebook.com/docs/graph-api/securing-requests).
+
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use url::form_urlencoded;
+use url::Url;
+
+/// Compute the `appsecret_proof` of an access token, as a lower case hex
+/// string.
+///
+/// # Example
+/// ```
+/// use facebook_api_rs::prelude::app_secret_proof::app_secret_proof;
+///
+/// let proof = app_secret_proof("access_token", "app_secret");
+/// assert_eq!(proof.len(), 64);
+/// ```
+pub fn app_secret_proof(access_token: &str, app_secret: &str) -> String {
+    // An HMAC accepts keys of any length, so this can not fail.
+    let mut mac = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes())
+        .expect("HMAC can take a key of any size");
+    mac.update(access_token.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Add the `appsecret_proof` of the `access_token` parameter of an url, and
+/// of the access tokens of the items when the url is a batch request.
+///
+/// An url without access token, or which is already signed, is left as it is.
+pub fn sign_url(url: &mut Url, app_secret: &str) {
+    if url.query().is_none() {
+        return;
+    }
+
+    let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let is_batch = pairs.iter().any(|(name, _)| name == "batch");
+    if is_batch {
+        for (name, value) in pairs.iter_mut() {
+            if name == "batch" {
+                *value = sign_batch(value, app_secret);
+            }
+        }
+    }
+    let proof = signed_proof(&pairs, app_secret);
+    if proof.is_none() && !is_batch {
+        return;
+    }
+
+    pairs.extend(proof);
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+}
+
+/// Sign the relative url and the body of every item of a batch.
+pub(crate) fn sign_batch(batch: &str, app_secret: &str) -> String {
+    let Ok(Value::Array(mut items)) = serde_json::from_str::<Value>(batch) else {
+        return batch.to_string();
+    };
+
+    for item in items.iter_mut() {
+        if let Some(Value::String(relative_url)) = item.get_mut("relative_url") {
+            let (path, query) = relative_url
+                .split_once('?')
+                .map_or((relative_url.as_str(), None), |(path, query)| {
+                    (path, Some(query))
+                });
+            if let Some(signed) = query.and_then(|query| sign_form(query, app_secret)) {
+                *relative_url = format!("{}?{}", path, signed);
+            }
+        }
+        if let Some(Value::String(body)) = item.get_mut("body") {
+            if let Some(signed) = sign_form(body, app_secret) {
+                *body = signed;
+            }
+        }
+    }
+    Value::Array(items).to_string()
+}
+
+/// Sign an url encoded form, or return `None` if there is nothing to sign.
+fn sign_form(form: &str, app_secret: &str) -> Option<String> {
+    let pairs: Vec<(String, String)> = form_urlencoded::parse(form.as_bytes())
+        .into_owned()
+        .collect();
+    let (name, proof) = signed_proof(&pairs, app_secret)?;
+    let proof = form_urlencoded::Serializer::new(String::new())
+        .append_pair(&name, &proof)
+        .finish();
+    Some(format!("{}&{}", form, proof))
+}
+
+fn signed_proof(pairs: &[(String, String)], app_secret: &str) -> Option<(String, String)> {
+    if pairs.iter().any(|(name, _)| name == "appsecret_proof") {
+        return None;
+    }
+    pairs
+        .iter()
+        .find(|(name, value)| name == "access_token" && !value.is_empty())
+        .map(|(_, token)| {
+            (
+                "appsecret_proof".to_string(),
+                app_secret_proof(token, app_secret),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::mock_server::MockGraphServer;
+    use crate::prelude::{BatchItems, TokenLiveType};
+
+    #[test]
+    fn test_app_secret_proof() {
+        assert_eq!(
+            app_secret_proof("The quick brown fox jumps over the lazy dog", "key"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn test_sign_url() {
+        let proof = app_secret_proof("token", "secret");
+
+        let mut url = Url::parse("https://graph.facebook.com/v23.0/me?access_token=token").unwrap();
+        sign_url(&mut url, "secret");
+        assert_eq!(
+            url.query(),
+            Some(format!("access_token=token&appsecret_proof={}", proof).as_str())
+        );
+
+        // Already signed or without token.
+        let mut signed = url.clone();
+        sign_url(&mut signed, "secret");
+        assert_eq!(signed, url);
+        let mut no_token = Url::parse("https://graph.facebook.com/v23.0/me?fields=id").unwrap();
+        sign_url(&mut no_token, "secret");
+        assert_eq!(no_token.query(), Some("fields=id"));
+    }
+
+    #[tokio::test]
+    async fn test_client_signs_every_request() {
+        let server = MockGraphServer::start().unwrap();
+        let client = || {
+            server
+                .client("token".to_string())
+                .add_app_secret("secret".to_string())
+        };
+        let proof = app_secret_proof("token", "secret");
+
+        client()
+            .accounts(TokenLiveType::LONGLIVE)
+            .pages_by_me()
+            .await
+            .unwrap();
+        let batch_items = BatchItems::default()
+            .add_batch_item_with_access_token("GET", "me", "token".to_string())
+            .add_batch_item_with_body("POST", "access_token=token".to_string(), "1234/feed");
+        client()
+            .batch_request("1234".to_string())
+            .request(batch_items)
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        for request in requests {
+            assert_eq!(
+                request.param("appsecret_proof"),
+                Some(proof.as_str()),
+                "{} {} is not signed",
+                request.method,
+                request.path
+            );
+        }
+    }
+}
@@ -0,0 +1,530 @@
+//! Record and replay of Graph API sessions.
+//!
+//! [CassetteClient](CassetteClient) is an [HttpClient](HttpClient) which
+//! sends the requests through a [ReqwestClient](ReqwestClient) and writes every
+//! request/response pair to a json file, the cassette. The same cassette can
+//! then be replayed without any network access, which makes a captured session
+//! usable as a deterministic test.
+//!
+//! Access tokens, app secrets and the other credentials are scrubbed from the
+//! recorded urls and response bodies before anything is written to disk.
+//!
+//! # Example
+//! ```no_run
+//! use facebook_api_rs::prelude::cassette::CassetteClient;
+//! use facebook_api_rs::prelude::reqwest::ReqwestClient;
+//! use facebook_api_rs::prelude::{Client, HttpClient, UserToken};
+//!
+//! // Capture a real session once.
+//! let recorder = CassetteClient::record(ReqwestClient::new(None).unwrap(), "tests/pages.json");
+//! let client = Client::new(UserToken::default(), "page_token".to_string())
+//!     .add_http_client(recorder);
+//!
+//! // Replay it later, e.g. in CI.
+//! let player = CassetteClient::replay("tests/pages.json").unwrap();
+//! let client = Client::new(UserToken::default(), "page_token".to_string())
+//!     .add_http_client(player);
+//! ```
+
+use crate::prelude::utils::UploadingData;
+use crate::prelude::video::VideoParams;
+use crate::universal::errors::ClientErr;
+use crate::universal::form_data::MultipartData;
+use crate::universal::reqwest::ReqwestClient;
+use crate::universal::secret::replace_parameters;
+use crate::universal::HttpClient;
+use async_trait::async_trait;
+use http::header::CONTENT_TYPE;
+use http::{HeaderMap, Request, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use url::{form_urlencoded, Url};
+
+/// The value written in place of a scrubbed credential.
+pub const SCRUBBED: &str = "SCRUBBED";
+
+pub use crate::universal::secret::SENSITIVE_PARAMETERS;
+
+/// Whether a [CassetteClient](CassetteClient) sends the requests or answers
+/// them from its cassette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    Record,
+    Replay,
+}
+
+/// A recorded request.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// The url, with the credentials scrubbed.
+    pub url: String,
+    /// The body, or a short description of it for multipart uploads.
+    pub body: String,
+}
+
+/// A recorded response.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    /// The body, with the credentials scrubbed.
+    pub body: String,
+}
+
+/// A request and the response it got.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// The content of a cassette file.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Default)]
+struct CassetteState {
+    cassette: Cassette,
+    /// Which interactions were already replayed.
+    replayed: Vec<bool>,
+}
+
+/// An [HttpClient](HttpClient) recording the requests sent through a
+/// [ReqwestClient](ReqwestClient) to a cassette, or replaying them from it.
+///
+/// Every clone shares the same cassette.
+#[derive(Debug, Clone)]
+pub struct CassetteClient {
+    inner: ReqwestClient,
+    mode: CassetteMode,
+    path: Option<PathBuf>,
+    state: Arc<Mutex<CassetteState>>,
+}
+
+impl CassetteClient {
+    /// Send the requests through `inner` and write them to the cassette at
+    /// `path` after every response. An existing file is replaced.
+    pub fn record<P: Into<PathBuf>>(inner: ReqwestClient, path: P) -> CassetteClient {
+        CassetteClient {
+            inner,
+            mode: CassetteMode::Record,
+            path: Some(path.into()),
+            state: Arc::default(),
+        }
+    }
+
+    /// Answer the requests from the cassette at `path`.
+    ///
+    /// A request is answered by the first interaction not replayed yet with
+    /// the same method and url, so a cassette replays a session in which the
+    /// same request was sent several times.
+    pub fn replay<P: AsRef<Path>>(path: P) -> Result<CassetteClient, ClientErr> {
+        let content = fs::read_to_string(path.as_ref()).map_err(|e| {
+            ClientErr::HttpClient(format!(
+                "failed to read the cassette {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        let cassette: Cassette = serde_json::from_str(&content)?;
+        Ok(CassetteClient::from_cassette(cassette))
+    }
+
+    /// Answer the requests from a cassette which is already loaded.
+    pub fn from_cassette(cassette: Cassette) -> CassetteClient {
+        let replayed = vec![false; cassette.interactions.len()];
+        CassetteClient {
+            inner: ReqwestClient::from_client(::reqwest::Client::new()),
+            mode: CassetteMode::Replay,
+            path: None,
+            state: Arc::new(Mutex::new(CassetteState { cassette, replayed })),
+        }
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    /// The interactions recorded, or loaded for the replay.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state().cassette.interactions.clone()
+    }
+
+    /// Write the cassette to `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ClientErr> {
+        let content = serde_json::to_string_pretty(&self.state().cassette)?;
+        fs::write(path.as_ref(), content).map_err(|e| {
+            ClientErr::HttpClient(format!(
+                "failed to write the cassette {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, CassetteState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Record the response, or answer from the cassette in replay mode.
+    async fn exchange<F>(
+        &self,
+        method: &http::Method,
+        url: &str,
+        body: String,
+        send: F,
+    ) -> Result<Response<String>, ClientErr>
+    where
+        F: std::future::Future<Output = Result<Response<String>, ClientErr>>,
+    {
+        let request = RecordedRequest {
+            method: method.to_string(),
+            url: scrub_url(url),
+            body: scrub_text(&body),
+        };
+
+        match self.mode {
+            CassetteMode::Replay => self.replay_response(&request),
+            CassetteMode::Record => {
+                let response = send.await?;
+                self.record_interaction(request, &response)?;
+                Ok(response)
+            }
+        }
+    }
+
+    fn replay_response(&self, request: &RecordedRequest) -> Result<Response<String>, ClientErr> {
+        let mut state = self.state();
+        let CassetteState { cassette, replayed } = &mut *state;
+
+        let index = cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .position(|(index, interaction)| {
+                !replayed[index]
+                    && interaction.request.method == request.method
+                    && interaction.request.url == request.url
+            })
+            .ok_or_else(|| {
+                ClientErr::HttpClient(format!(
+                    "no recorded interaction left for {} {}",
+                    request.method, request.url
+                ))
+            })?;
+        replayed[index] = true;
+
+        let recorded = &cassette.interactions[index].response;
+        let mut build = Response::builder().status(recorded.status);
+        for (name, value) in &recorded.headers {
+            build = build.header(name, value);
+        }
+        build
+            .body(recorded.body.clone())
+            .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))
+    }
+
+    fn record_interaction(
+        &self,
+        request: RecordedRequest,
+        response: &Response<String>,
+    ) -> Result<(), ClientErr> {
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.to_string(), value.to_string()))
+            })
+            .collect();
+        let interaction = Interaction {
+            request,
+            response: RecordedResponse {
+                status: response.status().as_u16(),
+                headers,
+                body: scrub_text(response.body()),
+            },
+        };
+
+        self.state().cassette.interactions.push(interaction);
+        match &self.path {
+            Some(path) => self.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl HttpClient for CassetteClient {
+    /// Create a client recording in memory. Use
+    /// [save](CassetteClient::save) to write the cassette.
+    fn new<U: Into<Option<HeaderMap>>>(headers: U) -> Result<Self, ClientErr> {
+        Ok(CassetteClient {
+            inner: ReqwestClient::new(headers)?,
+            mode: CassetteMode::Record,
+            path: None,
+            state: Arc::default(),
+        })
+    }
+
+    /// Replayed responses are not waited for, even when they ask for a
+    /// retry.
+    async fn sleep(&self, duration: Duration) {
+        if self.mode == CassetteMode::Record {
+            self.inner.sleep(duration).await
+        }
+    }
+
+    async fn request(&self, request: Request<String>) -> Result<Response<String>, ClientErr> {
+        let method = request.method().clone();
+        let url = request.uri().to_string();
+        let is_form = request
+            .headers()
+            .get(CONTENT_TYPE)
+            .is_some_and(|content_type| content_type == "application/x-www-form-urlencoded");
+        let body = if is_form {
+            scrub_form(request.body())
+        } else {
+            request.body().clone()
+        };
+        self.exchange(&method, &url, body, self.inner.request(request))
+            .await
+    }
+
+    async fn video_request(
+        &self,
+        request: Request<VideoParams>,
+    ) -> Result<Response<String>, ClientErr> {
+        let method = request.method().clone();
+        let url = request.uri().to_string();
+        let params = request.body();
+        let body = format!(
+            "multipart/form-data; title={}; description={}",
+            params.title, params.description
+        );
+        self.exchange(&method, &url, body, self.inner.video_request(request))
+            .await
+    }
+
+    async fn resumable_video_request(
+        &self,
+        request: Request<UploadingData>,
+    ) -> Result<Response<String>, ClientErr> {
+        let method = request.method().clone();
+        let url = request.uri().to_string();
+        let body = serde_json::to_string(request.body())?;
+        self.exchange(
+            &method,
+            &url,
+            body,
+            self.inner.resumable_video_request(request),
+        )
+        .await
+    }
+
+    async fn upload_by_form_data_request(
+        &self,
+        request: Request<(Vec<u8>, VideoParams)>,
+    ) -> Result<Response<String>, ClientErr> {
+        let method = request.method().clone();
+        let url = request.uri().to_string();
+        let (bytes, params) = request.body();
+        let body = format!(
+            "multipart/form-data; title={}; description={}; source={} bytes",
+            params.title,
+            params.description,
+            bytes.len()
+        );
+        self.exchange(
+            &method,
+            &url,
+            body,
+            self.inner.upload_by_form_data_request(request),
+        )
+        .await
+    }
+
+    async fn multipart_request(
+        &self,
+        request: Request<MultipartData>,
+    ) -> Result<Response<String>, ClientErr> {
+        let method = request.method().clone();
+        let url = request.uri().to_string();
+        let mut body = "multipart/form-data".to_string();
+        for (name, value) in request.body().fields() {
+            body = body + "; " + name + "=" + value;
+        }
+        for file in request.body().files() {
+            body = format!("{}; {}={} bytes", body, file.name, file.bytes.len());
+        }
+        self.exchange(&method, &url, body, self.inner.multipart_request(request))
+            .await
+    }
+}
+
+/// Replace the values of the [sensitive parameters](SENSITIVE_PARAMETERS) of
+/// an url, including the ones inside other parameters such as the relative
+/// urls of a batch request.
+pub fn scrub_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return scrub_text(url);
+    };
+    if parsed.query().is_none() {
+        return url.to_string();
+    }
+
+    let query = scrub_form(parsed.query().unwrap_or_default());
+    parsed.set_query(Some(&query));
+    parsed.to_string()
+}
+
+/// Replace the values of the [sensitive parameters](SENSITIVE_PARAMETERS) of
+/// an url encoded form, e.g. the query of an url or the body of a request.
+pub fn scrub_form(form: &str) -> String {
+    let pairs = form_urlencoded::parse(form.as_bytes()).map(|(name, value)| {
+        let value = if SENSITIVE_PARAMETERS.contains(&name.as_ref()) {
+            SCRUBBED.to_string()
+        } else {
+            scrub_text(&value)
+        };
+        (name.into_owned(), value)
+    });
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish()
+}
+
+/// Replace the values of the [sensitive parameters](SENSITIVE_PARAMETERS)
+/// found in a json document or in `name=value` pairs of a text.
+pub fn scrub_text(text: &str) -> String {
+    if let Ok(mut json) = serde_json::from_str::<Value>(text) {
+        if json.is_object() || json.is_array() {
+            scrub_json(&mut json);
+            return json.to_string();
+        }
+    }
+
+    replace_parameters(text, SCRUBBED)
+}
+
+fn scrub_json(json: &mut Value) {
+    match json {
+        Value::Object(map) => {
+            for (name, value) in map.iter_mut() {
+                if SENSITIVE_PARAMETERS.contains(&name.as_str()) && value.is_string() {
+                    *value = Value::String(SCRUBBED.to_string());
+                } else {
+                    scrub_json(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(scrub_json),
+        Value::String(text) => *text = scrub_text(text),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::mock_server::MockGraphServer;
+    use crate::prelude::{BatchItems, Client, TokenLiveType, UserToken};
+
+    #[test]
+    fn test_scrub_url() {
+        let url = "https://graph.facebook.com/v23.0/me/accounts?access_token=secret&fields=id";
+        assert_eq!(
+            scrub_url(url),
+            "https://graph.facebook.com/v23.0/me/accounts?access_token=SCRUBBED&fields=id"
+        );
+
+        let batch = r#"https://graph.facebook.com/v23.0?batch=[{"method":"GET","relative_url":"me?access_token=secret"}]"#;
+        assert!(!scrub_url(batch).contains("secret"));
+    }
+
+    #[test]
+    fn test_scrub_text() {
+        let body = r#"{"data":[{"id":"1","access_token":"secret"}],"paging":{"next":"https://x/?access_token=secret&after=1"}}"#;
+        let scrubbed = scrub_text(body);
+        assert!(!scrubbed.contains("secret"));
+        assert!(scrubbed.contains("after=1"));
+
+        assert_eq!(
+            scrub_text("my_access_token=keep&code=secret"),
+            "my_access_token=keep&code=SCRUBBED"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!(
+            "facebook_api_rs_cassette_{}.json",
+            std::process::id()
+        ));
+        let server = MockGraphServer::start().unwrap();
+        let graph_host = server.url();
+        let client = |http_client: &CassetteClient| {
+            Client::new(UserToken::default(), "secret".to_string())
+                .add_graph_host(graph_host.clone())
+                .add_http_client(http_client.clone())
+        };
+        let batch_items =
+            || BatchItems::default().add_batch_item_with_access_token("GET", "me", "secret".into());
+
+        let recorder = CassetteClient::record(ReqwestClient::new(None).unwrap(), &path);
+        let recorded_accounts = client(&recorder)
+            .accounts(TokenLiveType::LONGLIVE)
+            .pages_by_me()
+            .await
+            .unwrap();
+        let recorded_batch = client(&recorder)
+            .batch_request("1234".to_string())
+            .request(batch_items())
+            .await
+            .unwrap();
+        drop(server);
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("secret"));
+        assert!(!content.contains("mock_page_access_token"));
+
+        let player = CassetteClient::replay(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let replayed_accounts = client(&player)
+            .accounts(TokenLiveType::LONGLIVE)
+            .pages_by_me()
+            .await
+            .unwrap();
+        let replayed_batch = client(&player)
+            .batch_request("1234".to_string())
+            .request(batch_items())
+            .await
+            .unwrap();
+
+        assert_eq!(replayed_accounts.data.len(), recorded_accounts.data.len());
+        assert_eq!(
+            replayed_batch[0].as_ref().unwrap().code,
+            recorded_batch[0].as_ref().unwrap().code
+        );
+
+        // Every interaction was replayed.
+        let error = client(&player)
+            .accounts(TokenLiveType::LONGLIVE)
+            .pages_by_me()
+            .await
+            .err();
+        assert!(matches!(error, Some(ClientErr::HttpClient(_))));
+    }
+}
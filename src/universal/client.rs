@@ -1,102 +1,478 @@
+use chrono::{DateTime, Utc};
 use serde::de::DeserializeOwned;
 
-use std::sync::Arc;
+use http::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use http::{Method, Uri};
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
 
+use crate::prelude::media_upload::progress::RequestProgress;
 use crate::prelude::video::VideoParams;
+#[cfg(feature = "reqwest")]
+use crate::universal::app_secret_proof::sign_batch;
+use crate::universal::app_secret_proof::sign_url;
 use crate::universal::errors::ClientErr;
 #[cfg(any(feature = "reqwest"))]
+use crate::universal::form_data::MultipartData;
+#[cfg(feature = "reqwest")]
 use crate::universal::reqwest::ReqwestClient;
+use crate::universal::retry::RetryPolicy;
+use crate::universal::secret::{redact, Secret};
+use crate::universal::usage::{RateLimitUsage, Throttle};
 #[cfg(any(feature = "web-sys"))]
 use crate::universal::web_sys_client::Web_sysClient;
 use crate::universal::HttpClient;
-use url::Url;
 
 use crate::prelude::response::{deserialize_batch_handler, deserialize_response_handler};
 use crate::prelude::utils::UploadingData;
 #[cfg(any(feature = "web-sys"))]
 use web_sys::FormData;
 
+/// The [HttpClient](HttpClient) used when no other client is given to the
+/// [Client](crate::prelude::Client).
 #[cfg(any(feature = "reqwest"))]
-pub type HttpConnection = GenericClientConnection<ReqwestClient>;
+pub type DefaultHttpClient = ReqwestClient;
 
+/// The [HttpClient](HttpClient) used when no other client is given to the
+/// [Client](crate::prelude::Client).
 #[cfg(any(feature = "web-sys"))]
-pub type HttpConnection = GenericClientConnection<Web_sysClient>;
+pub type DefaultHttpClient = Web_sysClient;
 
-#[derive(Debug, Clone)]
+pub type HttpConnection = GenericClientConnection<DefaultHttpClient>;
+
+/// A connection which sends every request through one shared
+/// [HttpClient](HttpClient).
+///
+/// Cloning the connection is cheap and all clones reuse the same http client,
+/// so the pooled connections, default headers, timeouts and proxies
+/// configured on it apply to the whole session.
+///
+/// Requests failing with a transient error are sent again according to the
+/// [RetryPolicy](RetryPolicy) of the connection. The rate-limit usage of a
+/// response is returned with it by the `*_with_usage` methods, e.g.
+/// [get_with_usage](GenericClientConnection::get_with_usage), or by
+/// [Client::with_usage](crate::prelude::Client::with_usage) for the wrapped
+/// APIs, and the usage
+/// of the last response is kept by all the clones, see
+/// [rate_limit_usage](GenericClientConnection::rate_limit_usage).
+#[derive(Debug)]
 pub struct GenericClientConnection<HttpC: HttpClient> {
     http_client: Arc<HttpC>,
-    url: Url,
+    retry_policy: RetryPolicy,
+    throttle: Option<Throttle>,
+    rate_limit_usage: Arc<Mutex<Option<ReceivedUsage>>>,
+    /// The usage of the last response of this connection only, see
+    /// [Client::with_usage](crate::prelude::Client::with_usage).
+    response_usage: Option<Arc<Mutex<Option<RateLimitUsage>>>>,
+    app_secret: Option<Secret>,
+    request_progress: Option<RequestProgress>,
+}
+
+impl<HttpC: HttpClient> Clone for GenericClientConnection<HttpC> {
+    fn clone(&self) -> Self {
+        GenericClientConnection {
+            http_client: Arc::clone(&self.http_client),
+            retry_policy: self.retry_policy.clone(),
+            throttle: self.throttle.clone(),
+            rate_limit_usage: Arc::clone(&self.rate_limit_usage),
+            response_usage: self.response_usage.clone(),
+            app_secret: self.app_secret.clone(),
+            request_progress: self.request_progress.clone(),
+        }
+    }
+}
+
+impl<HttpC: HttpClient> Default for GenericClientConnection<HttpC> {
+    /// Create a connection with an http client that has no default headers.
+    ///
+    /// # Panics
+    /// Panics if the http client can not be built. Use
+    /// [try_default](GenericClientConnection::try_default) to get an error
+    /// instead.
+    fn default() -> Self {
+        GenericClientConnection::try_default().unwrap_or_else(|error| panic!("{}", error))
+    }
 }
 
 impl<HttpC: HttpClient> GenericClientConnection<HttpC> {
-    pub async fn get<T>(build_url: String, body: String) -> Result<T, ClientErr>
+    /// Same as [default](GenericClientConnection::default), but an http client
+    /// which can not be built is returned as an error instead of panicking.
+    pub fn try_default() -> Result<Self, ClientErr> {
+        Ok(GenericClientConnection::new(HttpC::new(None)?))
+    }
+
+    /// Create a connection from an http client, or from an `Arc` of an http
+    /// client which is already shared elsewhere.
+    pub fn new<C: Into<Arc<HttpC>>>(http_client: C) -> Self {
+        GenericClientConnection {
+            http_client: http_client.into(),
+            retry_policy: RetryPolicy::default(),
+            throttle: None,
+            rate_limit_usage: Arc::default(),
+            response_usage: None,
+            app_secret: None,
+            request_progress: None,
+        }
+    }
+
+    /// A connection with the same settings, e.g. the retry policy, sending
+    /// the requests through another http client.
+    pub fn with_http_client<C: HttpClient>(
+        &self,
+        http_client: Arc<C>,
+    ) -> GenericClientConnection<C> {
+        GenericClientConnection {
+            http_client,
+            retry_policy: self.retry_policy.clone(),
+            throttle: self.throttle.clone(),
+            rate_limit_usage: Arc::clone(&self.rate_limit_usage),
+            response_usage: self.response_usage.clone(),
+            app_secret: self.app_secret.clone(),
+            request_progress: self.request_progress.clone(),
+        }
+    }
+
+    /// Sign every request carrying an access token with the
+    /// `appsecret_proof` of the token, see
+    /// [app_secret_proof](crate::prelude::app_secret_proof).
+    pub fn with_app_secret(mut self, app_secret: String) -> Self {
+        self.app_secret = Some(Secret::new(app_secret));
+        self
+    }
+
+    /// Set how the requests failing with a transient error are retried.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Delay the requests when the usage of the rate limits gets near 100%.
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Report the progress of the bodies sent by this connection, see
+    /// [RequestProgress](RequestProgress). It is only reported by the
+    /// requests uploading a file.
+    pub fn with_request_progress(mut self, request_progress: RequestProgress) -> Self {
+        self.request_progress = Some(request_progress);
+        self
+    }
+
+    /// The http client shared by this connection.
+    pub fn http_client(&self) -> &Arc<HttpC> {
+        &self.http_client
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    pub fn throttle(&self) -> Option<&Throttle> {
+        self.throttle.as_ref()
+    }
+
+    /// The rate-limit usage reported by the last response which had usage
+    /// headers, if any.
+    ///
+    /// The usage is shared by every clone of the connection, so with
+    /// concurrent requests it may come from another request, or from the
+    /// page of another access token. Use the `*_with_usage` methods, e.g.
+    /// [get_with_usage](GenericClientConnection::get_with_usage), to get the
+    /// usage of a given response.
+    pub fn rate_limit_usage(&self) -> Option<RateLimitUsage> {
+        self.received_usage().map(|received| received.usage)
+    }
+
+    /// A clone of the connection which also keeps the usage of its own
+    /// responses, apart from the usage shared by every clone.
+    pub(crate) fn recording_usage(&self) -> (Self, Arc<Mutex<Option<RateLimitUsage>>>) {
+        let response_usage = Arc::new(Mutex::new(None));
+        let mut connection = self.clone();
+        connection.response_usage = Some(Arc::clone(&response_usage));
+        (connection, response_usage)
+    }
+
+    fn received_usage(&self) -> Option<ReceivedUsage> {
+        self.rate_limit_usage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Parse and sign the url of a request, and take its access token out
+    /// of the query to send it in the `Authorization` header instead.
+    fn prepare(&self, method: Method, build_url: &str) -> Result<PreparedRequest, ClientErr> {
+        let mut url: Url = build_url
+            .parse()
+            .map_err(|error| invalid_url(build_url, error))?;
+        if let Some(app_secret) = &self.app_secret {
+            sign_url(&mut url, app_secret.expose_secret());
+        }
+        let authorization = take_access_token(&mut url);
+        Ok(PreparedRequest {
+            method,
+            uri: parse_uri(&url)?,
+            url,
+            authorization,
+            form: false,
+            progress: self.request_progress.clone(),
+        })
+    }
+
+    /// Same as [prepare](GenericClientConnection::prepare), but the
+    /// parameters left in the query are sent as an url encoded body when the
+    /// request has no body, so credentials such as the tokens of the items of
+    /// a batch do not appear in the url.
+    fn prepare_form(
+        &self,
+        method: Method,
+        build_url: &str,
+        body: &mut String,
+    ) -> Result<PreparedRequest, ClientErr> {
+        let mut prepared = self.prepare(method, build_url)?;
+        if body.is_empty() {
+            if let Some(query) = prepared.url.query() {
+                *body = query.to_string();
+                prepared.url.set_query(None);
+                prepared.uri = parse_uri(&prepared.url)?;
+                prepared.form = true;
+            }
+        }
+        Ok(prepared)
+    }
+
+    /// Remove the access token and the app secret from an error, so it can be
+    /// logged.
+    fn redact_error(&self, prepared: &PreparedRequest, error: ClientErr) -> ClientErr {
+        let access_token = prepared
+            .authorization
+            .as_ref()
+            .map(|(token, _)| token.expose_secret())
+            .unwrap_or_default();
+        let app_secret = self
+            .app_secret
+            .as_ref()
+            .map(Secret::expose_secret)
+            .unwrap_or_default();
+        error.redact(&[access_token, app_secret])
+    }
+
+    fn update_rate_limit_usage(&self, response: &http::Response<String>) {
+        let usage = RateLimitUsage::from_headers(response.headers());
+        if let Some(response_usage) = &self.response_usage {
+            *response_usage
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(usage.clone());
+        }
+        if !usage.is_empty() {
+            *self
+                .rate_limit_usage
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(ReceivedUsage {
+                usage,
+                received_at: Utc::now(),
+            });
+        }
+    }
+
+    /// The wait asked by the throttle before sending a request.
+    fn throttle_delay(&self) -> Option<Duration> {
+        let throttle = self.throttle.as_ref()?;
+        let received = self.received_usage()?;
+        let elapsed = (Utc::now() - received.received_at)
+            .to_std()
+            .unwrap_or_default();
+        throttle.delay(&received.usage, elapsed)
+    }
+
+    /// Send a request, and send it again while it fails with a transient
+    /// error and the retry policy allows it. The first attempt is delayed by
+    /// the throttle when the usage of the rate limits is high, the retries
+    /// wait as long as the retry policy asks.
+    async fn send_with_retry<F, Fut>(
+        &self,
+        method: &Method,
+        send: F,
+    ) -> Result<http::Response<String>, ClientErr>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<http::Response<String>, ClientErr>>,
+    {
+        if let Some(delay) = self.throttle_delay() {
+            self.http_client.sleep(delay).await;
+        }
+
+        let mut attempt = 0;
+        loop {
+            let response = send().await;
+            if let Ok(response) = &response {
+                self.update_rate_limit_usage(response);
+            }
+            match self.retry_policy.retry_delay(attempt, method, &response) {
+                Some(delay) => {
+                    self.http_client.sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return response,
+            }
+        }
+    }
+
+    pub async fn get<T>(&self, build_url: String, body: String) -> Result<T, ClientErr>
     where
         Self: Sized,
         T: DeserializeOwned, // response Type
     {
-        let client = HttpC::new(None)?;
-        let response = client.get(build_url.parse().unwrap(), body).await;
-        Ok(deserialize_response_handler::<T>(response)?)
+        let (response, _) = self.get_with_usage(build_url, body).await?;
+        Ok(response)
+    }
+
+    /// Same as [get](GenericClientConnection::get), with the rate-limit
+    /// usage reported by the response. The usage of an error is available
+    /// from its [response](ClientErr::response).
+    pub async fn get_with_usage<T>(
+        &self,
+        build_url: String,
+        body: String,
+    ) -> Result<(T, RateLimitUsage), ClientErr>
+    where
+        Self: Sized,
+        T: DeserializeOwned, // response Type
+    {
+        let prepared = self.prepare(Method::GET, &build_url)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client.request(prepared.request(body.clone()))
+            })
+            .await;
+        with_usage(response, deserialize_response_handler::<T>)
+            .map_err(|error| self.redact_error(&prepared, error))
     }
 
-    pub async fn post<R, T>(build_url: String, body: T) -> Result<R, ClientErr>
+    pub async fn post<R, T>(&self, build_url: String, body: T) -> Result<R, ClientErr>
     where
         Self: Sized,
         R: DeserializeOwned, // response Type
         T: Into<String> + Send,
     {
-        let client = HttpC::new(None)?;
-        let response = client.post(build_url.parse().unwrap(), body).await;
-        Ok(deserialize_response_handler::<R>(response)?)
+        let (response, _) = self.post_with_usage(build_url, body).await?;
+        Ok(response)
     }
 
-    pub async fn batch_post<R, T>(build_url: String, body: T) -> Result<R, ClientErr>
+    /// Same as [post](GenericClientConnection::post), with the rate-limit
+    /// usage reported by the response.
+    pub async fn post_with_usage<R, T>(
+        &self,
+        build_url: String,
+        body: T,
+    ) -> Result<(R, RateLimitUsage), ClientErr>
     where
         Self: Sized,
         R: DeserializeOwned, // response Type
         T: Into<String> + Send,
     {
-        let client = HttpC::new(None)?;
-        let response = client.post(build_url.parse().unwrap(), body).await;
-        Ok(deserialize_batch_handler::<R>(response)?)
+        let mut body: String = body.into();
+        let prepared = self.prepare_form(Method::POST, &build_url, &mut body)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client.request(prepared.request(body.clone()))
+            })
+            .await;
+        with_usage(response, deserialize_response_handler::<R>)
+            .map_err(|error| self.redact_error(&prepared, error))
+    }
+
+    pub async fn batch_post<R, T>(&self, build_url: String, body: T) -> Result<R, ClientErr>
+    where
+        Self: Sized,
+        R: DeserializeOwned, // response Type
+        T: Into<String> + Send,
+    {
+        let mut body: String = body.into();
+        let prepared = self.prepare_form(Method::POST, &build_url, &mut body)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client.request(prepared.request(body.clone()))
+            })
+            .await;
+        deserialize_batch_handler::<R>(response)
+            .map_err(|error| self.redact_error(&prepared, error))
     }
 
-    pub async fn delete<T>(build_url: String, body: String) -> Result<T, ClientErr>
+    pub async fn delete<T>(&self, build_url: String, body: String) -> Result<T, ClientErr>
     where
         Self: Sized,
         T: DeserializeOwned, // response Type
     {
-        let client = HttpC::new(None)?;
-        let response = client.delete(build_url.parse().unwrap(), body).await;
-        Ok(deserialize_response_handler::<T>(response)?)
+        let (response, _) = self.delete_with_usage(build_url, body).await?;
+        Ok(response)
+    }
+
+    /// Same as [delete](GenericClientConnection::delete), with the
+    /// rate-limit usage reported by the response.
+    pub async fn delete_with_usage<T>(
+        &self,
+        build_url: String,
+        body: String,
+    ) -> Result<(T, RateLimitUsage), ClientErr>
+    where
+        Self: Sized,
+        T: DeserializeOwned, // response Type
+    {
+        let prepared = self.prepare(Method::DELETE, &build_url)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client.request(prepared.request(body.clone()))
+            })
+            .await;
+        with_usage(response, deserialize_response_handler::<T>)
+            .map_err(|error| self.redact_error(&prepared, error))
     }
 
     #[cfg(any(feature = "reqwest"))]
-    pub async fn video_post<T>(build_url: String, body: VideoParams) -> Result<T, ClientErr>
+    pub async fn video_post<T>(&self, build_url: String, body: VideoParams) -> Result<T, ClientErr>
     where
         Self: Sized,
         T: DeserializeOwned, /* response Type
                               * T: Send + DeserializeOwned, */
     {
-        let client = HttpC::new(None)?;
-        let response = client.video_post(build_url.parse().unwrap(), body).await;
-        Ok(deserialize_response_handler::<T>(response)?)
+        let prepared = self.prepare(Method::POST, &build_url)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client
+                    .video_request(prepared.request(body.clone()))
+            })
+            .await;
+        deserialize_response_handler::<T>(response)
+            .map_err(|error| self.redact_error(&prepared, error))
     }
 
     #[cfg(any(feature = "web-sys"))]
-    pub async fn video_post<T>(build_url: String, body: FormData) -> Result<T, ClientErr>
+    pub async fn video_post<T>(&self, build_url: String, body: FormData) -> Result<T, ClientErr>
     where
         Self: Sized,
         T: DeserializeOwned, /* response Type
                               * T: Send + DeserializeOwned, */
     {
-        let client = HttpC::new(None)?;
-        let response = client.video_post(build_url.parse().unwrap(), body).await;
-        Ok(deserialize_response_handler::<T>(response)?)
+        let prepared = self.prepare(Method::POST, &build_url)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client
+                    .video_request(prepared.request(body.clone()))
+            })
+            .await;
+        deserialize_response_handler::<T>(response)
+            .map_err(|error| self.redact_error(&prepared, error))
     }
 
     #[cfg(any(feature = "reqwest"))]
     pub async fn resumable_video_post<T>(
+        &self,
         build_url: String,
         body: UploadingData,
     ) -> Result<T, ClientErr>
@@ -105,16 +481,21 @@ impl<HttpC: HttpClient> GenericClientConnection<HttpC> {
         T: DeserializeOwned, /* response Type
                               * T: Send + DeserializeOwned, */
     {
-        let client = HttpC::new(None)?;
-        let response = client
-            .resumable_video_post(build_url.parse().unwrap(), body)
+        let prepared = self.prepare(Method::POST, &build_url)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client
+                    .resumable_video_request(prepared.request(body.clone()))
+            })
             .await;
-        Ok(deserialize_response_handler::<T>(response)?)
+        deserialize_response_handler::<T>(response)
+            .map_err(|error| self.redact_error(&prepared, error))
     }
 
     // this will be used for rqwest_async feature
     #[cfg(any(feature = "reqwest"))]
     pub async fn request_by_bytes_and_params<T>(
+        &self,
         build_url: String,
         body: (Vec<u8>, VideoParams),
     ) -> Result<T, ClientErr>
@@ -123,11 +504,67 @@ impl<HttpC: HttpClient> GenericClientConnection<HttpC> {
         T: DeserializeOwned, /* response Type
                               * T: Send + DeserializeOwned, */
     {
-        let client = HttpC::new(None)?;
-        let response = client
-            .upload_by_form_data(build_url.parse().unwrap(), body)
+        let prepared = self.prepare(Method::POST, &build_url)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client
+                    .upload_by_form_data_request(prepared.request(body.clone()))
+            })
             .await;
-        Ok(deserialize_response_handler::<T>(response)?)
+        deserialize_response_handler::<T>(response)
+            .map_err(|error| self.redact_error(&prepared, error))
+    }
+
+    /// Send a multipart POST request, e.g. to upload a photo or a video.
+    #[cfg(feature = "reqwest")]
+    pub async fn multipart_post<T>(
+        &self,
+        build_url: String,
+        body: MultipartData,
+    ) -> Result<T, ClientErr>
+    where
+        Self: Sized,
+        T: DeserializeOwned, // response Type
+    {
+        let prepared = self.prepare(Method::POST, &build_url)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client
+                    .multipart_request(prepared.request(body.clone()))
+            })
+            .await;
+        deserialize_response_handler::<T>(response)
+            .map_err(|error| self.redact_error(&prepared, error))
+    }
+
+    /// Send a batch request as a multipart body, with the files attached to
+    /// its operations. The items of its `batch` field are signed like the
+    /// items of the other batch requests.
+    #[cfg(feature = "reqwest")]
+    pub async fn batch_multipart_post<T>(
+        &self,
+        build_url: String,
+        body: MultipartData,
+    ) -> Result<T, ClientErr>
+    where
+        Self: Sized,
+        T: DeserializeOwned, // response Type
+    {
+        let body = match &self.app_secret {
+            Some(app_secret) => body.map_text("batch", |batch| {
+                sign_batch(batch, app_secret.expose_secret())
+            }),
+            None => body,
+        };
+        let prepared = self.prepare(Method::POST, &build_url)?;
+        let response = self
+            .send_with_retry(&prepared.method, || {
+                self.http_client
+                    .multipart_request(prepared.request(body.clone()))
+            })
+            .await;
+        deserialize_batch_handler::<T>(response)
+            .map_err(|error| self.redact_error(&prepared, error))
     }
 
     /*  // this will be used for rqwest_async feature
@@ -151,3 +588,94 @@ impl<HttpC: HttpClient> GenericClientConnection<HttpC> {
         Ok(deserialize_response_handler::<T>(response)?)
     }*/
 }
+
+/// The rate-limit usage of the last response, with the time it was received.
+#[derive(Debug, Clone)]
+struct ReceivedUsage {
+    usage: RateLimitUsage,
+    received_at: DateTime<Utc>,
+}
+
+/// Deserialize a response, and return it with its rate-limit usage.
+fn with_usage<T>(
+    response: Result<http::Response<String>, ClientErr>,
+    deserialize: impl FnOnce(Result<http::Response<String>, ClientErr>) -> Result<T, ClientErr>,
+) -> Result<(T, RateLimitUsage), ClientErr> {
+    let usage = response
+        .as_ref()
+        .map(|response| RateLimitUsage::from_headers(response.headers()))
+        .unwrap_or_default();
+    deserialize(response).map(|value| (value, usage))
+}
+
+/// A request ready to be sent, with the access token taken out of its url.
+struct PreparedRequest {
+    method: Method,
+    url: Url,
+    /// The url, checked to be a valid uri of an http request.
+    uri: Uri,
+    authorization: Option<(Secret, HeaderValue)>,
+    /// Whether the body is the url encoded query of the request.
+    form: bool,
+    progress: Option<RequestProgress>,
+}
+
+impl PreparedRequest {
+    fn request<B>(&self, body: B) -> http::Request<B> {
+        let mut request = http::Request::new(body);
+        *request.method_mut() = self.method.clone();
+        *request.uri_mut() = self.uri.clone();
+        if let Some((_, authorization)) = &self.authorization {
+            request
+                .headers_mut()
+                .insert(AUTHORIZATION, authorization.clone());
+        }
+        if self.form {
+            request.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            );
+        }
+        if let Some(progress) = &self.progress {
+            request.extensions_mut().insert(progress.clone());
+        }
+        request
+    }
+}
+
+/// The error of an url which can not be parsed, without the credentials it
+/// may contain.
+fn invalid_url(url: &str, error: impl fmt::Display) -> ClientErr {
+    ClientErr::InvalidUrl(redact(&format!("{}: {}", url, error), &[]))
+}
+
+fn parse_uri(url: &Url) -> Result<Uri, ClientErr> {
+    url.as_str()
+        .parse()
+        .map_err(|error| invalid_url(url.as_str(), error))
+}
+
+/// Remove the `access_token` parameter of an url, and return it with the
+/// value of the matching `Authorization` header.
+fn take_access_token(url: &mut Url) -> Option<(Secret, HeaderValue)> {
+    let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let token = pairs
+        .iter()
+        .find(|(name, value)| name == "access_token" && !value.is_empty())
+        .map(|(_, token)| Secret::new(token.as_str()))?;
+    // A token which can not be sent in a header is left in the url.
+    let mut authorization =
+        HeaderValue::from_str(&format!("Bearer {}", token.expose_secret())).ok()?;
+    authorization.set_sensitive(true);
+
+    let pairs: Vec<(String, String)> = pairs
+        .into_iter()
+        .filter(|(name, _)| name != "access_token")
+        .collect();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    Some((token, authorization))
+}
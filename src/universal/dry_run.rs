@@ -0,0 +1,422 @@
+//! Dry run of the Graph API requests.
+//!
+//! [DryRunClient](DryRunClient) is an [HttpClient](HttpClient) which keeps
+//! every request built by the APIs of the crate, exactly as it would be sent,
+//! with its method, url, headers and body, and answers it without any network
+//! access. It can be used to review what an application would publish, or to
+//! snapshot-test the Graph calls it makes. It works with the `reqwest` and
+//! the `web-sys` features: with `web-sys`, the fields of a `FormData` are
+//! kept as [FormDataField](FormDataField)s.
+//!
+//! Every request is answered with `{"id": "dry_run", "post_id": "dry_run",
+//! "success": true}`, and a batch request with one `null` per operation, so
+//! the methods publishing content return successfully. Use
+//! [respond_with](DryRunClient::respond_with) for the other responses.
+//!
+//! # Example
+//! ```
+//! use facebook_api_rs::prelude::dry_run::{DryRunBody, DryRunClient};
+//! use facebook_api_rs::prelude::feed::FeedPostFields;
+//! use facebook_api_rs::prelude::{Client, UserToken};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let dry_run = DryRunClient::default();
+//! let client = Client::new(UserToken::default(), "page_token".to_string())
+//!     .add_http_client(dry_run.clone());
+//!
+//! let post = client
+//!     .feed("page_id".to_string())
+//!     .post(FeedPostFields {
+//!         link: String::new(),
+//!         message: "Hello".to_string(),
+//!         tags: Vec::new(),
+//!         place: String::new(),
+//!         call_to_action: None,
+//!         feeling: None,
+//!     })
+//!     .await
+//!     .unwrap();
+//! assert_eq!(post.id, "dry_run");
+//!
+//! let requests = dry_run.take_requests();
+//! assert_eq!(requests[0].uri(), "https://graph.facebook.com/v23.0/page_id/feed");
+//! assert!(matches!(requests[0].body(), DryRunBody::Text(body) if body == "message=Hello"));
+//! # }
+//! ```
+
+#[cfg(feature = "reqwest")]
+use crate::prelude::utils::UploadingData;
+#[cfg(feature = "reqwest")]
+use crate::prelude::video::VideoParams;
+use crate::universal::errors::ClientErr;
+#[cfg(feature = "reqwest")]
+use crate::universal::form_data::MultipartData;
+#[cfg(feature = "web-sys")]
+use crate::universal::web_sys_client::js_error;
+use crate::universal::HttpClient;
+use async_trait::async_trait;
+use http::{HeaderMap, Request, Response, StatusCode};
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use url::form_urlencoded;
+#[cfg(feature = "web-sys")]
+use wasm_bindgen::JsCast;
+#[cfg(feature = "web-sys")]
+use web_sys::{Blob, File, FormData};
+
+/// The body of a request kept by a [DryRunClient](DryRunClient).
+#[derive(Clone)]
+pub enum DryRunBody {
+    /// A json or url encoded body, e.g. the parameters of a POST.
+    Text(String),
+    /// A multipart body, e.g. a photo or the files of a batch request.
+    #[cfg(feature = "reqwest")]
+    Multipart(MultipartData),
+    /// The parameters of a video sent in one request.
+    #[cfg(feature = "reqwest")]
+    Video(VideoParams),
+    /// A request of a resumable video upload.
+    #[cfg(feature = "reqwest")]
+    ResumableVideo(UploadingData),
+    /// The content and the parameters of a video sent from memory.
+    #[cfg(feature = "reqwest")]
+    VideoBytes(Vec<u8>, VideoParams),
+    /// The fields of a `FormData`, e.g. a video or a photo uploaded from the
+    /// browser, in their order.
+    #[cfg(feature = "web-sys")]
+    FormData(Vec<(String, FormDataField)>),
+}
+
+/// A field of a `FormData` kept by a [DryRunClient](DryRunClient).
+#[cfg(feature = "web-sys")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormDataField {
+    /// A text field, e.g. the description of a video.
+    Text(String),
+    /// A file or a blob, e.g. a chunk of a video, kept without its content.
+    File {
+        file_name: Option<String>,
+        size: u64,
+    },
+}
+
+/// The fields of a `FormData`, read with its iterator.
+#[cfg(feature = "web-sys")]
+fn form_data_fields(form_data: &FormData) -> Result<Vec<(String, FormDataField)>, ClientErr> {
+    let Some(entries) = js_sys::try_iter(form_data).map_err(js_error)? else {
+        return Ok(Vec::new());
+    };
+    let mut fields = Vec::new();
+    for entry in entries {
+        let entry = js_sys::Array::from(&entry.map_err(js_error)?);
+        let name = entry.get(0).as_string().unwrap_or_default();
+        let value = entry.get(1);
+        let field = match value.as_string() {
+            Some(text) => FormDataField::Text(text),
+            None => FormDataField::File {
+                file_name: value.dyn_ref::<File>().map(File::name),
+                size: value
+                    .dyn_ref::<Blob>()
+                    .map(|blob| blob.size() as u64)
+                    .unwrap_or_default(),
+            },
+        };
+        fields.push((name, field));
+    }
+    Ok(fields)
+}
+
+/// The video parameters and the content of the files are not printed.
+impl fmt::Debug for DryRunBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DryRunBody::Text(body) => f.debug_tuple("Text").field(body).finish(),
+            #[cfg(feature = "reqwest")]
+            DryRunBody::Multipart(data) => f.debug_tuple("Multipart").field(data).finish(),
+            #[cfg(feature = "reqwest")]
+            DryRunBody::Video(params) => f
+                .debug_struct("Video")
+                .field("title", &params.title)
+                .finish_non_exhaustive(),
+            #[cfg(feature = "reqwest")]
+            DryRunBody::ResumableVideo(data) => {
+                f.debug_tuple("ResumableVideo").field(data).finish()
+            }
+            #[cfg(feature = "reqwest")]
+            DryRunBody::VideoBytes(bytes, params) => f
+                .debug_struct("VideoBytes")
+                .field("len", &bytes.len())
+                .field("title", &params.title)
+                .finish_non_exhaustive(),
+            #[cfg(feature = "web-sys")]
+            DryRunBody::FormData(fields) => f.debug_tuple("FormData").field(fields).finish(),
+        }
+    }
+}
+
+/// A request kept by a [DryRunClient](DryRunClient), as it would be sent.
+pub type DryRunRequest = Request<DryRunBody>;
+
+type Responder = Arc<dyn Fn(&DryRunRequest) -> Value + Send + Sync>;
+
+/// An [HttpClient](HttpClient) keeping the requests instead of sending them.
+///
+/// Every clone shares the same requests.
+#[derive(Clone)]
+pub struct DryRunClient {
+    headers: HeaderMap,
+    requests: Arc<Mutex<Vec<DryRunRequest>>>,
+    responder: Responder,
+}
+
+impl DryRunClient {
+    /// Answer the requests with the json returned by `responder`, e.g. to
+    /// give a resumable upload the offsets of its next chunk.
+    pub fn respond_with<F>(mut self, responder: F) -> Self
+    where
+        F: Fn(&DryRunRequest) -> Value + Send + Sync + 'static,
+    {
+        self.responder = Arc::new(responder);
+        self
+    }
+
+    /// The requests kept since the last call, in the order they were built.
+    pub fn take_requests(&self) -> Vec<DryRunRequest> {
+        std::mem::take(&mut *self.requests())
+    }
+
+    fn requests(&self) -> MutexGuard<'_, Vec<DryRunRequest>> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Keep the request, with the default headers of the client, and answer
+    /// it.
+    fn exchange<B>(
+        &self,
+        request: Request<B>,
+        body: impl FnOnce(B) -> DryRunBody,
+    ) -> Result<Response<String>, ClientErr> {
+        let (mut parts, content) = request.into_parts();
+        for (name, value) in &self.headers {
+            if !parts.headers.contains_key(name) {
+                parts.headers.insert(name, value.clone());
+            }
+        }
+        let request = Request::from_parts(parts, body(content));
+
+        let response = (self.responder)(&request).to_string();
+        self.requests().push(request);
+        let mut response = Response::new(response);
+        *response.status_mut() = StatusCode::OK;
+        Ok(response)
+    }
+}
+
+impl Default for DryRunClient {
+    fn default() -> Self {
+        DryRunClient {
+            headers: HeaderMap::new(),
+            requests: Arc::default(),
+            responder: Arc::new(default_response),
+        }
+    }
+}
+
+impl fmt::Debug for DryRunClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DryRunClient")
+            .field("requests", &self.requests().len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait(?Send)]
+impl HttpClient for DryRunClient {
+    /// The headers are added to every request, as the
+    /// [ReqwestClient](crate::prelude::reqwest::ReqwestClient) does.
+    fn new<U: Into<Option<HeaderMap>>>(headers: U) -> Result<Self, ClientErr> {
+        Ok(DryRunClient {
+            headers: headers.into().unwrap_or_default(),
+            ..DryRunClient::default()
+        })
+    }
+
+    /// Nothing is sent, so a retry is not waited for.
+    async fn sleep(&self, _duration: Duration) {}
+
+    async fn request(&self, request: Request<String>) -> Result<Response<String>, ClientErr> {
+        self.exchange(request, DryRunBody::Text)
+    }
+
+    #[cfg(feature = "reqwest")]
+    async fn video_request(
+        &self,
+        request: Request<VideoParams>,
+    ) -> Result<Response<String>, ClientErr> {
+        self.exchange(request, DryRunBody::Video)
+    }
+
+    /// The `FormData` is kept as its fields.
+    #[cfg(feature = "web-sys")]
+    async fn video_request(
+        &self,
+        request: Request<FormData>,
+    ) -> Result<Response<String>, ClientErr> {
+        let (parts, form_data) = request.into_parts();
+        let fields = form_data_fields(&form_data)?;
+        self.exchange(Request::from_parts(parts, fields), DryRunBody::FormData)
+    }
+
+    #[cfg(feature = "reqwest")]
+    async fn resumable_video_request(
+        &self,
+        request: Request<UploadingData>,
+    ) -> Result<Response<String>, ClientErr> {
+        self.exchange(request, DryRunBody::ResumableVideo)
+    }
+
+    #[cfg(feature = "reqwest")]
+    async fn upload_by_form_data_request(
+        &self,
+        request: Request<(Vec<u8>, VideoParams)>,
+    ) -> Result<Response<String>, ClientErr> {
+        self.exchange(request, |(bytes, params)| {
+            DryRunBody::VideoBytes(bytes, params)
+        })
+    }
+
+    #[cfg(feature = "reqwest")]
+    async fn multipart_request(
+        &self,
+        request: Request<MultipartData>,
+    ) -> Result<Response<String>, ClientErr> {
+        self.exchange(request, DryRunBody::Multipart)
+    }
+}
+
+/// The answer of a [DryRunClient](DryRunClient) which was not given a
+/// [respond_with](DryRunClient::respond_with) callback.
+pub fn default_response(request: &DryRunRequest) -> Value {
+    match batch_len(request) {
+        Some(len) => Value::Array(vec![Value::Null; len]),
+        None => json!({ "id": "dry_run", "post_id": "dry_run", "success": true }),
+    }
+}
+
+/// The number of operations of a batch request, found in its `batch`
+/// parameter.
+fn batch_len(request: &DryRunRequest) -> Option<usize> {
+    let query = request.uri().query().unwrap_or_default().to_string();
+    let batch = match request.body() {
+        DryRunBody::Text(body) => form_urlencoded::parse(query.as_bytes())
+            .chain(form_urlencoded::parse(body.as_bytes()))
+            .find(|(name, _)| name == "batch")
+            .map(|(_, batch)| batch.into_owned()),
+        #[cfg(feature = "reqwest")]
+        DryRunBody::Multipart(data) => data
+            .fields()
+            .iter()
+            .find(|(name, _)| name == "batch")
+            .map(|(_, batch)| batch.clone()),
+        _ => None,
+    }?;
+    serde_json::from_str::<Vec<Value>>(&batch)
+        .ok()
+        .map(|operations| operations.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{BatchOperation, BatchRequest, Client, UserToken};
+    use http::header::AUTHORIZATION;
+
+    #[tokio::test]
+    async fn test_requests_are_kept_and_not_sent() {
+        let dry_run = DryRunClient::default();
+        let client = || {
+            Client::new(UserToken::default(), "page_token".to_string())
+                .add_graph_host("http://127.0.0.1:9".to_string())
+                .add_http_client(dry_run.clone())
+        };
+
+        let photo = client()
+            .photos("1234".to_string())
+            .post_by_bytes(
+                b"jpeg".to_vec(),
+                crate::prelude::photo::PhotoParams::new().message("Tom & Jerry"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(photo.post_id, "dry_run");
+
+        let container = client()
+            .instagram_content_publishing("5678".to_string())
+            .upload_image(
+                "https://example.com/a.jpg".to_string(),
+                "Hi".to_string(),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(container.id, "dry_run");
+
+        let responses = client()
+            .batch_request("1234".to_string())
+            .send::<Value>(
+                &[BatchOperation::get("me"), BatchOperation::get("1234/feed")]
+                    .into_iter()
+                    .collect::<BatchRequest>(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(responses.len(), 2);
+        assert!(responses.iter().all(Option::is_none));
+
+        let requests = dry_run.take_requests();
+        assert_eq!(requests.len(), 3);
+        assert!(dry_run.take_requests().is_empty());
+
+        assert_eq!(requests[0].uri(), "http://127.0.0.1:9/v23.0/1234/photos");
+        assert_eq!(requests[0].headers()[AUTHORIZATION], "Bearer page_token");
+        match requests[0].body() {
+            DryRunBody::Multipart(data) => {
+                assert!(data
+                    .fields()
+                    .contains(&("message".to_string(), "Tom & Jerry".to_string())));
+                assert_eq!(&data.files()[0].bytes[..], b"jpeg");
+            }
+            body => panic!("unexpected body: {:?}", body),
+        }
+
+        assert_eq!(requests[1].method(), "POST");
+        assert_eq!(requests[1].uri().path(), "/v23.0/5678/media");
+        assert!(matches!(
+            requests[1].body(),
+            DryRunBody::Text(body) if body.contains("image_url=https%3A%2F%2Fexample.com%2Fa.jpg")
+        ));
+
+        assert!(matches!(requests[2].body(), DryRunBody::Text(body) if body.starts_with("batch=")));
+    }
+
+    #[tokio::test]
+    async fn test_respond_with() {
+        let dry_run = DryRunClient::default().respond_with(
+            |request| json!({ "id": request.uri().path().trim_start_matches("/v23.0/") }),
+        );
+        let post = Client::new(UserToken::default(), "page_token".to_string())
+            .add_http_client(dry_run.clone())
+            .node("1234_5678")
+            .get::<Value>()
+            .await
+            .unwrap();
+        assert_eq!(post["id"], "1234_5678");
+        assert_eq!(dry_run.take_requests()[0].method(), "GET");
+    }
+}
@@ -1,8 +1,13 @@
 use std::fmt;
 
+use http::{HeaderMap, StatusCode};
 use serde::Deserialize;
 use thiserror::Error;
 
+use crate::universal::retry::TRANSIENT_ERROR_CODES;
+use crate::universal::secret::redact;
+use crate::universal::usage::RateLimitUsage;
+
 #[derive(Error, Debug)]
 pub enum ClientErr {
     #[error("facebook error:  {0}")]
@@ -10,27 +15,214 @@ pub enum ClientErr {
     #[error("facebook custom error:  {0}")]
     CustomError(String),
     #[error("Error from server: {0}")]
-    Facebook(#[from] FacebookAPiError),
+    Facebook(#[from] Box<FacebookAPiError>),
     #[error("Error from serde: {0}")]
     Serde(#[from] serde_json::error::Error),
     #[error("HTTP client error: {0}")]
     HttpClient(String),
+    /// A response with an error status whose body is not an error of
+    /// Facebook, e.g. the html page of a proxy.
+    #[error("unexpected response: {0}")]
+    UnexpectedResponse(Box<ErrorResponse>),
+    /// An url which can not be parsed, e.g. built with an id which is not
+    /// url encoded.
+    #[error("invalid url: {0}")]
+    InvalidUrl(String),
+    /// A request which can not be built, e.g. with an invalid header value.
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    /// A version of the Graph API which is not in the `vXX.X` format.
+    #[error("Invalid API version format: '{0}'. Expected format: 'vXX.X' (e.g., 'v23.0')")]
+    InvalidApiVersion(String),
+    /// A file which can not be read.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<FacebookAPiError> for ClientErr {
+    fn from(error: FacebookAPiError) -> Self {
+        ClientErr::Facebook(Box::new(error))
+    }
+}
+
+impl From<http::Error> for ClientErr {
+    fn from(error: http::Error) -> Self {
+        ClientErr::InvalidRequest(error.to_string())
+    }
+}
+
+impl ClientErr {
+    /// The same error with the given secrets, and the values of the
+    /// [sensitive parameters](crate::prelude::secret::SENSITIVE_PARAMETERS),
+    /// removed from its message.
+    pub fn redact(self, secrets: &[&str]) -> ClientErr {
+        match self {
+            ClientErr::FacebookError(message) => {
+                ClientErr::FacebookError(redact(&message, secrets))
+            }
+            ClientErr::CustomError(message) => ClientErr::CustomError(redact(&message, secrets)),
+            ClientErr::HttpClient(message) => ClientErr::HttpClient(redact(&message, secrets)),
+            ClientErr::Facebook(mut error) => {
+                error.error.message = redact(&error.error.message, secrets);
+                error.error.error_user_title = error
+                    .error
+                    .error_user_title
+                    .map(|title| redact(&title, secrets));
+                error.error.error_user_msg = error
+                    .error
+                    .error_user_msg
+                    .map(|message| redact(&message, secrets));
+                error.response = error.response.map(|response| response.redact(secrets));
+                ClientErr::Facebook(error)
+            }
+            ClientErr::UnexpectedResponse(response) => {
+                ClientErr::UnexpectedResponse(response.redact(secrets))
+            }
+            ClientErr::InvalidUrl(message) => ClientErr::InvalidUrl(redact(&message, secrets)),
+            ClientErr::InvalidRequest(message) => {
+                ClientErr::InvalidRequest(redact(&message, secrets))
+            }
+            error @ (ClientErr::InvalidApiVersion(_) | ClientErr::Io(_)) => error,
+            ClientErr::Serde(error) => {
+                let message = error.to_string();
+                let redacted = redact(&message, secrets);
+                if redacted == message {
+                    ClientErr::Serde(error)
+                } else {
+                    ClientErr::Serde(serde::de::Error::custom(redacted))
+                }
+            }
+        }
+    }
+
+    /// The [ErrorKind](ErrorKind) of an error returned by Facebook.
+    pub fn kind(&self) -> Option<ErrorKind> {
+        match self {
+            ClientErr::Facebook(error) => Some(error.error.kind()),
+            _ => None,
+        }
+    }
+
+    /// Whether the request is worth sending again later, see
+    /// [ErrorKind::is_retryable](ErrorKind::is_retryable). A response which
+    /// is not an error of Facebook is worth retrying with a `429 Too Many
+    /// Requests` or a `5xx` status.
+    ///
+    /// A POST answered with such a status may have been processed by
+    /// Facebook, e.g. a post may have been published, so it is not retried
+    /// by the [RetryPolicy](crate::prelude::retry::RetryPolicy).
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientErr::UnexpectedResponse(response) => {
+                response.status == StatusCode::TOO_MANY_REQUESTS
+                    || response.status.is_server_error()
+            }
+            _ => self.kind().is_some_and(|kind| kind.is_retryable()),
+        }
+    }
+
+    /// Whether the user must log in again to get a new access token, see
+    /// [ErrorKind::requires_relogin](ErrorKind::requires_relogin).
+    pub fn requires_relogin(&self) -> bool {
+        self.kind().is_some_and(|kind| kind.requires_relogin())
+    }
+
+    /// The http response of the error, when the request was answered with
+    /// an error status.
+    pub fn response(&self) -> Option<&ErrorResponse> {
+        match self {
+            ClientErr::Facebook(error) => error.response.as_deref(),
+            ClientErr::UnexpectedResponse(response) => Some(response),
+            _ => None,
+        }
+    }
+}
+
+/// The http response of a request which failed, kept with its error so it
+/// can be given to the Facebook support.
+#[derive(Debug, Clone)]
+pub struct ErrorResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// The raw body.
+    pub body: String,
+}
+
+impl ErrorResponse {
+    /// The `x-fb-trace-id` header, the id of the request at Facebook.
+    pub fn trace_id(&self) -> Option<&str> {
+        self.header("x-fb-trace-id")
+    }
+
+    /// The `x-fb-debug` header, to give to the Facebook support with the
+    /// trace id.
+    pub fn debug_id(&self) -> Option<&str> {
+        self.header("x-fb-debug")
+    }
+
+    /// The rate-limit usage reported by the headers of the response.
+    pub fn rate_limit_usage(&self) -> RateLimitUsage {
+        RateLimitUsage::from_headers(&self.headers)
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    fn redact(mut self: Box<Self>, secrets: &[&str]) -> Box<ErrorResponse> {
+        self.body = redact(&self.body, secrets);
+        self
+    }
+}
+
+impl fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "status: {}, body: {}", self.status, self.body)
+    }
 }
 
 #[derive(Deserialize, Debug, Clone, Error)]
 pub struct ApiError {
-    pub code: u16,
+    pub code: u32,
     pub message: String,
     pub r#type: Option<String>,
-    pub error_subcode: Option<u16>,
+    pub error_subcode: Option<u32>,
     pub fbtrace_id: Option<String>,
     pub error_user_title: Option<String>,
     pub error_user_msg: Option<String>,
+    pub is_transient: Option<bool>,
 }
 
 #[derive(Deserialize, Debug, Clone, Error)]
 pub struct FacebookAPiError {
     pub error: ApiError,
+    /// The http response of the error, absent for the errors of the items of
+    /// a batch request.
+    #[serde(skip)]
+    pub response: Option<Box<ErrorResponse>>,
+}
+
+impl FacebookAPiError {
+    pub fn new(error: ApiError) -> Self {
+        FacebookAPiError {
+            error,
+            response: None,
+        }
+    }
+
+    /// The http status of the response, e.g. 400.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.response.as_ref().map(|response| response.status)
+    }
+
+    /// The id of the request at Facebook, from the error or from the
+    /// `x-fb-trace-id` header, to give to the Facebook support.
+    pub fn fbtrace_id(&self) -> Option<&str> {
+        self.error
+            .fbtrace_id
+            .as_deref()
+            .or_else(|| self.response.as_ref()?.trace_id())
+    }
 }
 
 impl fmt::Display for FacebookAPiError {
@@ -45,7 +237,7 @@ impl fmt::Display for ApiError {
 }
 
 impl ApiError {
-    pub fn code(&self) -> u16 {
+    pub fn code(&self) -> u32 {
         self.code
     }
 
@@ -60,4 +252,210 @@ impl ApiError {
     pub fn fbtrace_id(&self) -> &Option<String> {
         &self.fbtrace_id
     }
+
+    pub fn error_subcode(&self) -> Option<u32> {
+        self.error_subcode
+    }
+
+    /// The category of the error, from its `code` and `error_subcode`.
+    ///
+    /// For more information check <https://developers.facebook.com/docs/graph-api/guides/error-handling>.
+    pub fn kind(&self) -> ErrorKind {
+        match (self.code, self.error_subcode) {
+            (102 | 190, subcode) => ErrorKind::OAuth(TokenError::from_subcode(subcode)),
+            _ if self.is_transient == Some(true) => ErrorKind::Transient,
+            (10 | 200..=299, _) => ErrorKind::PermissionDenied,
+            (4 | 17 | 32 | 613, _) => ErrorKind::RateLimited,
+            (code, _) if TRANSIENT_ERROR_CODES.contains(&code) => ErrorKind::Transient,
+            (506, _) => ErrorKind::DuplicatePost,
+            (100, Some(33)) | (803, _) => ErrorKind::NotFound,
+            (100, _) => ErrorKind::InvalidParameter,
+            (3, _) => ErrorKind::UnsupportedMethod,
+            (12, _) => ErrorKind::Deprecated,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Whether the error is temporary and the request is worth retrying, see
+    /// [ErrorKind::is_retryable](ErrorKind::is_retryable).
+    pub fn is_transient(&self) -> bool {
+        self.kind().is_retryable()
+    }
+}
+
+/// The category of an [ApiError](ApiError), to handle the errors of
+/// Facebook with a `match` on their meaning instead of their codes.
+///
+/// # Example
+/// ```
+/// use facebook_api_rs::prelude::errors::{ClientErr, ErrorKind, TokenError};
+///
+/// fn handle(error: &ClientErr) -> &'static str {
+///     match error.kind() {
+///         Some(ErrorKind::OAuth(TokenError::Expired)) => "refresh the token",
+///         Some(kind) if kind.requires_relogin() => "log in again",
+///         Some(kind) if kind.is_retryable() => "try again later",
+///         Some(ErrorKind::DuplicatePost) => "already posted",
+///         _ => "give up",
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The access token is not valid anymore, codes 102 and 190.
+    OAuth(TokenError),
+    /// A permission is missing or was removed, codes 10 and 200 to 299.
+    PermissionDenied,
+    /// A rate limit of the app, the user or the page was reached, codes 4,
+    /// 17, 32 and 613.
+    RateLimited,
+    /// A temporary failure of Facebook, e.g. codes 1 and 2, or an error
+    /// flagged with `is_transient`.
+    Transient,
+    /// The same message was just posted, code 506.
+    DuplicatePost,
+    /// A parameter is missing or has an invalid value, code 100.
+    InvalidParameter,
+    /// The node does not exist or can not be seen with the access token,
+    /// code 803 or code 100 with subcode 33.
+    NotFound,
+    /// The method is not supported by the node or the app, code 3.
+    UnsupportedMethod,
+    /// The endpoint is not available in the version of the Graph API, code
+    /// 12.
+    Deprecated,
+    /// Any other error.
+    Other,
+}
+
+impl ErrorKind {
+    /// Whether the same request may succeed when sent again later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorKind::RateLimited | ErrorKind::Transient)
+    }
+
+    /// Whether the user must log in again to get a valid access token.
+    pub fn requires_relogin(&self) -> bool {
+        matches!(self, ErrorKind::OAuth(_))
+    }
+}
+
+/// Why an access token is not valid, from the `error_subcode` of an OAuth
+/// error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenError {
+    /// The user removed the app, subcode 458.
+    AppNotInstalled,
+    /// The user must log in to facebook.com to fix their account, subcode
+    /// 459.
+    UserCheckpointed,
+    /// The user changed their password, subcode 460.
+    PasswordChanged,
+    /// The token expired, subcode 463.
+    Expired,
+    /// The user has not confirmed their account, subcode 464.
+    UnconfirmedUser,
+    /// The token is not valid, e.g. the user logged out, subcode 467.
+    Invalid,
+    /// Another or no subcode.
+    Other,
+}
+
+impl TokenError {
+    fn from_subcode(subcode: Option<u32>) -> Self {
+        match subcode {
+            Some(458) => TokenError::AppNotInstalled,
+            Some(459) => TokenError::UserCheckpointed,
+            Some(460) => TokenError::PasswordChanged,
+            Some(463) => TokenError::Expired,
+            Some(464) => TokenError::UnconfirmedUser,
+            Some(467) => TokenError::Invalid,
+            _ => TokenError::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(code: u32, error_subcode: Option<u32>) -> ApiError {
+        ApiError {
+            code,
+            message: "error".to_string(),
+            r#type: None,
+            error_subcode,
+            fbtrace_id: None,
+            error_user_title: None,
+            error_user_msg: None,
+            is_transient: None,
+        }
+    }
+
+    #[test]
+    fn test_errors_are_classified() {
+        let kinds = [
+            (190, Some(463), ErrorKind::OAuth(TokenError::Expired)),
+            (
+                190,
+                Some(460),
+                ErrorKind::OAuth(TokenError::PasswordChanged),
+            ),
+            (
+                190,
+                Some(458),
+                ErrorKind::OAuth(TokenError::AppNotInstalled),
+            ),
+            (190, Some(467), ErrorKind::OAuth(TokenError::Invalid)),
+            (190, None, ErrorKind::OAuth(TokenError::Other)),
+            (10, None, ErrorKind::PermissionDenied),
+            (230, None, ErrorKind::PermissionDenied),
+            (4, None, ErrorKind::RateLimited),
+            (32, None, ErrorKind::RateLimited),
+            (613, None, ErrorKind::RateLimited),
+            (2, None, ErrorKind::Transient),
+            (506, None, ErrorKind::DuplicatePost),
+            (100, None, ErrorKind::InvalidParameter),
+            (100, Some(33), ErrorKind::NotFound),
+            (999, None, ErrorKind::Other),
+        ];
+        for (code, subcode, kind) in kinds {
+            assert_eq!(error(code, subcode).kind(), kind, "code {}", code);
+        }
+
+        let mut flagged = error(100, None);
+        flagged.is_transient = Some(true);
+        assert_eq!(flagged.kind(), ErrorKind::Transient);
+
+        let expired = ClientErr::from(FacebookAPiError::new(error(190, Some(463))));
+        assert!(expired.requires_relogin());
+        assert!(!expired.is_retryable());
+        let limited = ClientErr::from(FacebookAPiError::new(error(613, None)));
+        assert!(limited.is_retryable());
+        assert_eq!(ClientErr::HttpClient("timeout".to_string()).kind(), None);
+    }
+
+    #[test]
+    fn test_large_subcodes_are_decoded() {
+        let response = http::Response::builder()
+            .status(400)
+            .body(
+                r#"{"error":{"message":"Media ID is not available","type":"OAuthException","code":9007,"error_subcode":2207027,"is_transient":false}}"#
+                    .to_string(),
+            )
+            .unwrap();
+
+        let decoded = match crate::universal::response::error_response(response) {
+            ClientErr::Facebook(decoded) => decoded.error,
+            other => panic!("not an error of Facebook: {:?}", other),
+        };
+        assert_eq!(decoded.code(), 9007);
+        assert_eq!(decoded.error_subcode(), Some(2207027));
+        assert_eq!(decoded.kind(), ErrorKind::Other);
+
+        assert_eq!(
+            error(190, Some(1363030)).kind(),
+            ErrorKind::OAuth(TokenError::Other)
+        );
+    }
 }
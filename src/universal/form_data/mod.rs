@@ -1,8 +1,10 @@
+use crate::prelude::media_upload::progress::RequestProgress;
+use crate::prelude::photo::PhotoParams;
 use crate::prelude::video::VideoParams;
+use bytes::Bytes;
 
 #[cfg(any(feature = "reqwest"))]
 use reqwest::multipart::{Form, Part};
-use std::io::Read;
 
 #[cfg(any(feature = "reqwest"))]
 pub fn create_form_data(video_params: VideoParams, buffer: Vec<u8>) -> Form {
@@ -24,3 +26,119 @@ pub fn form_data_with_bytes(buffer: Vec<u8>, video_params: VideoParams) -> Form
         .text("description", video_params.description)
         .part("source", part)
 }
+
+/// The multipart body uploading a photo: the photo as `source`, with the
+/// fields of `photo_params`.
+pub fn photo_form_data(photo_params: &PhotoParams, photo: impl Into<Bytes>) -> MultipartData {
+    let mut form_data = MultipartData::new().file("source", "source", photo);
+    for (name, value) in photo_params.fields() {
+        form_data = form_data.text(name, value);
+    }
+    form_data
+}
+
+/// The size of the pieces of a file handed to reqwest when the progress of
+/// the upload is reported.
+const PROGRESS_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The text fields and the files of a multipart request.
+///
+/// Unlike a reqwest [Form](Form), it can be cloned, so the request can be
+/// sent again when it fails.
+#[derive(Clone, Debug, Default)]
+pub struct MultipartData {
+    fields: Vec<(String, String)>,
+    files: Vec<MultipartFile>,
+}
+
+/// A file of a [MultipartData](MultipartData).
+#[derive(Clone, Debug)]
+pub struct MultipartFile {
+    pub name: String,
+    pub file_name: String,
+    pub bytes: Bytes,
+}
+
+impl MultipartData {
+    pub fn new() -> Self {
+        MultipartData::default()
+    }
+
+    /// Add a text field.
+    pub fn text(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add a file. The progress of a request is reported for its first file.
+    pub fn file(
+        mut self,
+        name: impl Into<String>,
+        file_name: impl Into<String>,
+        bytes: impl Into<Bytes>,
+    ) -> Self {
+        self.files.push(MultipartFile {
+            name: name.into(),
+            file_name: file_name.into(),
+            bytes: bytes.into(),
+        });
+        self
+    }
+
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    /// Replace the value of the text fields named `name`.
+    pub(crate) fn map_text(mut self, name: &str, map: impl Fn(&str) -> String) -> Self {
+        for (field, value) in self.fields.iter_mut() {
+            if field == name {
+                *value = map(value);
+            }
+        }
+        self
+    }
+
+    pub fn files(&self) -> &[MultipartFile] {
+        &self.files
+    }
+}
+
+/// The reqwest form of a [MultipartData](MultipartData), reporting the
+/// progress of its first file.
+pub fn multipart_form(data: &MultipartData, progress: Option<&RequestProgress>) -> Form {
+    let mut form = Form::new();
+    for (name, value) in &data.fields {
+        form = form.text(name.clone(), value.clone());
+    }
+    for (index, file) in data.files.iter().enumerate() {
+        let progress = if index == 0 { progress } else { None };
+        let part = progress_part(file.bytes.clone(), progress).file_name(file.file_name.clone());
+        form = form.part(file.name.clone(), part);
+    }
+    form
+}
+
+/// A multipart part with the bytes of a file. When the request has a
+/// [RequestProgress](RequestProgress), the bytes are streamed in pieces and
+/// each piece is reported when reqwest takes it to send it.
+pub(crate) fn progress_part(bytes: impl Into<Bytes>, progress: Option<&RequestProgress>) -> Part {
+    let bytes: Bytes = bytes.into();
+    let total = bytes.len() as u64;
+    let Some(progress) = progress.cloned() else {
+        return Part::stream_with_length(bytes, total);
+    };
+    progress.report(0, total);
+
+    let pieces = (0..bytes.len())
+        .step_by(PROGRESS_CHUNK_SIZE)
+        .map(move |start| {
+            let end = (start + PROGRESS_CHUNK_SIZE).min(bytes.len());
+            progress.report(end as u64, total);
+            Ok::<_, std::io::Error>(bytes.slice(start..end))
+        });
+    Part::stream_with_length(
+        reqwest::Body::wrap_stream(futures_util::stream::iter(pieces)),
+        total,
+    )
+}
@@ -0,0 +1,938 @@
+//! An in-process stand-in of the Facebook Graph API for offline tests.
+//!
+//! [MockGraphServer](MockGraphServer) listens on a random local port and
+//! answers the endpoints wrapped by this crate with canned responses shaped
+//! like the ones of the Graph API:
+//!
+//! * `GET /me`, `GET /me/accounts`
+//! * `GET|POST /{page_id}/feed`, `GET|POST /{page_id}/photos`
+//! * `POST /{page_id}/videos`, including the `start`, `transfer` and `finish`
+//!   phases of a resumable upload
+//! * `GET|POST /{ig_user_id}/media`, `POST /{ig_user_id}/media_publish`
+//! * `GET /debug_token`, `GET|POST /oauth/access_token`
+//! * `GET /?ids=a,b,c`, answered with a node for every id
+//! * `POST /` with a `batch` parameter, where every item of the batch is
+//!   answered as if it was sent on its own, with the `{result=name:$.id}`
+//!   references, `depends_on` and `omit_response_on_success` of the items,
+//!   and the files of a multipart batch given to the items naming them in
+//!   `attached_files`
+//!
+//! The API version segment of the path is ignored, so a request to
+//! `/v23.0/me` is answered by the `/me` route. Any response can be replaced
+//! with [mock](MockGraphServer::mock) or
+//! [mock_once](MockGraphServer::mock_once), and every request received is
+//! kept so the test can check what was sent.
+//!
+//! The module is available with the `mock-server` feature.
+//!
+//! # Example
+//! ```
+//! use facebook_api_rs::prelude::mock_server::{MockGraphServer, MockResponse};
+//!
+//! let server = MockGraphServer::start().unwrap();
+//! server.mock(
+//!     "POST",
+//!     "/*/feed",
+//!     MockResponse::error(400, 190, "OAuthException", "Invalid OAuth access token."),
+//! );
+//!
+//! // Every request of this client is sent to the mock server.
+//! let client = server.client("page_access_token".to_string());
+//! ```
+
+use crate::prelude::{Client, UserToken};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+/// Size of the chunks the mock server asks for during a resumable video
+/// upload.
+pub const MOCK_UPLOAD_CHUNK_SIZE: u64 = 1048576;
+
+/// A local HTTP server imitating the Graph API endpoints wrapped by this
+/// crate.
+///
+/// The server is stopped when it is dropped.
+pub struct MockGraphServer {
+    address: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockGraphServer {
+    /// Start a server on a random port of the loopback interface.
+    pub fn start() -> io::Result<MockGraphServer> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let server_state = Arc::clone(&state);
+        let server_shutdown = Arc::clone(&shutdown);
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if server_shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let state = Arc::clone(&server_state);
+                    thread::spawn(move || handle_connection(stream, &state));
+                }
+            }
+        });
+
+        Ok(MockGraphServer {
+            address,
+            state,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    /// The host of the server, e.g. "http://127.0.0.1:41234", to pass to
+    /// [Client::add_graph_host](Client::add_graph_host).
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// A [Client](Client) sending every request to this server.
+    pub fn client(&self, page_access_token: String) -> Client {
+        Client::new(UserToken::default(), page_access_token).add_graph_host(self.url())
+    }
+
+    /// Answer every request matching `method` and `path` with `response`.
+    ///
+    /// The path does not contain the API version, and a `*` segment matches
+    /// any id, e.g. "/*/feed". When several mocks match a request, the one
+    /// added last is used.
+    pub fn mock(&self, method: &str, path: &str, response: MockResponse) {
+        self.add_mock(method, path, response, None);
+    }
+
+    /// Same as [mock](MockGraphServer::mock) but the response is only used
+    /// for the next matching request.
+    pub fn mock_once(&self, method: &str, path: &str, response: MockResponse) {
+        self.add_mock(method, path, response, Some(1));
+    }
+
+    /// Remove the mocks and the recorded requests.
+    pub fn reset(&self) {
+        *self.state() = MockState::default();
+    }
+
+    /// The requests received so far, in the order they came in. The items of
+    /// a batch request are recorded after the batch request itself.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state().requests.clone()
+    }
+
+    fn add_mock(&self, method: &str, path: &str, response: MockResponse, times: Option<usize>) {
+        self.state().mocks.push(Mock {
+            method: method.to_uppercase(),
+            path: normalize_path(path),
+            response,
+            times,
+        });
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        lock(&self.state)
+    }
+}
+
+impl Drop for MockGraphServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the listener up so it sees the shutdown flag.
+        let _ = TcpStream::connect(self.address);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// A response given by the [MockGraphServer](MockGraphServer).
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl MockResponse {
+    /// A `200 OK` response with a json body.
+    pub fn ok(body: Value) -> MockResponse {
+        MockResponse::json(200, body)
+    }
+
+    /// A response with the given status code and json body.
+    pub fn json(status: u16, body: Value) -> MockResponse {
+        MockResponse {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.to_string(),
+        }
+    }
+
+    /// An error in the shape of [FacebookAPiError](crate::prelude::errors::FacebookAPiError).
+    ///
+    /// # Example
+    /// ```
+    /// use facebook_api_rs::prelude::mock_server::MockResponse;
+    ///
+    /// let response = MockResponse::error(400, 190, "OAuthException", "Invalid OAuth access token.");
+    /// assert_eq!(response.status(), 400);
+    /// ```
+    pub fn error(status: u16, code: u32, error_type: &str, message: &str) -> MockResponse {
+        MockResponse::json(
+            status,
+            json!({
+                "error": {
+                    "message": message,
+                    "type": error_type,
+                    "code": code,
+                    "fbtrace_id": "mock_fbtrace_id",
+                }
+            }),
+        )
+    }
+
+    /// Add a header to the response, e.g. a `X-App-Usage` header.
+    pub fn with_header(mut self, name: &str, value: &str) -> MockResponse {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+}
+
+/// A request received by the [MockGraphServer](MockGraphServer).
+#[derive(Debug, Clone, Default)]
+pub struct RecordedRequest {
+    /// The http method, e.g. "GET".
+    pub method: String,
+    /// The path of the request, without the query.
+    pub path: String,
+    /// The decoded query parameters.
+    pub query: HashMap<String, String>,
+    /// The headers, with lower case names.
+    pub headers: HashMap<String, String>,
+    /// The raw body.
+    pub body: Vec<u8>,
+    /// The text fields of an url encoded or multipart body.
+    pub form: HashMap<String, String>,
+    /// The file fields of a multipart body.
+    pub files: HashMap<String, Vec<u8>>,
+}
+
+impl RecordedRequest {
+    /// The value of a query parameter or of a text field of the body.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .get(name)
+            .or_else(|| self.form.get(name))
+            .map(String::as_str)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    /// The access token of the request, from the `Authorization: Bearer`
+    /// header or from the `access_token` parameter.
+    pub fn access_token(&self) -> Option<&str> {
+        self.header("authorization")
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .or_else(|| self.param("access_token"))
+    }
+}
+
+#[derive(Debug)]
+struct Mock {
+    method: String,
+    path: String,
+    response: MockResponse,
+    times: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    mocks: Vec<Mock>,
+    requests: Vec<RecordedRequest>,
+    /// The file size of the resumable uploads, by upload session id.
+    upload_sessions: HashMap<String, u64>,
+    last_id: u64,
+}
+
+impl MockState {
+    fn next_id(&mut self) -> String {
+        self.last_id += 1;
+        format!("{}", 1000 + self.last_id)
+    }
+
+    fn respond(&mut self, request: RecordedRequest) -> MockResponse {
+        let method = request.method.clone();
+        let path = normalize_path(&request.path);
+        self.requests.push(request.clone());
+
+        if let Some(response) = self.scripted_response(&method, &path) {
+            return response;
+        }
+        match (method.as_str(), segments(&path).as_slice()) {
+            ("POST", []) if request.param("batch").is_some() => self.batch(&request),
+            ("GET", []) if request.param("ids").is_some() => ids(&request),
+            ("GET", ["me"]) => MockResponse::ok(json!({
+                "id": "mock_user_id",
+                "name": "Mock User",
+                "first_name": "Mock",
+                "last_name": "User",
+                "email": "mock.user@example.com",
+                "picture": {"data": {"url": "https://example.com/picture.png"}},
+            })),
+            ("GET", [_, "accounts"]) => MockResponse::ok(json!({
+                "data": [{
+                    "id": "mock_page_id",
+                    "name": "Mock Page",
+                    "access_token": "mock_page_access_token",
+                    "category": "Software",
+                    "category_list": [{"id": "2211", "name": "Software"}],
+                    "tasks": ["ANALYZE", "ADVERTISE", "MODERATE", "CREATE_CONTENT", "MANAGE"],
+                }]
+            })),
+            ("GET", [_, "feed" | "photos" | "videos" | "media"]) => {
+                MockResponse::ok(json!({ "data": [] }))
+            }
+            ("POST", [node, "feed"]) => {
+                let id = format!("{}_{}", node, self.next_id());
+                MockResponse::ok(json!({ "id": id }))
+            }
+            ("POST", [node, "photos"]) => {
+                let id = self.next_id();
+                let post_id = format!("{}_{}", node, id);
+                MockResponse::ok(json!({ "id": id, "post_id": post_id }))
+            }
+            ("POST", [_, "videos"]) => self.video_upload(&request),
+            ("POST", [_, "media" | "media_publish"]) => {
+                MockResponse::ok(json!({ "id": self.next_id() }))
+            }
+            ("GET", ["debug_token"]) => MockResponse::ok(json!({
+                "data": {
+                    "app_id": "mock_app_id",
+                    "type": "USER",
+                    "application": "Mock App",
+                    "data_access_expires_at": 1893456000,
+                    "expires_at": 0,
+                    "is_valid": true,
+                    "scopes": ["pages_show_list", "pages_manage_posts"],
+                    "granular_scopes": [],
+                    "user_id": "mock_user_id",
+                }
+            })),
+            ("GET" | "POST", ["oauth", "access_token"]) => MockResponse::ok(json!({
+                "access_token": "mock_access_token",
+                "token_type": "bearer",
+                "expires_in": 5183944,
+            })),
+            ("GET", [node]) => MockResponse::ok(json!({ "id": node })),
+            _ => unsupported_request(&method, &path),
+        }
+    }
+
+    fn scripted_response(&mut self, method: &str, path: &str) -> Option<MockResponse> {
+        let index = self
+            .mocks
+            .iter()
+            .rposition(|mock| mock.method == method && path_matches(&mock.path, path))?;
+        let mock = &mut self.mocks[index];
+        let response = mock.response.clone();
+        if let Some(times) = mock.times.as_mut() {
+            *times -= 1;
+            if *times == 0 {
+                self.mocks.remove(index);
+            }
+        }
+        Some(response)
+    }
+
+    /// Answer the `start`, `transfer` and `finish` phases of a resumable
+    /// video upload, or a video sent in one request.
+    fn video_upload(&mut self, request: &RecordedRequest) -> MockResponse {
+        match request.param("upload_phase") {
+            Some("start") => {
+                let file_size = request
+                    .param("file_size")
+                    .and_then(|size| size.parse::<u64>().ok())
+                    .unwrap_or_default();
+                let upload_session_id = self.next_id();
+                self.upload_sessions
+                    .insert(upload_session_id.clone(), file_size);
+                MockResponse::ok(json!({
+                    "video_id": self.next_id(),
+                    "upload_session_id": upload_session_id,
+                    "start_offset": "0",
+                    "end_offset": file_size.min(MOCK_UPLOAD_CHUNK_SIZE).to_string(),
+                }))
+            }
+            Some("transfer") => {
+                let file_size = request
+                    .param("upload_session_id")
+                    .and_then(|session| self.upload_sessions.get(session));
+                let start_offset = request
+                    .param("start_offset")
+                    .and_then(|offset| offset.parse::<u64>().ok());
+                match (file_size, start_offset) {
+                    (Some(&file_size), Some(start_offset)) => {
+                        let chunk_size = request
+                            .files
+                            .get("video_file_chunk")
+                            .map(|chunk| chunk.len() as u64)
+                            .unwrap_or_default();
+                        let start_offset = (start_offset + chunk_size).min(file_size);
+                        let end_offset = (start_offset + MOCK_UPLOAD_CHUNK_SIZE).min(file_size);
+                        MockResponse::ok(json!({
+                            "start_offset": start_offset.to_string(),
+                            "end_offset": end_offset.to_string(),
+                        }))
+                    }
+                    _ => MockResponse::error(
+                        400,
+                        6000,
+                        "OAuthException",
+                        "There was a problem uploading your video file. Please try again.",
+                    ),
+                }
+            }
+            Some("finish") => MockResponse::ok(json!({ "success": true })),
+            _ => MockResponse::ok(json!({ "id": self.next_id() })),
+        }
+    }
+
+    /// Answer every item of a batch request as if it was sent on its own.
+    fn batch(&mut self, request: &RecordedRequest) -> MockResponse {
+        let items = request
+            .param("batch")
+            .and_then(|batch| serde_json::from_str::<Vec<Value>>(batch).ok());
+        let Some(items) = items else {
+            return MockResponse::error(
+                400,
+                100,
+                "OAuthException",
+                "(#100) The parameter batch must be a JSON array.",
+            );
+        };
+        if items.len() > 50 {
+            return MockResponse::error(
+                400,
+                100,
+                "OAuthException",
+                "(#100) Too many requests in batch message. Maximum batch size is 50",
+            );
+        }
+
+        // The bodies of the named operations which succeeded, by name.
+        let mut results: HashMap<String, Value> = HashMap::new();
+        let mut failed: Vec<String> = Vec::new();
+        let mut responses = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let name = item["name"].as_str();
+            let depends_on = item["depends_on"].as_str();
+            if depends_on.is_some_and(|depends_on| failed.iter().any(|name| name == depends_on)) {
+                failed.extend(name.map(str::to_string));
+                responses.push(Value::Null);
+                continue;
+            }
+
+            let method = item["method"].as_str().unwrap_or("GET").to_uppercase();
+            let relative_url =
+                resolve_references(item["relative_url"].as_str().unwrap_or_default(), &results);
+            let (path, query) = relative_url.split_once('?').unwrap_or((&relative_url, ""));
+            let body = resolve_references(item["body"].as_str().unwrap_or_default(), &results);
+
+            let item_request = RecordedRequest {
+                method,
+                path: format!("/{}", path.trim_start_matches('/')),
+                query: parse_query(query),
+                body: body.as_bytes().to_vec(),
+                form: parse_query(&body),
+                files: attached_files(item, request),
+                ..RecordedRequest::default()
+            };
+            let response = self.respond(item_request);
+
+            let succeeded = response.status == 200;
+            if let Some(name) = name {
+                if succeeded {
+                    let body = serde_json::from_str(&response.body).unwrap_or(Value::Null);
+                    results.insert(name.to_string(), body);
+                } else {
+                    failed.push(name.to_string());
+                }
+            }
+            // Facebook leaves out the responses the next operations refer to.
+            let referenced = name.is_some_and(|name| {
+                items[index + 1..].iter().any(|next| {
+                    next["depends_on"].as_str() == Some(name)
+                        || next.to_string().contains(&format!("{{result={}:", name))
+                })
+            });
+            let omit = item["omit_response_on_success"]
+                .as_bool()
+                .unwrap_or(referenced);
+            if succeeded && omit {
+                responses.push(Value::Null);
+            } else {
+                responses.push(json!({ "code": response.status, "body": response.body }));
+            }
+        }
+        MockResponse::ok(Value::Array(responses))
+    }
+}
+
+/// The files of a multipart batch request named in the `attached_files` of
+/// one of its items.
+fn attached_files(item: &Value, request: &RecordedRequest) -> HashMap<String, Vec<u8>> {
+    item["attached_files"]
+        .as_str()
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|name| request.files.get_key_value(name.trim()))
+        .map(|(name, bytes)| (name.clone(), bytes.clone()))
+        .collect()
+}
+
+/// Replace the `{result=name:$.path}` references of a batch operation with
+/// the values found in the response of the named operation, joined with
+/// commas. Only the `.field` and `.*` steps of JSONPath are supported.
+fn resolve_references(text: &str, results: &HashMap<String, Value>) -> String {
+    let mut resolved = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{result=") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        resolved.push_str(&rest[..start]);
+        let reference = &rest[start + "{result=".len()..start + end];
+        let (name, path) = reference.split_once(':').unwrap_or((reference, "$"));
+        let mut values: Vec<&Value> = results.get(name).into_iter().collect();
+        for step in path
+            .trim_start_matches('$')
+            .split('.')
+            .filter(|s| !s.is_empty())
+        {
+            values = values
+                .into_iter()
+                .flat_map(|value| match (step, value) {
+                    ("*", Value::Array(items)) => items.iter().collect(),
+                    (field, value) => value.get(field).into_iter().collect::<Vec<_>>(),
+                })
+                .collect();
+        }
+        let values: Vec<String> = values
+            .into_iter()
+            .map(|value| match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            })
+            .collect();
+        resolved.push_str(&values.join(","));
+        rest = &rest[start + end + 1..];
+    }
+    resolved.push_str(rest);
+    resolved
+}
+
+/// Answer `GET /?ids=` with a node for every id, failing above the limit of
+/// Facebook.
+fn ids(request: &RecordedRequest) -> MockResponse {
+    let ids: Vec<&str> = request
+        .param("ids")
+        .unwrap_or_default()
+        .split(',')
+        .filter(|id| !id.is_empty())
+        .collect();
+    if ids.len() > 50 {
+        return MockResponse::error(
+            400,
+            100,
+            "OAuthException",
+            "(#100) You can request a maximum of 50 ids",
+        );
+    }
+    let nodes: serde_json::Map<String, Value> = ids
+        .into_iter()
+        .map(|id| (id.to_string(), json!({ "id": id })))
+        .collect();
+    MockResponse::ok(Value::Object(nodes))
+}
+
+fn unsupported_request(method: &str, path: &str) -> MockResponse {
+    MockResponse::error(
+        400,
+        100,
+        "GraphMethodException",
+        &format!(
+            "Unsupported {} request. Object with path '{}' does not exist.",
+            method.to_lowercase(),
+            path
+        ),
+    )
+}
+
+fn lock(state: &Mutex<MockState>) -> MutexGuard<'_, MockState> {
+    // A panicking test thread must not break the other requests.
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+/// Remove the trailing slash and the API version from a path.
+fn normalize_path(path: &str) -> String {
+    let mut segments = segments(path);
+    if segments
+        .first()
+        .is_some_and(|s| s.starts_with('v') && s[1..].split('.').all(|n| n.parse::<u32>().is_ok()))
+    {
+        segments.remove(0);
+    }
+    format!("/{}", segments.join("/"))
+}
+
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern = segments(pattern);
+    let path = segments(path);
+    pattern.len() == path.len()
+        && pattern
+            .iter()
+            .zip(path.iter())
+            .all(|(expected, actual)| *expected == "*" || expected == actual)
+}
+
+fn decode(value: &str) -> String {
+    let value = value.replace('+', " ");
+    urlencoding::decode(&value)
+        .map(|decoded| decoded.into_owned())
+        .unwrap_or(value)
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(name), decode(value))
+        })
+        .collect()
+}
+
+fn handle_connection(stream: TcpStream, state: &Mutex<MockState>) {
+    let response = match read_request(&stream) {
+        Ok(request) => lock(state).respond(request),
+        Err(err) => MockResponse::error(400, 1, "MockServerException", &err.to_string()),
+    };
+    let _ = write_response(&stream, &response);
+}
+
+fn read_request(stream: &TcpStream) -> io::Result<RecordedRequest> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid request line",
+        ));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let body = if headers
+        .get("transfer-encoding")
+        .is_some_and(|encoding| encoding.contains("chunked"))
+    {
+        read_chunked_body(&mut reader)?
+    } else {
+        let length = headers
+            .get("content-length")
+            .and_then(|length| length.parse::<usize>().ok())
+            .unwrap_or_default();
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        body
+    };
+
+    let content_type = headers.get("content-type").cloned().unwrap_or_default();
+    let (form, files) = if content_type.starts_with("multipart/form-data") {
+        let boundary = content_type
+            .split(';')
+            .find_map(|param| param.trim().strip_prefix("boundary="))
+            .unwrap_or_default()
+            .trim_matches('"');
+        parse_multipart(&body, boundary)
+    } else if content_type.starts_with("application/x-www-form-urlencoded") {
+        (parse_query(&String::from_utf8_lossy(&body)), HashMap::new())
+    } else {
+        (HashMap::new(), HashMap::new())
+    };
+
+    Ok(RecordedRequest {
+        method: method.to_uppercase(),
+        path: path.to_string(),
+        query: parse_query(query),
+        headers,
+        body,
+        form,
+        files,
+    })
+}
+
+fn read_chunked_body<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut size_line = String::new();
+        reader.read_line(&mut size_line)?;
+        let size_hex = size_line.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let mut chunk = vec![0; size + 2];
+        reader.read_exact(&mut chunk)?;
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
+
+type MultipartFields = (HashMap<String, String>, HashMap<String, Vec<u8>>);
+
+fn parse_multipart(body: &[u8], boundary: &str) -> MultipartFields {
+    let mut form = HashMap::new();
+    let mut files = HashMap::new();
+    if boundary.is_empty() {
+        return (form, files);
+    }
+
+    let delimiter = format!("--{}", boundary);
+    for part in split_bytes(body, delimiter.as_bytes()) {
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+        let Some(header_end) = find_bytes(part, b"\r\n\r\n") else {
+            continue;
+        };
+        let part_headers = String::from_utf8_lossy(&part[..header_end]).to_string();
+        let content = &part[header_end + 4..];
+
+        let disposition = part_headers
+            .lines()
+            .find(|line| line.to_lowercase().starts_with("content-disposition"))
+            .unwrap_or_default();
+        let param = |name: &str| {
+            disposition
+                .split(';')
+                .find_map(|param| param.trim().strip_prefix(name))
+                .map(|value| value.trim_matches('"').to_string())
+        };
+        let Some(name) = param("name=") else {
+            continue;
+        };
+
+        if param("filename=").is_some() || part_headers.to_lowercase().contains("content-type") {
+            files.insert(name, content.to_vec());
+        } else {
+            form.insert(name, String::from_utf8_lossy(content).to_string());
+        }
+    }
+    (form, files)
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn split_bytes<'a>(mut bytes: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut parts = Vec::new();
+    while let Some(position) = find_bytes(bytes, delimiter) {
+        parts.push(&bytes[..position]);
+        bytes = &bytes[position + delimiter.len()..];
+    }
+    parts.push(bytes);
+    parts
+}
+
+fn write_response(mut stream: &TcpStream, response: &MockResponse) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason_phrase(response.status),
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    http::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("Unknown")
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod tests {
+    use super::*;
+    use crate::prelude::errors::ClientErr;
+    use crate::prelude::{BatchItems, TokenLiveType};
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/v23.0/me/accounts/"), "/me/accounts");
+        assert_eq!(normalize_path("/debug_token"), "/debug_token");
+        assert_eq!(normalize_path("/v23.0"), "/");
+        assert!(path_matches("/*/feed", "/1234/feed"));
+        assert!(!path_matches("/*/feed", "/1234/photos"));
+    }
+
+    #[test]
+    fn test_parse_multipart() {
+        let body = b"--xyz\r\nContent-Disposition: form-data; name=\"upload_phase\"\r\n\r\ntransfer\r\n--xyz\r\nContent-Disposition: form-data; name=\"video_file_chunk\"; filename=\"chunk\"\r\nContent-Type: application/octet-stream\r\n\r\n\x00\x01\x02\r\n--xyz--\r\n";
+        let (form, files) = parse_multipart(body, "xyz");
+
+        assert_eq!(
+            form.get("upload_phase").map(String::as_str),
+            Some("transfer")
+        );
+        assert_eq!(files.get("video_file_chunk"), Some(&vec![0, 1, 2]));
+    }
+
+    #[tokio::test]
+    async fn test_default_accounts_response() {
+        let server = MockGraphServer::start().unwrap();
+
+        let accounts = server
+            .client("page_token".to_string())
+            .accounts(TokenLiveType::LONGLIVE)
+            .pages_by_me()
+            .await
+            .unwrap();
+
+        assert_eq!(accounts.data.len(), 1);
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/v23.0/me/accounts");
+        assert_eq!(requests[0].access_token(), Some("page_token"));
+        assert_eq!(requests[0].param("access_token"), None);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_error() {
+        let server = MockGraphServer::start().unwrap();
+        server.mock_once(
+            "POST",
+            "/*/media_publish",
+            MockResponse::error(400, 190, "OAuthException", "Invalid OAuth access token."),
+        );
+        let publish = || {
+            server
+                .client("page_token".to_string())
+                .instagram_content_publishing("1234".to_string())
+                .publish_container("5678".to_string())
+        };
+
+        match publish().await {
+            Err(ClientErr::Facebook(error)) => assert_eq!(error.error.code(), 190),
+            other => panic!("expected a facebook error, got {:?}", other),
+        }
+
+        // The scripted response is only used once.
+        let media = publish().await.unwrap();
+        assert!(!media.id.is_empty());
+        assert_eq!(server.requests()[1].param("creation_id"), Some("5678"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_items_use_the_routes() {
+        let server = MockGraphServer::start().unwrap();
+        server.mock(
+            "GET",
+            "/me",
+            MockResponse::error(400, 190, "OAuthException", "Invalid OAuth access token."),
+        );
+        let batch_items = BatchItems::default()
+            .add_batch_item_without_body("GET", "me")
+            .add_batch_item_without_body("GET", "1234");
+
+        let responses = server
+            .client("page_token".to_string())
+            .batch_request("1234".to_string())
+            .request(batch_items)
+            .await
+            .unwrap();
+
+        assert_eq!(responses[0].as_ref().unwrap().code, 400);
+        assert_eq!(responses[1].as_ref().unwrap().code, 200);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_token_endpoints() {
+        let server = MockGraphServer::start().unwrap();
+        let user_token = server
+            .client("".to_string())
+            .add_app_secret("app_secret".to_string())
+            .user_token();
+
+        let information = user_token
+            .debug_access_token("app_token".to_string(), "user_token".to_string())
+            .await
+            .unwrap();
+        assert!(information.is_valid);
+
+        let exchange = user_token
+            .exchange_code_for_access_token_at_server(
+                "code".to_string(),
+                "secret".to_string(),
+                "app_id".to_string(),
+                "http://localhost".to_string(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(exchange.access_token(), "mock_access_token");
+        assert_eq!(server.requests()[1].param("code"), Some("code"));
+        // The requests are signed by the connection of the client.
+        assert!(server.requests()[0].param("appsecret_proof").is_some());
+    }
+}
@@ -1,11 +1,13 @@
 use crate::prelude::utils::UploadingData;
 use crate::prelude::video::VideoParams;
 use crate::universal::errors::ClientErr;
-
+#[cfg(feature = "reqwest")]
+use crate::universal::form_data::MultipartData;
 
 use async_trait::async_trait;
 use http::{HeaderMap, Request, Response};
 
+use std::time::Duration;
 use url::Url;
 #[cfg(any(feature = "web-sys"))]
 use web_sys::FormData;
@@ -19,13 +21,23 @@ compile_error!(
 #[cfg(all(feature = "reqwest", feature = "web-sys"))]
 compile_error!(r#"only one of features "reqwest_async", "seed_async" and "..." can be"#);
 
+pub mod app_secret_proof;
+#[cfg(feature = "reqwest")]
+pub mod cassette;
 pub mod client;
+pub mod dry_run;
 pub mod errors;
 #[cfg(any(feature = "reqwest"))]
 pub mod form_data;
+#[cfg(any(test, feature = "mock-server"))]
+pub mod mock_server;
+pub(crate) mod request_url;
 #[cfg(any(feature = "reqwest"))]
 pub mod reqwest;
 pub mod response;
+pub mod retry;
+pub mod secret;
+pub mod usage;
 
 #[cfg(any(feature = "web-sys"))]
 pub mod web_sys_client;
@@ -42,12 +54,8 @@ pub trait HttpClient: Sync + Clone {
         Self: Sized,
         T: Into<String> + Send,
     {
-        self.request(
-            Request::get(url.to_string())
-                .body(request_body.into())
-                .unwrap(),
-        )
-        .await
+        self.request(Request::get(url.to_string()).body(request_body.into())?)
+            .await
     }
 
     #[inline]
@@ -56,12 +64,8 @@ pub trait HttpClient: Sync + Clone {
         Self: Sized,
         T: Into<String> + Send,
     {
-        self.request(
-            Request::post(url.to_string())
-                .body(request_body.into())
-                .unwrap(),
-        )
-        .await
+        self.request(Request::post(url.to_string()).body(request_body.into())?)
+            .await
     }
 
     #[cfg(any(feature = "web-sys"))]
@@ -71,7 +75,7 @@ pub trait HttpClient: Sync + Clone {
         url: Url,
         request_body: FormData,
     ) -> Result<Response<String>, ClientErr> {
-        self.video_request(Request::post(url.to_string()).body(request_body).unwrap())
+        self.video_request(Request::post(url.to_string()).body(request_body)?)
             .await
     }
 
@@ -82,7 +86,7 @@ pub trait HttpClient: Sync + Clone {
         url: Url,
         request_body: VideoParams,
     ) -> Result<Response<String>, ClientErr> {
-        self.video_request(Request::post(url.to_string()).body(request_body).unwrap())
+        self.video_request(Request::post(url.to_string()).body(request_body)?)
             .await
     }
 
@@ -93,7 +97,7 @@ pub trait HttpClient: Sync + Clone {
         url: Url,
         request_body: UploadingData,
     ) -> Result<Response<String>, ClientErr> {
-        self.resumable_video_request(Request::post(url.to_string()).body(request_body).unwrap())
+        self.resumable_video_request(Request::post(url.to_string()).body(request_body)?)
             .await
     }
 
@@ -104,7 +108,7 @@ pub trait HttpClient: Sync + Clone {
         url: Url,
         request_body: (Vec<u8>, VideoParams),
     ) -> Result<Response<String>, ClientErr> {
-        self.upload_by_form_data_request(Request::post(url.to_string()).body(request_body).unwrap())
+        self.upload_by_form_data_request(Request::post(url.to_string()).body(request_body)?)
             .await
     }
 
@@ -114,12 +118,8 @@ pub trait HttpClient: Sync + Clone {
         Self: Sized,
         T: Into<String> + Send,
     {
-        self.request(
-            Request::put(url.to_string())
-                .body(request_body.into())
-                .unwrap(),
-        )
-        .await
+        self.request(Request::put(url.to_string()).body(request_body.into())?)
+            .await
     }
 
     #[inline]
@@ -128,12 +128,8 @@ pub trait HttpClient: Sync + Clone {
         Self: Sized,
         T: Into<String> + Send,
     {
-        self.request(
-            Request::delete(url.to_string())
-                .body(request_body.into())
-                .unwrap(),
-        )
-        .await
+        self.request(Request::delete(url.to_string()).body(request_body.into())?)
+            .await
     }
 
     #[inline]
@@ -142,12 +138,8 @@ pub trait HttpClient: Sync + Clone {
         Self: Sized,
         T: Into<String> + Send,
     {
-        self.request(
-            Request::patch(url.to_string())
-                .body(request_body.into())
-                .unwrap(),
-        )
-        .await
+        self.request(Request::patch(url.to_string()).body(request_body.into())?)
+            .await
     }
 
     #[inline]
@@ -156,12 +148,8 @@ pub trait HttpClient: Sync + Clone {
         Self: Sized,
         T: Into<String> + Send,
     {
-        self.request(
-            Request::head(url.to_string())
-                .body(request_body.into())
-                .unwrap(),
-        )
-        .await
+        self.request(Request::head(url.to_string()).body(request_body.into())?)
+            .await
     }
 
     #[inline]
@@ -170,12 +158,18 @@ pub trait HttpClient: Sync + Clone {
         Self: Sized,
         T: Into<String> + Send,
     {
-        self.request(
-            Request::options(url.to_string())
-                .body(request_body.into())
-                .unwrap(),
-        )
-        .await
+        self.request(Request::options(url.to_string()).body(request_body.into())?)
+            .await
+    }
+
+    /// Wait before a request is retried, see
+    /// [RetryPolicy](retry::RetryPolicy). Clients which never hit the network,
+    /// e.g. in tests, can return at once.
+    async fn sleep(&self, duration: Duration)
+    where
+        Self: Sized,
+    {
+        retry::sleep(duration).await
     }
 
     async fn request(&self, request: Request<String>) -> Result<Response<String>, ClientErr>
@@ -216,4 +210,20 @@ pub trait HttpClient: Sync + Clone {
     ) -> Result<Response<String>, ClientErr>
     where
         Self: Sized;
+
+    /// Send a multipart request, e.g. a photo or a video read from memory.
+    /// Clients which do not send files can keep this default, which fails.
+    #[cfg(feature = "reqwest")]
+    async fn multipart_request(
+        &self,
+        request: Request<MultipartData>,
+    ) -> Result<Response<String>, ClientErr>
+    where
+        Self: Sized,
+    {
+        Err(ClientErr::HttpClient(format!(
+            "multipart requests are not supported by this client: {}",
+            request.uri()
+        )))
+    }
 }
@@ -0,0 +1,284 @@
+//! The urls of the requests sent by the APIs of the crate.
+//!
+//! A [RequestUrl](RequestUrl) takes the graph url of a node, with its `EDGE`
+//! placeholder, and adds the parameters of a request as typed pairs. The
+//! values, e.g. a message, a caption or a link given by the caller, are
+//! percent encoded, so they can not break the url or add other parameters.
+//!
+//! The ids of the nodes and the edges are added as path segments, see
+//! [node_url](node_url), and are percent encoded too.
+
+use crate::prelude::fields::FieldSelection;
+use url::{form_urlencoded, Url};
+
+/// The last path segment of the graph url of a node, replaced by the edge of
+/// a request.
+const EDGE: &str = "EDGE";
+
+/// The end of the graph url of a client, `<host>/<version>/NODE/EDGE`.
+const NODE_EDGE: &str = "/NODE/EDGE";
+
+/// The graph url of `node`, from the graph url of a client, which may already
+/// have a query. The id is one percent encoded path segment, so an id with a
+/// `/`, a `?` or a `#` can not change the path or the query of the url. The
+/// `EDGE` placeholder is kept for [RequestUrl::edge](RequestUrl::edge).
+pub(crate) fn node_url(graph: &str, node: &str) -> String {
+    graph_path(graph, &[node, EDGE])
+}
+
+/// The url of a path of the Graph API, e.g. `["oauth", "access_token"]`, from
+/// the graph url of a client.
+pub(crate) fn graph_path(graph: &str, segments: &[&str]) -> String {
+    let (path, query) = split_query(graph);
+    let root = path.strip_suffix(NODE_EDGE).unwrap_or(path);
+    push_segments(&with_query(root, query), segments)
+}
+
+/// Append percent encoded path segments to an url, keeping its query.
+///
+/// An url which can not be parsed gets the segments appended to its path, so
+/// the connection returns it as
+/// [ClientErr::InvalidUrl](crate::prelude::errors::ClientErr::InvalidUrl).
+fn push_segments(url: &str, segments: &[&str]) -> String {
+    let mut parsed = match Url::parse(url) {
+        Ok(parsed) if !parsed.cannot_be_a_base() => parsed,
+        _ => {
+            let (path, query) = split_query(url);
+            let path = segments.iter().fold(path.to_string(), |path, segment| {
+                path + "/" + &urlencoding::encode(segment)
+            });
+            return with_query(&path, query);
+        }
+    };
+    if let Ok(mut path) = parsed.path_segments_mut() {
+        path.pop_if_empty().extend(segments);
+    }
+    parsed.into()
+}
+
+/// The url without its `EDGE` placeholder, when its path ends with it.
+fn strip_edge(url: &str) -> Option<String> {
+    let (path, query) = split_query(url);
+    let path = path.strip_suffix(EDGE)?.strip_suffix('/')?;
+    Some(with_query(path, query))
+}
+
+fn split_query(url: &str) -> (&str, Option<&str>) {
+    match url.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (url, None),
+    }
+}
+
+fn with_query(path: &str, query: Option<&str>) -> String {
+    match query {
+        Some(query) => format!("{}?{}", path, query),
+        None => path.to_string(),
+    }
+}
+
+/// The url of a request, built from a graph url and its parameters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct RequestUrl {
+    base_url: String,
+    params: Vec<(String, String)>,
+}
+
+impl RequestUrl {
+    /// A request to `base_url`, which may already have a query.
+    pub(crate) fn new(base_url: impl Into<String>) -> Self {
+        RequestUrl {
+            base_url: base_url.into(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Replace the `EDGE` placeholder at the end of the path, e.g. with
+    /// `"feed"` or `"insights/page_fans"`. Each segment of the edge is
+    /// percent encoded.
+    pub(crate) fn edge(self, edge: &str) -> Self {
+        let segments: Vec<&str> = edge.split('/').collect();
+        self.segments(&segments)
+    }
+
+    /// Replace the `EDGE` placeholder at the end of the path with several
+    /// path segments, e.g. an id given by the caller and an edge, each one
+    /// percent encoded.
+    pub(crate) fn segments(mut self, segments: &[&str]) -> Self {
+        if let Some(url) = strip_edge(&self.base_url) {
+            self.base_url = push_segments(&url, segments);
+        }
+        self
+    }
+
+    /// Remove the `EDGE` placeholder, to send the request to the node itself.
+    pub(crate) fn node(mut self) -> Self {
+        if let Some(url) = strip_edge(&self.base_url) {
+            self.base_url = url;
+        }
+        self
+    }
+
+    /// Add a parameter, percent encoded.
+    pub(crate) fn param(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add several parameters, percent encoded.
+    pub(crate) fn params<K, V>(mut self, params: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: AsRef<str>,
+        V: ToString,
+    {
+        for (name, value) in params {
+            self = self.param(name.as_ref(), value);
+        }
+        self
+    }
+
+    /// Add a parameter, unless its value is empty.
+    pub(crate) fn non_empty_param(self, name: &str, value: impl ToString) -> Self {
+        let value = value.to_string();
+        if value.is_empty() {
+            self
+        } else {
+            self.param(name, value)
+        }
+    }
+
+    /// Add a parameter when it has a value, and its value is not empty.
+    pub(crate) fn optional_param<V: ToString>(self, name: &str, value: Option<V>) -> Self {
+        match value {
+            Some(value) => self.non_empty_param(name, value),
+            None => self,
+        }
+    }
+
+    /// Add the `fields` parameter, see [FieldSelection](FieldSelection).
+    pub(crate) fn fields(self, fields: &FieldSelection) -> Self {
+        self.param("fields", fields)
+    }
+
+    /// Add the `access_token` parameter, unless the token is empty. It is
+    /// moved to the `Authorization` header when the request is sent.
+    pub(crate) fn access_token(self, access_token: &str) -> Self {
+        self.non_empty_param("access_token", access_token)
+    }
+
+    /// The url with its parameters.
+    ///
+    /// An url which can not be parsed is kept as it is, with the parameters
+    /// appended, so the connection returns it as
+    /// [ClientErr::InvalidUrl](crate::prelude::errors::ClientErr::InvalidUrl).
+    pub(crate) fn build(self) -> String {
+        if self.params.is_empty() {
+            return self.base_url;
+        }
+        match Url::parse(&self.base_url) {
+            Ok(mut url) => {
+                url.query_pairs_mut().extend_pairs(&self.params);
+                url.into()
+            }
+            Err(_) => {
+                let query = form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(&self.params)
+                    .finish();
+                let separator = if self.base_url.contains('?') {
+                    "&"
+                } else {
+                    "?"
+                };
+                self.base_url + separator + &query
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::fields::Field;
+
+    #[test]
+    fn test_user_text_is_encoded() {
+        let url = RequestUrl::new("https://graph.facebook.com/v23.0/1234/EDGE")
+            .edge("feed")
+            .param("message", "Tom & Jerry #1 = 100% fun?")
+            .non_empty_param("place", "")
+            .optional_param::<String>("tags", None)
+            .fields(&FieldSelection::new().field(Field::new("comments").modifier("limit", 5)))
+            .access_token("a+b")
+            .build();
+        assert_eq!(
+            url,
+            "https://graph.facebook.com/v23.0/1234/feed?message=Tom+%26+Jerry+%231+%3D+100%25+fun%3F\
+             &fields=comments.limit%285%29&access_token=a%2Bb"
+        );
+
+        let pairs: Vec<(String, String)> = Url::parse(&url)
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect();
+        assert_eq!(pairs[0].1, "Tom & Jerry #1 = 100% fun?");
+        assert_eq!(pairs.len(), 3);
+    }
+
+    #[test]
+    fn test_parameters_are_added_to_an_existing_query() {
+        assert_eq!(
+            RequestUrl::new("https://x/ig_hashtag_search?user_id=1")
+                .param("q", "rust&lang")
+                .build(),
+            "https://x/ig_hashtag_search?user_id=1&q=rust%26lang"
+        );
+        assert_eq!(
+            RequestUrl::new("not a host/v23.0/1234?a=1")
+                .param("b", "c d")
+                .build(),
+            "not a host/v23.0/1234?a=1&b=c+d"
+        );
+        assert_eq!(RequestUrl::new("https://x/1234").build(), "https://x/1234");
+    }
+
+    #[test]
+    fn test_node_and_edge_are_path_segments() {
+        let graph = "https://graph.facebook.com/v23.0/NODE/EDGE";
+        assert_eq!(
+            RequestUrl::new(node_url(graph, "PLEDGE"))
+                .edge("feed")
+                .build(),
+            "https://graph.facebook.com/v23.0/PLEDGE/feed"
+        );
+        assert_eq!(
+            RequestUrl::new(node_url(graph, "EDGE")).node().build(),
+            "https://graph.facebook.com/v23.0/EDGE"
+        );
+        assert_eq!(
+            RequestUrl::new(node_url(graph, "12/34?fields=x#y"))
+                .edge("insights/page_fans")
+                .build(),
+            "https://graph.facebook.com/v23.0/12%2F34%3Ffields=x%23y/insights/page_fans"
+        );
+        assert_eq!(
+            RequestUrl::new(graph_path(graph, &[EDGE]))
+                .param("user_id", "1")
+                .segments(&["a/b", "recent_media"])
+                .build(),
+            "https://graph.facebook.com/v23.0/a%2Fb/recent_media?user_id=1"
+        );
+        assert_eq!(
+            node_url("https://x/v23.0/NODE/EDGE?access_token=a", "me"),
+            "https://x/v23.0/me/EDGE?access_token=a"
+        );
+        assert_eq!(
+            graph_path(graph, &["oauth", "access_token"]),
+            "https://graph.facebook.com/v23.0/oauth/access_token"
+        );
+        assert_eq!(
+            node_url("not a host/v23.0/NODE/EDGE", "a/b"),
+            "not a host/v23.0/a%2Fb/EDGE"
+        );
+    }
+}
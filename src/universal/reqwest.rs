@@ -1,7 +1,6 @@
 //! Reqwest HTTP client
 use crate::universal::errors::ClientErr;
 use std::convert::TryInto;
-use std::io::Read;
 // use crate::universal::utils::generic_req;
 use crate::universal::HttpClient;
 #[cfg(feature = "reqwest")]
@@ -12,16 +11,51 @@ use http::Method;
 
 // use reqwest::multipart;
 use crate::prelude::video::VideoParams;
-use crate::universal::form_data::create_form_data;
+use crate::universal::form_data::{create_form_data, multipart_form, progress_part, MultipartData};
 
+use crate::prelude::file_analyze::FileResultServer;
+use crate::prelude::media_upload::progress::RequestProgress;
 use crate::prelude::utils::UploadingData;
-
+use reqwest::multipart::Form;
+use std::fs::File;
+
+/// An [HttpClient](HttpClient) backed by a [reqwest::Client](Client).
+///
+/// The inner client keeps a connection pool, so it should be created once and
+/// shared. To set timeouts, proxies or a user agent build the reqwest client
+/// yourself and pass it to [from_client](ReqwestClient::from_client).
+///
+/// # Example
+/// ```
+/// use facebook_api_rs::prelude::reqwest::ReqwestClient;
+/// use facebook_api_rs::prelude::{Client, UserToken};
+/// use std::time::Duration;
+///
+/// let reqwest_client = reqwest::Client::builder()
+///     .timeout(Duration::from_secs(30))
+///     .user_agent("my-app/1.0")
+///     .build()
+///     .unwrap();
+///
+/// let client = Client::new(UserToken::default(), "page_token".to_string())
+///     .add_http_client(ReqwestClient::from_client(reqwest_client));
+/// ```
 #[derive(Debug, Clone)]
 pub struct ReqwestClient {
     pub client: Client,
     pub headers: HeaderMap,
 }
 
+impl ReqwestClient {
+    /// Create a client from an already configured [reqwest::Client](Client).
+    pub fn from_client(client: Client) -> Self {
+        ReqwestClient {
+            client,
+            headers: HeaderMap::new(),
+        }
+    }
+}
+
 #[async_trait(?Send)]
 impl HttpClient for ReqwestClient {
     fn new<U: Into<Option<HeaderMap>>>(headers: U) -> Result<Self, ClientErr> {
@@ -45,7 +79,9 @@ impl HttpClient for ReqwestClient {
         // No version on the response when using from client but works when using from
         // server (backend)
         let version = request.version().clone();
-        let req = request.try_into().unwrap();
+        let req = request
+            .try_into()
+            .map_err(|e: reqwest::Error| ClientErr::InvalidRequest(format!("{}", e)))?;
 
         let resp = self
             .client
@@ -85,13 +121,14 @@ impl HttpClient for ReqwestClient {
         let body = request.body().to_owned();
 
         let req = match method {
-            Method::GET => Client::new().get(url),
-            Method::POST => Client::new().post(url),
-            Method::PUT => Client::new().put(url),
-            Method::DELETE => Client::new().delete(url),
+            Method::GET => self.client.get(url),
+            Method::POST => self.client.post(url),
+            Method::PUT => self.client.put(url),
+            Method::DELETE => self.client.delete(url),
 
             m @ _ => return Err(ClientErr::HttpClient(format!("invalid method {}", m))),
         };
+        let req = req.headers(request.headers().clone());
         let resp = req
             .multipart(create_form_data(body, Vec::new()))
             .send()
@@ -130,83 +167,29 @@ impl HttpClient for ReqwestClient {
         // let req: RequestBuilder = generic_req::<FormData>(request).unwrap();
         let url = request.uri().to_string();
         let method = request.method().clone();
-        let body = request.body().to_owned();
-        //  let test = Form::from(request.body().as_ref());
-        // let ne_test: Form = request.into_body();
 
         let req = match method {
-            Method::GET => Client::new().get(url),
-            Method::POST => Client::new().post(url),
-            Method::PUT => Client::new().put(url),
-            Method::DELETE => Client::new().delete(url),
-            Method::PATCH => Client::new().patch(url),
+            Method::GET => self.client.get(url),
+            Method::POST => self.client.post(url),
+            Method::PUT => self.client.put(url),
+            Method::DELETE => self.client.delete(url),
+            Method::PATCH => self.client.patch(url),
             // Method::CONNECT => Client::new().connect(url),
-            Method::HEAD => Client::new().head(url),
+            Method::HEAD => self.client.head(url),
             //   Method::OPTIONS => Client::new().option(url),
             m @ _ => return Err(ClientErr::HttpClient(format!("invalid method {}", m))),
         };
-
-        // tsting
-
-        let resp = if body.upload_phase == "start".to_string() {
-            let response = req
-                .send()
-                .await
-                .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
-            response
-        } else if body.upload_phase == "transfer".to_string() {
-            use std::fs::File;
-
-            let mut file = File::open(body.file_path.clone()).unwrap();
-            let mut buffer = [0; 1048576];
-            //  let stream = FramedRead::new(file, BytesCodec::new());
-
-            file.read_exact(&mut buffer).unwrap();
-            // let stream_body = FramedRead::new(Cursor::new(buffer), BytesCodec::new());
-            //  let stream =
-            // reqwest::Body::wrap_stream(stream::once(future::ready(Ok::<_,
-            // reqwest::Error>( "part1 part2".to_owned(),
-            // ))));
-            //   let stream = reqwest::Body::wrap_stream(stream_body);
-            //  let bytes = Bytes::from(buffer.to_vec());
-
-            // let mut reader = BufReader::new(&*test.chun).buffer();
-            // let stream_part = Part::bytes(buffer.to_vec());
-            let part = reqwest::multipart::Part::bytes(buffer.to_vec());
-
-            let form = reqwest::multipart::Form::new().part("video_file_chunk", part);
-            // println!("form data  :{:?}", form);
-            let response = req
-                .multipart(form)
-                .send()
-                .await
-                .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
-            response
-        } else if body.upload_phase == "finish".to_string() {
-            let response = req
-                .send()
-                .await
-                .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
-            response
-        } else if body.upload_phase == "cancel".to_string() {
-            // Todo: impliment this
-            let response = req
-                .send()
-                .await
-                .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
-            response
-        } else {
-            let response = req
-                .send()
-                .await
-                .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
-            response
-        };
+        let form = resumable_form(request.body(), request.extensions().get())?;
+        let resp = req
+            .headers(request.headers().clone())
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
 
         let status_code = resp.status();
         let headers = resp.headers().clone();
         let version = request.version();
-        // println!("raw resp.{:?}", resp);
         let content = resp
             .text()
             .await
@@ -233,25 +216,26 @@ impl HttpClient for ReqwestClient {
         let (buffer, params) = request.body().clone();
 
         let req = match method {
-            Method::GET => Client::new().get(url),
-            Method::POST => Client::new().post(url),
-            Method::PUT => Client::new().put(url),
-            Method::DELETE => Client::new().delete(url),
-            Method::PATCH => Client::new().patch(url),
-            Method::HEAD => Client::new().head(url),
+            Method::GET => self.client.get(url),
+            Method::POST => self.client.post(url),
+            Method::PUT => self.client.put(url),
+            Method::DELETE => self.client.delete(url),
+            Method::PATCH => self.client.patch(url),
+            Method::HEAD => self.client.head(url),
             m @ _ => return Err(ClientErr::HttpClient(format!("invalid method {}", m))),
         };
+        let req = req.headers(request.headers().clone());
 
-        let part = reqwest::multipart::Part::bytes(buffer);
+        let part = progress_part(buffer, request.extensions().get());
 
         let form = reqwest::multipart::Form::new()
             .part("source", part)
             .text("description", params.clone().description)
-            .text("description", params.clone().title);
+            .text("title", params.clone().title);
 
         let resp = req
             //.form(&form)
-            .multipart( form)
+            .multipart(form)
             .send()
             .await
             .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
@@ -264,7 +248,6 @@ impl HttpClient for ReqwestClient {
             .text()
             .await
             .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
-        println!("facebook api {}", content.clone());
 
         let mut build = http::Response::builder();
         for header in headers.iter() {
@@ -277,4 +260,87 @@ impl HttpClient for ReqwestClient {
             .body(content)
             .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))
     }
+    async fn multipart_request(
+        &self,
+        request: http::Request<MultipartData>,
+    ) -> Result<http::Response<String>, ClientErr> {
+        let method = reqwest::Method::from_bytes(request.method().as_str().as_bytes())
+            .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
+        let form = multipart_form(request.body(), request.extensions().get());
+        let resp = self
+            .client
+            .request(method, request.uri().to_string())
+            .headers(request.headers().clone())
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
+
+        let status_code = resp.status();
+        let headers = resp.headers().clone();
+        let content = resp
+            .text()
+            .await
+            .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))?;
+
+        let mut build = http::Response::builder();
+        for header in headers.iter() {
+            build = build.header(header.0, header.1);
+        }
+        build
+            .status(status_code)
+            .version(request.version())
+            .body(content)
+            .map_err(|e| ClientErr::HttpClient(format!("{:?}", e)))
+    }
+}
+
+/// The multipart form of one request of a resumable upload. The chunk of a
+/// `transfer` request is read from the file when the data has no chunk.
+fn resumable_form(
+    data: &UploadingData,
+    progress: Option<&RequestProgress>,
+) -> Result<Form, ClientErr> {
+    let mut form = Form::new().text("upload_phase", data.upload_phase.clone());
+    if !data.upload_session_id.is_empty() {
+        form = form.text("upload_session_id", data.upload_session_id.clone());
+    }
+
+    let form = match data.upload_phase.as_str() {
+        "start" => form.text("file_size", data.file_size.to_string()),
+        "transfer" => {
+            let chunk = if data.chunk.is_empty() && !data.file_path.is_empty() {
+                File::open(&data.file_path)
+                    .and_then(FileResultServer::from_file)
+                    .and_then(|mut file| file.chunk_file(data.start_offset, data.end_offset))
+                    .map_err(|e| {
+                        ClientErr::CustomError(format!("can not read the video chunk: {}", e))
+                    })?
+            } else {
+                data.chunk.clone()
+            };
+            form.text("start_offset", data.start_offset.to_string())
+                .part(
+                    "video_file_chunk",
+                    progress_part(chunk, progress).file_name("video_file_chunk"),
+                )
+        }
+        "finish" => {
+            if let Some(thumb) = &data.thumb {
+                form = form.part(
+                    "thumb",
+                    progress_part(thumb.clone(), None).file_name("thumb"),
+                );
+            }
+            if !data.description.is_empty() {
+                form = form.text("description", data.description.clone());
+            }
+            if !data.title.trim().is_empty() {
+                form = form.text("title", data.title.clone());
+            }
+            form
+        }
+        _ => form,
+    };
+    Ok(form)
 }
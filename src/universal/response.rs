@@ -1,12 +1,10 @@
-use http::StatusCode;
-
 use serde::{
     de::{self, DeserializeOwned, Deserializer},
     Deserialize,
 };
 use serde_json::value::Value;
 
-use crate::universal::errors::{ClientErr, FacebookAPiError};
+use crate::universal::errors::{ClientErr, ErrorResponse, FacebookAPiError};
 
 // @todo Review the response methods and refactor  the response method to have just one method
 
@@ -16,28 +14,11 @@ pub fn deserialize_response_handler<T>(
 where
     T: DeserializeOwned,
 {
-    match response {
-        Ok(result) => {
-            match result.status() {
-                StatusCode::OK => {
-                    let result = deserialize_response::<T>(result.body())?;
-                    Ok(result)
-                }
-                StatusCode::BAD_REQUEST => Err(client_error_deserialize_response(result.body())?),
-                _ => {
-                    //  let result = deserialize_response::<T>(result.body())?;
-                    //@Todo:  handle the possible response error
-                    Err(ClientErr::CustomError(format!(
-                        "unhandled facebook response error.  response: {}",
-                        result.body().to_string(),
-                    )))
-                }
-            }
-        }
-        Err(err) => {
-            //  let result = deserialize_response::<T>(result.body())?;
-            Err(err)
-        }
+    let response = response?;
+    if response.status().is_success() {
+        deserialize_response::<T>(response.body())
+    } else {
+        Err(error_response(response))
     }
 }
 
@@ -47,25 +28,29 @@ pub fn deserialize_batch_handler<T>(
 where
     T: DeserializeOwned,
 {
-    match response {
-        Ok(result) => {
-            match result.status() {
-                StatusCode::OK => {
-                    let result = deserialize_response_batch::<T>(result.body())?;
-                    Ok(result)
-                }
-                StatusCode::BAD_REQUEST => Err(client_error_deserialize_response(result.body())?),
-                _ => {
-                    //  let result = deserialize_response::<T>(result.body())?;
-                    //@Todo:  handle the possible response error
-                    Err(ClientErr::CustomError(format!(
-                        "unhandled facebook response error.  response: {}",
-                        result.body().to_string(),
-                    )))
-                }
-            }
+    let response = response?;
+    if response.status().is_success() {
+        deserialize_response_batch::<T>(response.body())
+    } else {
+        Err(error_response(response))
+    }
+}
+
+/// The error of a response with an error status: the error of Facebook in
+/// its body, or the response itself when its body is not one.
+pub(crate) fn error_response(response: http::Response<String>) -> ClientErr {
+    let (parts, body) = response.into_parts();
+    let response = Box::new(ErrorResponse {
+        status: parts.status,
+        headers: parts.headers,
+        body,
+    });
+    match serde_json::from_str::<FacebookAPiError>(&response.body) {
+        Ok(mut error) => {
+            error.response = Some(response);
+            ClientErr::from(error)
         }
-        Err(err) => Err(err),
+        Err(_) => ClientErr::UnexpectedResponse(response),
     }
 }
 
@@ -86,11 +71,6 @@ where
     Ok(response)
 }
 
-fn client_error_deserialize_response(text: &str) -> Result<ClientErr, ClientErr> {
-    let error: FacebookAPiError = serde_json::from_str(text)?;
-    Err(ClientErr::Facebook(error))
-}
-
 #[derive(Debug)]
 pub(crate) enum Response<T> {
     Ok(T),
@@ -132,3 +112,53 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::errors::{ClientErr, ErrorKind};
+    use crate::prelude::mock_server::{MockGraphServer, MockResponse};
+    use crate::prelude::retry::RetryPolicy;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn test_errors_are_decoded_for_every_status() {
+        let server = MockGraphServer::start().unwrap();
+        server.mock_once(
+            "GET",
+            "/1234",
+            MockResponse::error(403, 200, "OAuthException", "(#200) Permissions error")
+                .with_header("x-fb-trace-id", "AbCdEf")
+                .with_header("x-fb-debug", "debug_id"),
+        );
+        server.mock_once(
+            "GET",
+            "/5678",
+            MockResponse::json(503, json!("Service Unavailable")),
+        );
+        let client = || {
+            server
+                .client("page_token".to_string())
+                .add_retry_policy(RetryPolicy::none())
+        };
+
+        let error = client().node("1234").get::<Value>().await.unwrap_err();
+        assert_eq!(error.kind(), Some(ErrorKind::PermissionDenied));
+        let ClientErr::Facebook(error) = error else {
+            panic!("not an error of Facebook: {:?}", error);
+        };
+        assert_eq!(error.status().map(|status| status.as_u16()), Some(403));
+        assert_eq!(error.fbtrace_id(), Some("mock_fbtrace_id"));
+        let response = error.response.unwrap();
+        assert_eq!(response.trace_id(), Some("AbCdEf"));
+        assert_eq!(response.debug_id(), Some("debug_id"));
+        assert!(response.body.contains("Permissions error"));
+
+        let error = client().node("5678").get::<Value>().await.unwrap_err();
+        assert!(error.is_retryable());
+        let ClientErr::UnexpectedResponse(response) = error else {
+            panic!("not an unexpected response: {:?}", error);
+        };
+        assert_eq!(response.status.as_u16(), 503);
+        assert_eq!(response.body, "\"Service Unavailable\"");
+    }
+}
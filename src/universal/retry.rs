@@ -0,0 +1,383 @@
+//! Retry of the requests which failed with a transient error.
+//!
+//! Every request sent through a
+//! [GenericClientConnection](crate::prelude::GenericClientConnection) is
+//! retried according to its [RetryPolicy](RetryPolicy), with an exponential
+//! backoff and jitter between the attempts. A request is retried when:
+//!
+//! * Facebook answers with a [retryable](crate::prelude::errors::ErrorKind::is_retryable)
+//!   error: a rate limit, one of the [transient error
+//!   codes](TRANSIENT_ERROR_CODES) or an error with `is_transient: true`
+//! * the server answers an idempotent request, e.g. a GET or a DELETE, with
+//!   a `429 Too Many Requests` or a `5xx` status
+//! * the request could not be sent at all, if
+//!   [with_retry_connection_errors](RetryPolicy::with_retry_connection_errors)
+//!   is set
+//!
+//! When the response tells how long to wait, with a `Retry-After` header or
+//! the `estimated_time_to_regain_access` of the [usage
+//! headers](crate::prelude::usage::RateLimitUsage), the next attempt is not sent before that time. If the wait is
+//! longer than the [max_delay](RetryPolicy::max_delay) of the policy, the
+//! error is returned instead.
+//!
+//! A POST, e.g. publishing a post, a photo or a batch, answered with a bare
+//! `429` or `5xx` status is not sent again: Facebook may have processed it
+//! before failing, and a retry could publish it twice. It is only retried
+//! when the body of the response is an error of Facebook telling it is
+//! transient.
+
+use crate::universal::errors::{ClientErr, FacebookAPiError};
+use crate::universal::usage::RateLimitUsage;
+use http::{HeaderMap, Method, Response};
+use rand::Rng;
+use std::time::Duration;
+
+/// Facebook error codes which are worth retrying after a while.
+///
+/// See <https://developers.facebook.com/docs/graph-api/guides/error-handling>.
+pub const TRANSIENT_ERROR_CODES: [u32; 6] = [1, 2, 4, 17, 341, 368];
+
+/// How the requests failing with a transient error are retried.
+///
+/// # Example
+/// ```
+/// use facebook_api_rs::prelude::retry::RetryPolicy;
+/// use facebook_api_rs::prelude::{Client, UserToken};
+/// use std::time::Duration;
+///
+/// let retry_policy = RetryPolicy::default()
+///     .with_max_retries(5)
+///     .with_initial_delay(Duration::from_millis(200));
+///
+/// let client = Client::new(UserToken::default(), "page_token".to_string())
+///     .add_retry_policy(retry_policy);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+    multiplier: f64,
+    jitter: bool,
+    retry_connection_errors: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Retry up to 3 times, waiting 1, 2 and 4 seconds with jitter.
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: true,
+            retry_connection_errors: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy which never retries.
+    pub fn none() -> Self {
+        RetryPolicy::default().with_max_retries(0)
+    }
+
+    /// The number of times a request is sent again after the first attempt.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// The wait before the first retry.
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// The longest wait between two attempts.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// The factor applied to the wait after every attempt.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Wait a random time between half and all of the backoff, so clients
+    /// failing together do not retry together.
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Also retry the requests which could not be sent, e.g. because the
+    /// connection was reset. A request may then reach Facebook twice.
+    pub fn with_retry_connection_errors(mut self, retry_connection_errors: bool) -> Self {
+        self.retry_connection_errors = retry_connection_errors;
+        self
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    pub fn initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    pub fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    pub fn jitter(&self) -> bool {
+        self.jitter
+    }
+
+    pub fn retry_connection_errors(&self) -> bool {
+        self.retry_connection_errors
+    }
+
+    /// The wait before the retry following the given attempt, starting at 0,
+    /// without jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(attempt.min(i32::MAX as u32) as i32);
+        let delay = self.initial_delay.as_secs_f64() * factor;
+        Duration::try_from_secs_f64(delay)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Whether a response to a request sent with `method`, or the error of a
+    /// request which could not be sent, is worth retrying.
+    ///
+    /// A `429` or `5xx` status without a transient error of Facebook is only
+    /// retried for the [idempotent](Method::is_idempotent) methods.
+    pub fn is_transient(
+        &self,
+        method: &Method,
+        response: &Result<Response<String>, ClientErr>,
+    ) -> bool {
+        match response {
+            Ok(response) => {
+                let transient = serde_json::from_str::<FacebookAPiError>(response.body())
+                    .map(|error| error.error.is_transient())
+                    .unwrap_or(false);
+                let status = response.status();
+                transient
+                    || method.is_idempotent()
+                        && (status.as_u16() == 429 || status.is_server_error())
+            }
+            Err(ClientErr::HttpClient(_)) => self.retry_connection_errors,
+            Err(_) => false,
+        }
+    }
+
+    /// The wait before sending the request again, or `None` when the
+    /// response must be returned as it is.
+    pub fn retry_delay(
+        &self,
+        attempt: u32,
+        method: &Method,
+        response: &Result<Response<String>, ClientErr>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_retries || !self.is_transient(method, response) {
+            return None;
+        }
+
+        let mut delay = self.backoff(attempt);
+        if self.jitter && !delay.is_zero() {
+            delay = rand::thread_rng().gen_range(delay / 2..=delay);
+        }
+
+        match response
+            .as_ref()
+            .ok()
+            .and_then(|r| requested_wait(r.headers()))
+        {
+            Some(wait) if wait > self.max_delay => None,
+            Some(wait) => Some(delay.max(wait)),
+            None => Some(delay),
+        }
+    }
+}
+
+/// The wait asked by the `Retry-After` header, or by the
+/// `estimated_time_to_regain_access` of the usage headers.
+fn requested_wait(headers: &HeaderMap) -> Option<Duration> {
+    let retry_after = headers
+        .get("retry-after")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+
+    retry_after.max(RateLimitUsage::from_headers(headers).estimated_time_to_regain_access())
+}
+
+/// Wait for the given time without blocking the thread.
+#[cfg(feature = "reqwest")]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+/// Wait for the given time without blocking the thread.
+#[cfg(feature = "web-sys")]
+pub(crate) async fn sleep(duration: Duration) {
+    use wasm_bindgen::JsValue;
+
+    let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let scheduled = web_sys::window().map(|window| {
+            window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis)
+        });
+        if !matches!(scheduled, Some(Ok(_))) {
+            let _ = resolve.call0(&JsValue::NULL);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::mock_server::{MockGraphServer, MockResponse};
+    use crate::prelude::TokenLiveType;
+
+    fn response(status: u16, body: &str) -> Result<Response<String>, ClientErr> {
+        Ok(Response::builder()
+            .status(status)
+            .body(body.to_string())
+            .unwrap())
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default()
+            .with_initial_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(5));
+
+        assert_eq!(policy.backoff(0), Duration::from_secs(1));
+        assert_eq!(policy.backoff(1), Duration::from_secs(2));
+        assert_eq!(policy.backoff(2), Duration::from_secs(4));
+        assert_eq!(policy.backoff(3), Duration::from_secs(5));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        let policy = RetryPolicy::default().with_jitter(false);
+        let transient = r#"{"error":{"message":"Service temporarily unavailable","code":2}}"#;
+        let flagged = r#"{"error":{"message":"Try later","code":100,"is_transient":true}}"#;
+        let invalid_token = r#"{"error":{"message":"Invalid OAuth access token.","code":190}}"#;
+
+        let get = &Method::GET;
+        assert!(policy.is_transient(get, &response(400, transient)));
+        assert!(policy.is_transient(get, &response(400, flagged)));
+        assert!(policy.is_transient(get, &response(503, "")));
+        assert!(policy.is_transient(&Method::DELETE, &response(429, "")));
+        assert!(!policy.is_transient(get, &response(400, invalid_token)));
+        assert!(!policy.is_transient(get, &response(200, "{}")));
+        assert!(!policy.is_transient(get, &Err(ClientErr::HttpClient("reset".to_string()))));
+
+        // A POST may have been processed before a bare error status.
+        let post = &Method::POST;
+        assert!(!policy.is_transient(post, &response(500, "")));
+        assert!(!policy.is_transient(post, &response(429, "")));
+        assert!(policy.is_transient(post, &response(503, transient)));
+
+        assert_eq!(
+            policy.retry_delay(1, get, &response(400, transient)),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(policy.retry_delay(3, get, &response(400, transient)), None);
+    }
+
+    #[test]
+    fn test_rate_limit_headers() {
+        let policy = RetryPolicy::default()
+            .with_jitter(false)
+            .with_max_delay(Duration::from_secs(300));
+        let throttled = |name: &str, value: &str| {
+            Ok(Response::builder()
+                .status(429)
+                .header(name, value)
+                .body("".to_string())
+                .unwrap())
+        };
+
+        assert_eq!(
+            policy.retry_delay(0, &Method::GET, &throttled("Retry-After", "30")),
+            Some(Duration::from_secs(30))
+        );
+        let usage =
+            r#"{"1234":[{"type":"pages","call_count":100,"estimated_time_to_regain_access":2}]}"#;
+        assert_eq!(
+            policy.retry_delay(
+                0,
+                &Method::GET,
+                &throttled("X-Business-Use-Case-Usage", usage)
+            ),
+            Some(Duration::from_secs(120))
+        );
+        // Longer than the max delay of the policy.
+        assert_eq!(
+            policy.retry_delay(0, &Method::GET, &throttled("Retry-After", "3600")),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connection_retries_transient_errors() {
+        let server = MockGraphServer::start().unwrap();
+        let retry_policy = RetryPolicy::default().with_initial_delay(Duration::from_millis(1));
+        let pages = || {
+            server
+                .client("page_token".to_string())
+                .add_retry_policy(retry_policy.clone())
+                .accounts(TokenLiveType::LONGLIVE)
+                .pages_by_me()
+        };
+
+        let unavailable =
+            MockResponse::error(500, 2, "OAuthException", "Service temporarily unavailable");
+        server.mock_once("GET", "/me/accounts", unavailable.clone());
+        server.mock_once("GET", "/me/accounts", unavailable);
+        assert!(pages().await.is_ok());
+        assert_eq!(server.requests().len(), 3);
+
+        server.reset();
+        server.mock_once(
+            "GET",
+            "/me/accounts",
+            MockResponse::error(400, 190, "OAuthException", "Invalid OAuth access token."),
+        );
+        assert!(pages().await.is_err());
+        assert_eq!(server.requests().len(), 1);
+
+        server.reset();
+        server.mock(
+            "POST",
+            "/1234/feed",
+            MockResponse::json(500, serde_json::json!("Internal Server Error")),
+        );
+        let post = server
+            .client("page_token".to_string())
+            .add_retry_policy(retry_policy.clone())
+            .node("1234")
+            .edge("feed")
+            .param("message", "Hello")
+            .post::<serde_json::Value>()
+            .await;
+        assert!(post.is_err());
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -0,0 +1,233 @@
+//! Redaction of access tokens and app secrets.
+//!
+//! The access tokens, the app secret and the other credentials handled by the
+//! crate are kept in a [Secret](Secret), whose `Debug` and `Display` never
+//! print the value, so they do not end up in logs by accident. The errors
+//! returned by a [GenericClientConnection](crate::prelude::GenericClientConnection)
+//! are also [redacted](redact) before they reach the caller.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use url::form_urlencoded;
+
+/// The text printed in place of a secret.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Parameters whose values are credentials, and which are redacted from the
+/// error messages and scrubbed from the cassettes.
+pub const SENSITIVE_PARAMETERS: [&str; 7] = [
+    "access_token",
+    "input_token",
+    "fb_exchange_token",
+    "client_secret",
+    "appsecret_proof",
+    "code",
+    "upload_session_id",
+];
+
+/// A credential, e.g. an access token or an app secret, which is not printed
+/// by `Debug` and `Display`.
+///
+/// # Example
+/// ```
+/// use facebook_api_rs::prelude::secret::Secret;
+///
+/// let token = Secret::new("EAAB...");
+/// assert_eq!(format!("{}", token), "[REDACTED]");
+/// assert_eq!(format!("{:?}", token), "Secret([REDACTED])");
+/// assert_eq!(token.expose_secret(), "EAAB...");
+/// ```
+#[derive(Deserialize, Serialize, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new<S: Into<String>>(secret: S) -> Self {
+        Secret(secret.into())
+    }
+
+    /// The value of the secret. Take care not to log it.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    /// The value of the secret. Take care not to log it.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Secret(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Secret(secret.to_string())
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            f.write_str("Secret(\"\")")
+        } else {
+            write!(f, "Secret({})", REDACTED)
+        }
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Remove the given secrets, raw or url encoded, and the values of the
+/// [sensitive parameters](SENSITIVE_PARAMETERS) from a text, e.g. an error
+/// message showing the url of a request.
+///
+/// # Example
+/// ```
+/// use facebook_api_rs::prelude::secret::redact;
+///
+/// let message = "error sending request for url (https://graph.facebook.com/me?access_token=EAAB)";
+/// assert_eq!(
+///     redact(message, &[]),
+///     "error sending request for url (https://graph.facebook.com/me?access_token=[REDACTED])"
+/// );
+/// assert_eq!(redact("invalid key: my_secret", &["my_secret"]), "invalid key: [REDACTED]");
+/// ```
+pub fn redact(text: &str, secrets: &[&str]) -> String {
+    let mut redacted = replace_parameters(text, REDACTED);
+    for secret in secrets.iter().filter(|secret| !secret.is_empty()) {
+        redacted = redacted.replace(secret, REDACTED);
+        let encoded: String = form_urlencoded::byte_serialize(secret.as_bytes()).collect();
+        if encoded != *secret {
+            redacted = redacted.replace(&encoded, REDACTED);
+        }
+    }
+    redacted
+}
+
+/// Replace the values of the [sensitive parameters](SENSITIVE_PARAMETERS)
+/// found in `name=value` pairs of a text.
+pub(crate) fn replace_parameters(text: &str, replacement: &str) -> String {
+    let mut replaced = text.to_string();
+    for name in SENSITIVE_PARAMETERS {
+        let pattern = format!("{}=", name);
+        let mut search_from = 0;
+        while let Some(found) = replaced[search_from..].find(&pattern) {
+            let value_start = search_from + found + pattern.len();
+            // Only whole parameter names, e.g. not "my_access_token=".
+            let is_parameter = replaced[..search_from + found]
+                .chars()
+                .next_back()
+                .map_or(true, |c| !(c.is_alphanumeric() || c == '_'));
+            let value_end = replaced[value_start..]
+                .find(['&', '"', '\'', ',', ';', '}', ')', ' ', '\n'])
+                .map_or(replaced.len(), |end| value_start + end);
+            if is_parameter {
+                replaced.replace_range(value_start..value_end, replacement);
+                search_from = value_start + replacement.len();
+            } else {
+                search_from = value_end;
+            }
+        }
+    }
+    replaced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::errors::ClientErr;
+    use crate::prelude::mock_server::{MockGraphServer, MockResponse};
+    use crate::prelude::{BatchItems, TokenLiveType};
+
+    #[test]
+    fn test_secret_is_not_printed() {
+        let secret = Secret::from("EAAB_token");
+        assert!(!format!("{:?} {}", secret, secret).contains("EAAB_token"));
+        assert_eq!(format!("{:?}", Secret::default()), "Secret(\"\")");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"EAAB_token\"");
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("url=https://x/?code=abc&fields=id", &[]),
+            "url=https://x/?code=[REDACTED]&fields=id"
+        );
+        assert_eq!(
+            redact("key a+b/c, encoded a%2Bb%2Fc", &["a+b/c"]),
+            "key [REDACTED], encoded [REDACTED]"
+        );
+        assert_eq!(
+            redact("my_access_token=keep", &[""]),
+            "my_access_token=keep"
+        );
+    }
+
+    #[test]
+    fn test_redact_client_error() {
+        let error = ClientErr::HttpClient(
+            "error sending request for url (https://graph.facebook.com/me?access_token=EAAB)"
+                .to_string(),
+        )
+        .redact(&[]);
+        assert!(!error.to_string().contains("EAAB"));
+    }
+
+    #[tokio::test]
+    async fn test_tokens_are_not_sent_in_the_url() {
+        let server = MockGraphServer::start().unwrap();
+        server.mock_once(
+            "GET",
+            "/me/accounts",
+            MockResponse::error(400, 190, "OAuthException", "Invalid token EAAB_token"),
+        );
+        let client = || {
+            server
+                .client("EAAB_token".to_string())
+                .add_app_secret("APP_SECRET".to_string())
+        };
+
+        let error = client()
+            .accounts(TokenLiveType::LONGLIVE)
+            .pages_by_me()
+            .await
+            .unwrap_err();
+        assert!(!error.to_string().contains("EAAB_token"));
+        assert!(!format!("{:?}", client()).contains("EAAB_token"));
+        assert!(!format!("{:?}", client()).contains("APP_SECRET"));
+
+        let batch_items = BatchItems::default().add_batch_item_with_access_token(
+            "GET",
+            "me",
+            "item_token".to_string(),
+        );
+        client()
+            .batch_request("1234".to_string())
+            .request(batch_items)
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].header("authorization"),
+            Some("Bearer EAAB_token")
+        );
+        assert!(!requests[0].query.contains_key("access_token"));
+        // The batch is sent in the body, with its tokens.
+        assert_eq!(requests[1].access_token(), Some("EAAB_token"));
+        assert!(requests[1].query.is_empty());
+        assert!(requests[1].param("batch").unwrap().contains("item_token"));
+    }
+}
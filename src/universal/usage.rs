@@ -0,0 +1,460 @@
+//! Rate-limit usage reported by the Graph API.
+//!
+//! Facebook reports how much of the rate limits an app has used in the
+//! `X-App-Usage`, `X-Page-Usage`, `X-Business-Use-Case-Usage` and
+//! `X-Ad-Account-Usage` headers of its responses. The usage of a call of any
+//! API is returned with its result by
+//! [Client::with_usage](crate::prelude::Client::with_usage), the usage of a
+//! response by the `*_with_usage` methods, e.g.
+//! [get_with_usage](crate::prelude::GenericClientConnection::get_with_usage),
+//! and the usage of an error by
+//! [ErrorResponse::rate_limit_usage](crate::prelude::errors::ErrorResponse::rate_limit_usage).
+//!
+//! Every [GenericClientConnection](crate::prelude::GenericClientConnection)
+//! also keeps the usage of the last response it got, from any request of any
+//! of its clones, see
+//! [rate_limit_usage](crate::prelude::GenericClientConnection::rate_limit_usage),
+//! and can delay the next requests with a [Throttle](Throttle) when the usage
+//! gets near 100%.
+//!
+//! For more information check [facebook rate limiting](https://developers.facebook.com/docs/graph-api/overview/rate-limiting).
+
+use http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The usage of the `X-App-Usage` and `X-Page-Usage` headers, in percent of
+/// the limits.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppUsage {
+    #[serde(default)]
+    pub call_count: u32,
+    #[serde(default)]
+    pub total_cputime: u32,
+    #[serde(default)]
+    pub total_time: u32,
+    /// Minutes before the calls are not throttled anymore. Only sent by the
+    /// `X-Page-Usage` header.
+    #[serde(default)]
+    pub estimated_time_to_regain_access: Option<u64>,
+}
+
+impl AppUsage {
+    /// The highest of the usages, in percent.
+    pub fn max_percent(&self) -> f64 {
+        f64::from(self.call_count.max(self.total_cputime).max(self.total_time))
+    }
+}
+
+/// The usage of a business object, e.g. a Page or an Instagram account, from
+/// the `X-Business-Use-Case-Usage` header.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct BusinessUseCaseUsage {
+    /// The kind of rate limit, e.g. "pages" or "instagram".
+    #[serde(default)]
+    pub r#type: String,
+    #[serde(default)]
+    pub call_count: u32,
+    #[serde(default)]
+    pub total_cputime: u32,
+    #[serde(default)]
+    pub total_time: u32,
+    /// Minutes before the calls are not throttled anymore.
+    #[serde(default)]
+    pub estimated_time_to_regain_access: u64,
+}
+
+impl BusinessUseCaseUsage {
+    /// The highest of the usages, in percent.
+    pub fn max_percent(&self) -> f64 {
+        f64::from(self.call_count.max(self.total_cputime).max(self.total_time))
+    }
+}
+
+/// The usage of the `X-Ad-Account-Usage` header.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct AdAccountUsage {
+    /// The usage of the ad account, in percent.
+    #[serde(default)]
+    pub acc_id_util_pct: f64,
+    /// Seconds before the usage is reset.
+    #[serde(default)]
+    pub reset_time_duration: u64,
+    #[serde(default)]
+    pub ads_api_access_tier: Option<String>,
+}
+
+/// The rate-limit usage reported by the headers of a response.
+///
+/// # Example
+/// ```
+/// use facebook_api_rs::prelude::usage::RateLimitUsage;
+/// use http::HeaderMap;
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert(
+///     "x-app-usage",
+///     r#"{"call_count":28,"total_time":25,"total_cputime":96}"#.parse().unwrap(),
+/// );
+///
+/// let usage = RateLimitUsage::from_headers(&headers);
+/// assert_eq!(usage.max_percent(), 96.0);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitUsage {
+    pub app: Option<AppUsage>,
+    pub page: Option<AppUsage>,
+    /// The usage of every business object, by object id.
+    pub business_use_case: HashMap<String, Vec<BusinessUseCaseUsage>>,
+    pub ad_account: Option<AdAccountUsage>,
+}
+
+impl RateLimitUsage {
+    /// Parse the usage headers. A header which is missing or can not be
+    /// parsed is left empty.
+    pub fn from_headers(headers: &HeaderMap) -> RateLimitUsage {
+        fn parse<T: serde::de::DeserializeOwned>(headers: &HeaderMap, name: &str) -> Option<T> {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| serde_json::from_str(value).ok())
+        }
+
+        RateLimitUsage {
+            app: parse(headers, "x-app-usage"),
+            page: parse(headers, "x-page-usage"),
+            business_use_case: parse(headers, "x-business-use-case-usage").unwrap_or_default(),
+            ad_account: parse(headers, "x-ad-account-usage"),
+        }
+    }
+
+    /// Whether the response had none of the usage headers.
+    pub fn is_empty(&self) -> bool {
+        self.app.is_none()
+            && self.page.is_none()
+            && self.business_use_case.is_empty()
+            && self.ad_account.is_none()
+    }
+
+    /// The highest usage reported by any of the headers, in percent.
+    pub fn max_percent(&self) -> f64 {
+        let app = self
+            .app
+            .iter()
+            .chain(self.page.iter())
+            .map(AppUsage::max_percent);
+        let business_use_case = self
+            .business_use_case
+            .values()
+            .flatten()
+            .map(BusinessUseCaseUsage::max_percent);
+        let ad_account = self.ad_account.iter().map(|usage| usage.acc_id_util_pct);
+
+        app.chain(business_use_case)
+            .chain(ad_account)
+            .fold(0.0, f64::max)
+    }
+
+    /// The longest time before the calls are not throttled anymore, if any
+    /// of the headers reports one.
+    pub fn estimated_time_to_regain_access(&self) -> Option<Duration> {
+        let page_minutes = self
+            .page
+            .iter()
+            .filter_map(|usage| usage.estimated_time_to_regain_access);
+        let business_minutes = self
+            .business_use_case
+            .values()
+            .flatten()
+            .map(|usage| usage.estimated_time_to_regain_access);
+        let minutes = page_minutes.chain(business_minutes).max().unwrap_or(0);
+        let ad_account_seconds = self
+            .ad_account
+            .as_ref()
+            .map_or(0, |usage| usage.reset_time_duration);
+
+        let wait = Duration::from_secs(minutes * 60).max(Duration::from_secs(ad_account_seconds));
+        (!wait.is_zero()).then_some(wait)
+    }
+}
+
+/// Delay the requests of a connection when the reported usage gets near the
+/// rate limits, instead of getting blocked by Facebook.
+///
+/// Before every request the usage of the last response is checked:
+///
+/// * if it reports an `estimated_time_to_regain_access`, the request waits for
+///   it, up to [max_delay](Throttle::max_delay)
+/// * otherwise, if the usage is above the [threshold](Throttle::threshold),
+///   the request waits in proportion of how close to 100% the usage is, up to
+///   `max_delay` at 100%
+///
+/// The time elapsed since that response was received is deducted from the
+/// wait. The retries of a request are not delayed by the throttle, they
+/// already wait as long as the response asks, see
+/// [RetryPolicy](crate::prelude::retry::RetryPolicy).
+///
+/// # Example
+/// ```
+/// use facebook_api_rs::prelude::usage::Throttle;
+/// use facebook_api_rs::prelude::{Client, UserToken};
+///
+/// let client = Client::new(UserToken::default(), "page_token".to_string())
+///     .add_throttle(Throttle::default().with_threshold(80.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Throttle {
+    threshold: f64,
+    max_delay: Duration,
+}
+
+impl Default for Throttle {
+    /// Start delaying the requests at 90% of usage, waiting up to 5 minutes.
+    fn default() -> Self {
+        Throttle {
+            threshold: 90.0,
+            max_delay: Duration::from_secs(300),
+        }
+    }
+}
+
+impl Throttle {
+    /// The usage, in percent, from which the requests are delayed.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// The longest wait before a request.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    pub fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    /// The wait before the next request, given the usage of the last
+    /// response, received `elapsed` ago.
+    pub fn delay(&self, usage: &RateLimitUsage, elapsed: Duration) -> Option<Duration> {
+        let wait = match usage.estimated_time_to_regain_access() {
+            Some(wait) => wait.min(self.max_delay),
+            None => {
+                let percent = usage.max_percent();
+                if percent < self.threshold {
+                    return None;
+                }
+                let ratio = if self.threshold >= 100.0 {
+                    1.0
+                } else {
+                    ((percent - self.threshold) / (100.0 - self.threshold)).clamp(0.0, 1.0)
+                };
+                self.max_delay.mul_f64(ratio)
+            }
+        };
+        let wait = wait.saturating_sub(elapsed);
+        (!wait.is_zero()).then_some(wait)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::mock_server::{MockGraphServer, MockResponse};
+    use crate::prelude::TokenLiveType;
+    use serde_json::json;
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(
+                http::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        headers
+    }
+
+    #[test]
+    fn test_parse_usage_headers() {
+        let usage = RateLimitUsage::from_headers(&headers(&[
+            (
+                "X-App-Usage",
+                r#"{"call_count":10,"total_time":25,"total_cputime":5}"#,
+            ),
+            (
+                "X-Business-Use-Case-Usage",
+                r#"{"1234":[{"type":"pages","call_count":95,"total_cputime":20,"total_time":30,"estimated_time_to_regain_access":3}]}"#,
+            ),
+            (
+                "X-Ad-Account-Usage",
+                r#"{"acc_id_util_pct":9.67,"reset_time_duration":0,"ads_api_access_tier":"standard_access"}"#,
+            ),
+            ("X-Page-Usage", "not json"),
+        ]));
+
+        assert_eq!(usage.app.as_ref().map(|app| app.total_time), Some(25));
+        assert_eq!(usage.business_use_case["1234"][0].r#type, "pages");
+        assert_eq!(usage.page, None);
+        assert_eq!(usage.max_percent(), 95.0);
+        assert_eq!(
+            usage.estimated_time_to_regain_access(),
+            Some(Duration::from_secs(180))
+        );
+        assert!(RateLimitUsage::from_headers(&HeaderMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_throttle_delay() {
+        let throttle = Throttle::default()
+            .with_threshold(80.0)
+            .with_max_delay(Duration::from_secs(100));
+        let usage = |call_count: u32| RateLimitUsage {
+            app: Some(AppUsage {
+                call_count,
+                ..AppUsage::default()
+            }),
+            ..RateLimitUsage::default()
+        };
+
+        let now = Duration::ZERO;
+        assert_eq!(throttle.delay(&usage(50), now), None);
+        assert_eq!(
+            throttle.delay(&usage(90), now),
+            Some(Duration::from_secs(50))
+        );
+        assert_eq!(
+            throttle.delay(&usage(120), now),
+            Some(Duration::from_secs(100))
+        );
+
+        // The time elapsed since the usage was received is deducted.
+        let regain_access = RateLimitUsage {
+            page: Some(AppUsage {
+                estimated_time_to_regain_access: Some(1),
+                ..AppUsage::default()
+            }),
+            ..RateLimitUsage::default()
+        };
+        assert_eq!(
+            throttle.delay(&regain_access, Duration::from_secs(30)),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            throttle.delay(&regain_access, Duration::from_secs(60)),
+            None
+        );
+        assert_eq!(
+            throttle.delay(&usage(90), Duration::from_secs(20)),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connection_keeps_the_last_usage() {
+        let server = MockGraphServer::start().unwrap();
+        server.mock(
+            "GET",
+            "/me/accounts",
+            MockResponse::ok(json!({ "data": [] })).with_header(
+                "X-App-Usage",
+                r#"{"call_count":42,"total_time":1,"total_cputime":1}"#,
+            ),
+        );
+        let client = server.client("page_token".to_string());
+        let connection = client.http_connection().clone();
+        assert_eq!(connection.rate_limit_usage(), None);
+
+        client
+            .accounts(TokenLiveType::LONGLIVE)
+            .pages_by_me()
+            .await
+            .unwrap();
+
+        let usage = connection.rate_limit_usage().unwrap();
+        assert_eq!(usage.max_percent(), 42.0);
+    }
+
+    #[tokio::test]
+    async fn test_usage_is_returned_with_the_response() {
+        let server = MockGraphServer::start().unwrap();
+        server.mock(
+            "GET",
+            "/1234",
+            MockResponse::ok(json!({ "id": "1234" })).with_header(
+                "X-Page-Usage",
+                r#"{"call_count":12,"total_time":1,"total_cputime":1}"#,
+            ),
+        );
+        server.mock(
+            "GET",
+            "/5678",
+            MockResponse::error(400, 32, "OAuthException", "Page request limit reached")
+                .with_header(
+                    "X-Page-Usage",
+                    r#"{"call_count":100,"total_time":1,"total_cputime":1}"#,
+                ),
+        );
+        let client = || {
+            server
+                .client("page_token".to_string())
+                .add_retry_policy(crate::prelude::retry::RetryPolicy::none())
+        };
+
+        let (node, usage) = client()
+            .node("1234")
+            .get_with_usage::<serde_json::Value>()
+            .await
+            .unwrap();
+        assert_eq!(node["id"], "1234");
+        assert_eq!(usage.page.map(|page| page.call_count), Some(12));
+
+        let error = client()
+            .node("5678")
+            .get_with_usage::<serde_json::Value>()
+            .await
+            .unwrap_err();
+        let usage = error.response().unwrap().rate_limit_usage();
+        assert_eq!(usage.max_percent(), 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_usage_is_returned_with_a_wrapped_api() {
+        let server = MockGraphServer::start().unwrap();
+        server.mock(
+            "GET",
+            "/1234/feed",
+            MockResponse::ok(json!({ "data": [] })).with_header(
+                "X-Page-Usage",
+                r#"{"call_count":12,"total_time":1,"total_cputime":1}"#,
+            ),
+        );
+        server.mock(
+            "GET",
+            "/5678/feed",
+            MockResponse::ok(json!({ "data": [] })).with_header(
+                "X-Page-Usage",
+                r#"{"call_count":88,"total_time":1,"total_cputime":1}"#,
+            ),
+        );
+        let client = server.client("page_token".to_string());
+
+        let ((_, first), (_, second)) = futures_util::future::try_join(
+            client.with_usage(|client| client.feed("1234".to_string()).get()),
+            client.with_usage(|client| client.feed("5678".to_string()).get()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(first.page.map(|page| page.call_count), Some(12));
+        assert_eq!(second.page.map(|page| page.call_count), Some(88));
+        // The usage of the last response is still shared by the client.
+        assert!(client.rate_limit_usage().is_some());
+    }
+}
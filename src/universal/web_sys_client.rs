@@ -1,23 +1,146 @@
+//! Web-sys HTTP client, for applications running in a browser.
+use crate::prelude::media_upload::progress::RequestProgress;
+use crate::universal::errors::ClientErr;
 use crate::universal::HttpClient;
 use async_trait::async_trait;
-use http::{
-    header::{HeaderMap, HeaderName, HeaderValue},
-    Method, StatusCode, Version,
-};
-
+use http::header::HeaderMap;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
 use web_sys::{
-    FormData, Request as Web_sys_Request, RequestInit, RequestMode, Response, XmlHttpRequest,
+    FormData, Headers, ProgressEvent, Request, RequestInit, RequestMode, Response, XmlHttpRequest,
 };
 
-use super::*;
-
-use crate::universal::errors::ClientErr;
-use serde::Serialize;
-
+/// An [HttpClient](HttpClient) backed by the `fetch` API of the browser.
+///
+/// The requests are sent asynchronously, so the page is not blocked while
+/// waiting for Facebook. The headers given to [new](HttpClient::new) are sent
+/// with every request, along with the headers of the request itself.
+///
+/// `fetch` can not report the progress of an upload, so the uploads with a
+/// [RequestProgress](RequestProgress) are sent with an asynchronous
+/// `XMLHttpRequest` instead.
 #[derive(Debug, Clone)]
 pub struct Web_sysClient {
     pub headers: HeaderMap,
 }
+
+impl Web_sysClient {
+    /// Send a request with `window.fetch` and read the whole response.
+    async fn fetch<B>(
+        &self,
+        request: &http::Request<B>,
+        body: Option<&JsValue>,
+    ) -> Result<http::Response<String>, ClientErr> {
+        let init = RequestInit::new();
+        init.set_method(request.method().as_str());
+        init.set_mode(RequestMode::Cors);
+
+        let headers = Headers::new().map_err(js_error)?;
+        for (name, value) in self.headers.iter().chain(request.headers()) {
+            let value = value.to_str().map_err(|e| {
+                ClientErr::HttpClient(format!("invalid value of header {}: {}", name, e))
+            })?;
+            headers.set(name.as_str(), value).map_err(js_error)?;
+        }
+        init.set_headers(&headers);
+        if let Some(body) = body {
+            init.set_body(body);
+        }
+
+        let window = web_sys::window()
+            .ok_or_else(|| ClientErr::HttpClient("no window to send the request".to_string()))?;
+        let fetch_request =
+            Request::new_with_str_and_init(&request.uri().to_string(), &init).map_err(js_error)?;
+        let response: Response = JsFuture::from(window.fetch_with_request(&fetch_request))
+            .await
+            .map_err(js_error)?
+            .dyn_into()
+            .map_err(js_error)?;
+        let content = JsFuture::from(response.text().map_err(js_error)?)
+            .await
+            .map_err(js_error)?
+            .as_string()
+            .unwrap_or_default();
+
+        let mut build = http::Response::builder().status(response.status());
+        for entry in response.headers().entries() {
+            let entry = js_sys::Array::from(&entry.map_err(js_error)?);
+            if let (Some(name), Some(value)) = (entry.get(0).as_string(), entry.get(1).as_string())
+            {
+                build = build.header(name, value);
+            }
+        }
+        build
+            .body(content)
+            .map_err(|e| ClientErr::HttpClient(format!("{}", e)))
+    }
+
+    /// Send a form with an asynchronous `XMLHttpRequest`, reporting the
+    /// `progress` events of its upload.
+    async fn send_with_progress(
+        &self,
+        request: &http::Request<FormData>,
+        progress: &RequestProgress,
+    ) -> Result<http::Response<String>, ClientErr> {
+        let xhr = XmlHttpRequest::new().map_err(js_error)?;
+        xhr.open_with_async(request.method().as_str(), &request.uri().to_string(), true)
+            .map_err(js_error)?;
+        for (name, value) in self.headers.iter().chain(request.headers()) {
+            let value = value.to_str().map_err(|e| {
+                ClientErr::HttpClient(format!("invalid value of header {}: {}", name, e))
+            })?;
+            xhr.set_request_header(name.as_str(), value)
+                .map_err(js_error)?;
+        }
+
+        let progress = progress.clone();
+        let on_progress = Closure::<dyn FnMut(ProgressEvent)>::new(move |event: ProgressEvent| {
+            if event.length_computable() {
+                progress.report(event.loaded() as u64, event.total() as u64);
+            }
+        });
+        xhr.upload()
+            .map_err(js_error)?
+            .set_onprogress(Some(on_progress.as_ref().unchecked_ref()));
+
+        // Resolved when the response is loaded, rejected on a network error.
+        let loaded = js_sys::Promise::new(&mut |resolve, reject| {
+            xhr.set_onload(Some(&resolve));
+            xhr.set_onerror(Some(&reject));
+            xhr.set_onabort(Some(&reject));
+        });
+        xhr.send_with_opt_form_data(Some(request.body()))
+            .map_err(js_error)?;
+        let result = JsFuture::from(loaded).await;
+        xhr.upload().map_err(js_error)?.set_onprogress(None);
+        drop(on_progress);
+        result.map_err(|_| ClientErr::HttpClient("the upload request failed".to_string()))?;
+
+        let mut build = http::Response::builder().status(xhr.status().map_err(js_error)?);
+        let headers = xhr.get_all_response_headers().map_err(js_error)?;
+        for line in headers.split("\r\n") {
+            if let Some((name, value)) = line.split_once(':') {
+                build = build.header(name.trim(), value.trim());
+            }
+        }
+        let content = xhr.response_text().map_err(js_error)?.unwrap_or_default();
+        build
+            .body(content)
+            .map_err(|e| ClientErr::HttpClient(format!("{}", e)))
+    }
+}
+
+/// Map an exception thrown by the browser, e.g. a network or CORS failure.
+pub(crate) fn js_error(error: JsValue) -> ClientErr {
+    let message = error
+        .dyn_ref::<js_sys::Error>()
+        .map(|error| String::from(error.message()))
+        .or_else(|| error.as_string())
+        .unwrap_or_else(|| format!("{:?}", error));
+    ClientErr::HttpClient(message)
+}
+
 #[async_trait(?Send)]
 impl HttpClient for Web_sysClient {
     fn new<U: Into<Option<HeaderMap>>>(headers: U) -> Result<Self, ClientErr> {
@@ -32,73 +155,18 @@ impl HttpClient for Web_sysClient {
         &self,
         request: http::Request<String>,
     ) -> Result<http::Response<String>, ClientErr> {
-        let method = request.method().clone();
-        let url = request.uri().to_owned().to_string();
-        let request_body = request.body().clone();
-
-        let version = request.version().clone(); //
-        let mut req_init = XmlHttpRequest::new().unwrap();
-
-        let req = match method {
-            Method::GET => req_init.open_with_async("GET", url.as_str(), false),
-            Method::POST => req_init.open_with_async("POST", url.as_str(), false),
-            m @ _ => return Err(ClientErr::HttpClient(format!("invalid method {}", m))),
-        };
-
-        req_init.send().unwrap();
-        let status_code = req_init.status().unwrap();
-        let headers = req_init.get_all_response_headers().unwrap();
-        let content_opt = req_init.response_text().unwrap();
-
-        let mut build = http::Response::builder();
-
-        if let Some(content) = content_opt.clone() {
-            build
-                .status(status_code)
-                .version(version)
-                .body(content_opt.unwrap())
-                .map_err(|e| ClientErr::HttpClient(format!("{}", e)))
-        } else {
-            Err(ClientErr::FacebookError("can`t unwrap result".to_string()))
-        }
+        let body = (!request.body().is_empty()).then(|| JsValue::from_str(request.body()));
+        self.fetch(&request, body.as_ref()).await
     }
 
     async fn video_request(
         &self,
         request: http::Request<FormData>,
     ) -> Result<http::Response<String>, ClientErr> {
-        let method = request.method().clone();
-        let url = request.uri().to_owned().to_string();
-        let request_body = request.body().clone();
-
-        let version = request.version().clone();
-
-        let mut req_init = XmlHttpRequest::new().unwrap();
-        let req = match method {
-            Method::GET => req_init.open_with_async("GET", url.as_str(), false),
-            Method::POST => req_init.open_with_async("POST", url.as_str(), false),
-            Method::DELETE => req_init.open_with_async("DELETE", url.as_str(), false),
-
-            m @ _ => return Err(ClientErr::HttpClient(format!("invalid method {}", m))),
-        };
-
-        req_init
-            .send_with_opt_form_data(Some(&request_body))
-            .unwrap();
-        let status_code = req_init.status().unwrap();
-        let headers = req_init.get_all_response_headers().unwrap();
-        let content_opt = req_init.response_text().unwrap();
-
-        let mut build = http::Response::builder();
-
-        if let Some(content) = content_opt {
-            build
-                .status(status_code)
-                .version(version)
-                .body(content)
-                .map_err(|e| ClientErr::HttpClient(format!("{}", e)))
-        } else {
-            Err(ClientErr::FacebookError("can`t unwrap result".to_string()))
+        if let Some(progress) = request.extensions().get::<RequestProgress>() {
+            return self.send_with_progress(&request, progress).await;
         }
+        let body = JsValue::from(request.body().clone());
+        self.fetch(&request, Some(&body)).await
     }
 }